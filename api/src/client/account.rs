@@ -159,7 +159,17 @@ impl Binance {
         Ok(transaction)
     }
     */
-    // Check an order's status
+    // Place an order
+    pub fn place_order(
+        &self,
+        order: OrderRequest,
+    ) -> Fallible<impl Future<Output = Fallible<Transaction>>> {
+        let params = Self::build_order(order);
+        let transaction = self.transport.signed_post(API_V3_ORDER, Some(params))?;
+        Ok(transaction)
+    }
+
+    // Cancel an order
     pub fn cancel_order(
         &self,
         symbol: &str,
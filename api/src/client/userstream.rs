@@ -4,6 +4,7 @@ use crate::{
 };
 use failure::Fallible;
 use futures::prelude::*;
+use serde_json::json;
 
 const USER_DATA_STREAM: &str = "/api/v1/userDataStream";
 
@@ -21,7 +22,7 @@ impl Binance {
     ) -> Fallible<impl Future<Output = Fallible<Success>>> {
         let success = self.transport.put(
             USER_DATA_STREAM,
-            Some(vec![("listen_key", listen_key.to_string())]),
+            Some(json! {{"listenKey": listen_key}}),
         )?;
         Ok(success)
     }
@@ -32,7 +33,7 @@ impl Binance {
     ) -> Fallible<impl Future<Output = Fallible<Success>>> {
         let success = self.transport.delete(
             USER_DATA_STREAM,
-            Some(vec![("listen_key", listen_key.to_string())]),
+            Some(json! {{"listenKey": listen_key}}),
         )?;
         Ok(success)
     }
@@ -28,12 +28,22 @@ pub type StoredStream = SplitStream<WSStream>;
 #[allow(clippy::module_name_repetitions)]
 #[derive(Default)]
 pub struct BinanceWebsocket {
+    base: Option<String>,
     subscriptions: HashMap<Subscription, usize>,
     tokens: HashMap<usize, Subscription>,
     streams: StreamUnordered<StoredStream>,
 }
 
 impl BinanceWebsocket {
+    /// Connects to another server instead of Binance, e.g. `ws://127.0.0.1:8080/ws`.
+    #[must_use]
+    pub fn with_base(base: &str) -> Self {
+        Self {
+            base: Some(base.trim_end_matches('/').to_string()),
+            ..Self::default()
+        }
+    }
+
     pub async fn subscribe(&mut self, subscription: Subscription) -> Fallible<()> {
         let sub = match subscription {
             Subscription::AggregateTrade(ref symbol) => format!("{}@aggTrade", symbol),
@@ -52,7 +62,8 @@ impl BinanceWebsocket {
 
         trace!("[Websocket] Subscribing to '{:?}'", subscription);
 
-        let endpoint = Url::parse(&format!("{}/{}", WS_URL, sub)).unwrap();
+        let base = self.base.as_deref().unwrap_or(WS_URL);
+        let endpoint = Url::parse(&format!("{}/{}", base, sub)).unwrap();
 
         let token = self
             .streams
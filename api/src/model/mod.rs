@@ -98,8 +98,8 @@ pub struct Bids {
     #[serde(with = "string_or_float")]
     pub qty: f64,
 
-    // Never serialized.
-    #[serde(skip_serializing)]
+    // Never serialized, and missing from current responses.
+    #[serde(skip_serializing, default)]
     ignore: Vec<String>,
 }
 
@@ -110,8 +110,8 @@ pub struct Asks {
     #[serde(with = "string_or_float")]
     pub qty: f64,
 
-    // Never serialized.
-    #[serde(skip_serializing)]
+    // Never serialized, and missing from current responses.
+    #[serde(skip_serializing, default)]
     ignore: Vec<String>,
 }
 
@@ -503,6 +503,11 @@ pub enum TimeInForce {
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum OrderExecType {
     New,
+    Canceled,
+    Replaced,
+    Rejected,
+    Trade,
+    Expired,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -521,6 +526,8 @@ pub enum OrderStatus {
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum OrderRejectReason {
     None,
+    #[serde(other)]
+    Other,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -246,19 +246,20 @@ pub struct AccountUpdate {
     pub event_type: String,
     #[serde(rename = "E")]
     pub event_time: u64,
-    #[serde(rename = "m")]
+    // Missing from `outboundAccountPosition` events, which only carry the changed balances.
+    #[serde(rename = "m", default)]
     pub maker_commision_rate: u64,
-    #[serde(rename = "t")]
+    #[serde(rename = "t", default)]
     pub taker_commision_rate: u64,
-    #[serde(rename = "b")]
+    #[serde(rename = "b", default)]
     pub buyer_commision_rate: u64,
-    #[serde(rename = "s")]
+    #[serde(rename = "s", default)]
     pub seller_commision_rate: u64,
-    #[serde(rename = "T")]
+    #[serde(rename = "T", default)]
     pub can_trade: bool,
-    #[serde(rename = "W")]
+    #[serde(rename = "W", default)]
     pub can_withdraw: bool,
-    #[serde(rename = "D")]
+    #[serde(rename = "D", default)]
     pub can_deposit: bool,
     #[serde(rename = "u")]
     pub last_account_update: u64,
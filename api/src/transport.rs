@@ -12,11 +12,16 @@ use serde::{de::DeserializeOwned, Serialize};
 use serde_json::{to_string, to_value, Value};
 use sha2::Sha256;
 use std::str::FromStr;
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc,
+};
 use tracing::*;
 use url::Url;
 
 const BASE: &str = "https://www.binance.com";
 const RECV_WINDOW: usize = 5000;
+const USED_WEIGHT: &str = "x-mbx-used-weight-1m";
 
 pub struct BinanceApiKey(pub String);
 
@@ -49,6 +54,8 @@ pub struct Transport {
     credential: Option<(String, String)>,
     client: reqwest::Client,
     pub recv_window: usize,
+    base: String,
+    used_weight: Arc<AtomicU32>,
 }
 
 impl Default for Transport {
@@ -63,6 +70,8 @@ impl Transport {
             credential: None,
             client: reqwest::Client::builder().build().unwrap(),
             recv_window: RECV_WINDOW,
+            base: BASE.to_string(),
+            used_weight: Arc::new(AtomicU32::new(0)),
         }
     }
 
@@ -71,6 +80,8 @@ impl Transport {
             client: reqwest::Client::builder().build().unwrap(),
             credential: Some((api_key.into(), api_secret.into())),
             recv_window: RECV_WINDOW,
+            base: BASE.to_string(),
+            used_weight: Arc::new(AtomicU32::new(0)),
         }
     }
 
@@ -182,7 +193,7 @@ impl Transport {
         Q: Serialize,
         D: Serialize,
     {
-        let url = format!("{}{}", BASE, endpoint);
+        let url = format!("{}{}", self.base, endpoint);
         let url = match params {
             Some(p) => Url::parse_with_params(&url, p.to_url_query())?,
             None => Url::parse(&url)?,
@@ -205,11 +216,12 @@ impl Transport {
         }
 
         let req = req.body(body);
+        let used_weight = self.used_weight.clone();
 
         Ok(async move {
-            Ok(req
-                .send()
-                .await?
+            let response = req.send().await?;
+            record_weight(&used_weight, response.headers());
+            Ok(response
                 .json::<BinanceResponse<_>>()
                 .await?
                 .into_result()?)
@@ -229,7 +241,7 @@ impl Transport {
         D: Serialize,
     {
         let query = params.map_or_else(Vec::new, |q| q.to_url_query());
-        let url = format!("{}{}", BASE, endpoint);
+        let url = format!("{}{}", self.base, endpoint);
         let mut url = Url::parse_with_params(&url, &query)?;
         url.query_pairs_mut()
             .append_pair("timestamp", &Utc::now().timestamp_millis().to_string());
@@ -248,17 +260,28 @@ impl Transport {
             .typed_header(headers::ContentType::form_url_encoded())
             .typed_header(BinanceApiKey(key.to_string()))
             .body(body);
+        let used_weight = self.used_weight.clone();
 
         Ok(async move {
-            Ok(req
-                .send()
-                .await?
+            let response = req.send().await?;
+            record_weight(&used_weight, response.headers());
+            Ok(response
                 .json::<BinanceResponse<_>>()
                 .await?
                 .into_result()?)
         })
     }
 
+    /// Sends all requests to another server, e.g. the spot testnet or a local mock exchange.
+    pub fn set_base(&mut self, base: &str) {
+        self.base = base.trim_end_matches('/').to_string();
+    }
+
+    /// Request weight used in the current minute, as reported by the last response.
+    pub fn used_weight(&self) -> u32 {
+        self.used_weight.load(Ordering::Relaxed)
+    }
+
     fn check_key(&self) -> Fallible<(&str, &str)> {
         match self.credential.as_ref() {
             None => Err(Error::NoApiKeySet.into()),
@@ -278,6 +301,16 @@ impl Transport {
     }
 }
 
+fn record_weight(used_weight: &AtomicU32, headers: &http::HeaderMap) {
+    if let Some(weight) = headers
+        .get(USED_WEIGHT)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+    {
+        used_weight.store(weight, Ordering::Relaxed);
+    }
+}
+
 trait ToUrlQuery: Serialize {
     fn to_url_query_string(&self) -> String {
         let vec = self.to_url_query();
@@ -1,6 +1,6 @@
 use binance_async::{
     model::websocket::{BinanceWebsocketMessage, Subscription},
-    BinanceWebsocket,
+    Binance, BinanceWebsocket,
 };
 use sqlx::PgPool;
 use std::{error::Error, time::SystemTime};
@@ -15,6 +15,12 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let pool = PgPool::new(&std::env::var("DATABASE_URL")?).await?;
     let mut timestamp: Option<i64> = None;
     let interval: i64 = std::env::var("DATA_INTERVAL")?.parse()?;
+    // Order books are only recorded for the given markets, each costs one request per second.
+    let binance = Binance::new();
+    let depth_symbols = std::env::var("DEPTH_SYMBOLS")
+        .map(|symbols| symbols.split(',').map(String::from).collect::<Vec<String>>())
+        .unwrap_or_default();
+    let depth_limit: u64 = std::env::var("DEPTH_LIMIT").map(|limit| limit.parse()).unwrap_or(Ok(20))?;
 
     // Throw away first few results for better timing.
     for _ in 0..3 {
@@ -65,8 +71,57 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 query = query.bind(timestamp.unwrap() / 1000);
             }
             query.execute(&pool).await?;
+
+            // Request the order books concurrently, without holding up the tickers.
+            for symbol in &depth_symbols {
+                tokio::spawn(record_depth(
+                    binance.clone(),
+                    pool.clone(),
+                    symbol.clone(),
+                    depth_limit,
+                    timestamp.unwrap() / 1000,
+                    interval,
+                ));
+            }
         }
     }
 
     Ok(())
 }
+
+/// Inserts the current order book of `symbol`, logging errors instead of stopping the fetcher.
+async fn record_depth(binance: Binance, pool: PgPool, symbol: String, limit: u64, timestamp: i64, interval: i64) {
+    let book = match binance.get_depth(&symbol, limit) {
+        Ok(request) => request.await,
+        Err(error) => Err(error),
+    };
+    let book = match book {
+        Ok(book) => book,
+        Err(error) => {
+            eprintln!("depth request error for {}: {}", symbol, error);
+            return;
+        }
+    };
+    let bids = book.bids.iter().map(|bid| (bid.price, bid.qty)).collect::<Vec<(f64, f64)>>();
+    let asks = book.asks.iter().map(|ask| (ask.price, ask.qty)).collect::<Vec<(f64, f64)>>();
+    let inserted = sqlx::query(&format!(
+            "
+                WITH inserted AS (
+                    INSERT INTO depths (symbol, timestamp, bids, asks)
+                    VALUES ($1, $2, $3::JSONB, $4::JSONB)
+                    RETURNING timestamp
+                )
+                DELETE FROM depths WHERE symbol = $1 AND timestamp <= (SELECT MAX(timestamp) - {} FROM inserted)
+            ",
+            interval
+        ))
+        .bind(&symbol)
+        .bind(timestamp)
+        .bind(serde_json::to_string(&bids).unwrap())
+        .bind(serde_json::to_string(&asks).unwrap())
+        .execute(&pool)
+        .await;
+    if let Err(error) = inserted {
+        eprintln!("depth insert error for {}: {}", symbol, error);
+    }
+}
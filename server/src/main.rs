@@ -10,8 +10,10 @@ use std::collections::HashMap;
 use std::time::Duration;
 use tokio::time;
 use sqlx::PgPool;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::time::SystemTime;
+use std::path::Path;
+use std::fs;
 
 static NEXT_USER_ID: AtomicUsize = AtomicUsize::new(1);
 
@@ -106,9 +108,90 @@ struct Balance {
     usdt: f64,
 }
 
+/// The part of the trader's simulation config the server needs.
+#[derive(Deserialize, Default)]
+struct SimulationConfig {
+    #[serde(default)]
+    balances: HashMap<String, f64>,
+    start: Option<i64>,
+}
+
+impl SimulationConfig {
+    fn load(path: &Path) -> SimulationConfig {
+        let content = fs::read_to_string(path).unwrap();
+        if path.extension().map_or(false, |extension| extension == "toml") {
+            toml::from_str(&content).unwrap()
+        } else {
+            serde_json::from_str(&content).unwrap()
+        }
+    }
+}
+
+/// Value of the initial balances in USDT, at the first tickers of the simulation.
+async fn start_capital(pool: &PgPool, config: &SimulationConfig, start_at: i64) -> f64 {
+    if config.balances.is_empty() {
+        return 200.0;
+    }
+
+    let mut total = 0.0;
+    for (symbol, balance) in &config.balances {
+        let value = sqlx::query!(
+            "
+                SELECT COALESCE(
+                    (
+                        SELECT value
+                        FROM tickers
+                        WHERE symbol = $1::TEXT || 'USDT'
+                        AND timestamp >= $2::BIGINT
+                        ORDER BY timestamp ASC
+                        LIMIT 1
+                    ),
+                    (
+                        SELECT 1.0 / value
+                        FROM tickers
+                        WHERE symbol = 'USDT' || $1::TEXT
+                        AND timestamp >= $2::BIGINT
+                        ORDER BY timestamp ASC
+                        LIMIT 1
+                    ),
+                    1.0
+                ) AS value
+            ",
+            symbol,
+            start_at
+            )
+            .fetch_one(pool)
+            .await
+            .unwrap()
+            .value
+            .unwrap_or(1.0);
+        total += balance * value;
+    }
+    total
+}
+
 async fn stream_data(pool: PgPool, listeners: Listeners) {
     let mut interval = time::interval(Duration::from_secs(1));
 
+    let config = std::env::var("SIMULATION_CONFIG")
+        .map(|path| SimulationConfig::load(Path::new(&path)))
+        .unwrap_or_default();
+    let start_at = match config.start {
+        Some(start) => start,
+        None => sqlx::query!(
+            "
+            SELECT timestamp
+            FROM tickers
+            ORDER BY timestamp ASC
+            LIMIT 1
+            ")
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+            .timestamp,
+    };
+    let start_capital = start_capital(&pool, &config, start_at).await;
+
     loop {
         let balances = sqlx::query_as!(
             Balance,
@@ -139,25 +222,11 @@ async fn stream_data(pool: PgPool, listeners: Listeners) {
 
         let total = balances.iter().map(|balance| balance.usdt).sum();
 
-        let start_at = sqlx::query!(
-            "
-            SELECT timestamp
-            FROM tickers
-            ORDER BY timestamp ASC
-            LIMIT 1
-            ")
-            .fetch_one(&pool)
-            .await
-            .unwrap()
-            .timestamp;
-
         let seconds_running = SystemTime::now()
             .duration_since(SystemTime::UNIX_EPOCH)
             .unwrap()
             .as_secs() as i64 - start_at;
 
-        let start_capital = 200.0;
-
         let profit_per_day = (total - start_capital) / start_capital / (seconds_running as f64 / 60.0 / 60.0 / 24.0);
 
         let data = Data {
@@ -0,0 +1,153 @@
+use super::{AssetId, Market, MarketId, Monetary};
+use crate::environments::OrderId;
+use crate::traders::Action;
+
+/// A single conversion of a cycle, selling or buying on a market.
+#[derive(Debug, Clone)]
+pub struct Leg {
+    pub market: MarketId,
+    pub action: Action,
+    pub from: AssetId,
+    pub to: AssetId,
+}
+
+impl Leg {
+    /// Converts `from` into the market's other asset.
+    pub fn new(market: MarketId, markets: &[Market], from: AssetId) -> Leg {
+        let base = markets[market.index()].get_base();
+        let quote = markets[market.index()].get_quote();
+        if from == base {
+            Leg { market, action: Action::Sell, from, to: quote }
+        } else {
+            Leg { market, action: Action::Buy, from, to: base }
+        }
+    }
+
+    /// Amount of the `to` asset obtained for one unit of the `from` asset when selling at the bid
+    /// or buying at the ask, after fees.
+    pub fn rate(&self, markets: &[Market]) -> Option<Monetary> {
+        let market = &markets[self.market.index()];
+        if market.is_halted() {
+            return None;
+        }
+        match self.action {
+            Action::Sell => market.base_to_quote(),
+            Action::Buy => market.quote_to_base(),
+        }
+    }
+}
+
+/// A cycle whose conversions multiply to more than one.
+#[derive(Debug, Clone)]
+pub struct Opportunity {
+    pub legs: [Leg; 3],
+    pub rate: Monetary,
+}
+
+/// Finds triangular cycles starting and ending at a given asset, e.g. USDT → BTC → ETH → USDT.
+///
+/// The cycles are enumerated once whenever markets are added, the rates are checked on every scan.
+pub struct ArbitrageScanner {
+    start: AssetId,
+    margin: Monetary,
+    cycles: Vec<[Leg; 3]>,
+    markets: usize,
+}
+
+impl ArbitrageScanner {
+    /// Only cycles whose rate exceeds `1 + margin` are reported.
+    pub fn new(start: AssetId, margin: Monetary) -> ArbitrageScanner {
+        ArbitrageScanner {
+            start,
+            margin,
+            cycles: Vec::new(),
+            markets: 0,
+        }
+    }
+
+    fn rebuild(&mut self, markets: &[Market]) {
+        let touches = |market: &Market, asset: AssetId| market.get_base() == asset || market.get_quote() == asset;
+
+        self.cycles.clear();
+        for (first, market) in markets.iter().enumerate() {
+            if !touches(market, self.start) {
+                continue;
+            }
+            let first = Leg::new(MarketId::new(first), markets, self.start);
+            for (second, market) in markets.iter().enumerate() {
+                if second == first.market.index() || !touches(market, first.to) {
+                    continue;
+                }
+                let second = Leg::new(MarketId::new(second), markets, first.to);
+                if second.to == self.start {
+                    continue;
+                }
+                for (third, market) in markets.iter().enumerate() {
+                    if third == second.market.index() || !touches(market, second.to) || !touches(market, self.start) {
+                        continue;
+                    }
+                    let third = Leg::new(MarketId::new(third), markets, second.to);
+                    self.cycles.push([first.clone(), second.clone(), third]);
+                }
+            }
+        }
+        self.markets = markets.len();
+    }
+
+    /// Returns all profitable cycles, the most profitable first.
+    pub fn scan(&mut self, markets: &[Market]) -> Vec<Opportunity> {
+        if self.markets != markets.len() {
+            self.rebuild(markets);
+        }
+
+        let mut opportunities = Vec::new();
+        for legs in &self.cycles {
+            let mut rate = 1.0;
+            for leg in legs.iter() {
+                rate *= leg.rate(markets).unwrap_or(0.0);
+            }
+            if rate > 1.0 + self.margin {
+                opportunities.push(Opportunity {
+                    legs: legs.clone(),
+                    rate,
+                });
+            }
+        }
+        opportunities.sort_by(|a, b| b.rate.total_cmp(&a.rate));
+        opportunities
+    }
+}
+
+/// Progress of an opportunity that is being executed, one leg at a time.
+pub struct Execution {
+    pub opportunity: Opportunity,
+    /// Index of the leg whose order is open.
+    pub leg: usize,
+    /// Amount of the current leg's `from` asset that is converted.
+    pub amount: Monetary,
+    /// Order of the current leg once it is placed, and when it was placed.
+    pub order: Option<(OrderId, i64)>,
+    /// Amount of the start asset the execution began with.
+    pub invested: Monetary,
+}
+
+#[tokio::test]
+async fn test_scan() {
+    let usdt = AssetId::new(0);
+    let btc = AssetId::new(1);
+    let eth = AssetId::new(2);
+    let mut btc_usdt = Market::new((String::from("BTC"), String::from("USDT")), btc, usdt);
+    btc_usdt.set_value(10000.0);
+    let mut eth_btc = Market::new((String::from("ETH"), String::from("BTC")), eth, btc);
+    eth_btc.set_value(0.02);
+    let mut eth_usdt = Market::new((String::from("ETH"), String::from("USDT")), eth, usdt);
+    eth_usdt.set_value(201.0);
+    let markets = vec![btc_usdt, eth_btc, eth_usdt];
+
+    let mut scanner = ArbitrageScanner::new(usdt, 0.001);
+    let opportunities = scanner.scan(&markets);
+    assert_eq!(opportunities.len(), 1);
+    let legs = &opportunities[0].legs;
+    assert_eq!((legs[0].to, legs[1].to, legs[2].to), (btc, eth, usdt));
+    assert!(opportunities[0].rate > 1.001 && opportunities[0].rate < 1.003);
+}
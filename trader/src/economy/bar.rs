@@ -0,0 +1,90 @@
+use super::Monetary;
+use serde::{Deserialize, Serialize};
+
+/// Open, high, low and close value of a market over an interval, and the volume traded in it.
+/// The best bid and ask are those at the close, if the environment reports them.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Bar {
+    pub open: Monetary,
+    pub high: Monetary,
+    pub low: Monetary,
+    pub close: Monetary,
+    pub volume: Monetary,
+    pub bid: Option<Monetary>,
+    pub ask: Option<Monetary>,
+}
+
+impl Bar {
+    /// A bar of a single value, e.g. of a market whose value didn't change in the interval.
+    pub fn flat(value: Monetary) -> Bar {
+        Bar {
+            open: value,
+            high: value,
+            low: value,
+            close: value,
+            volume: 0.0,
+            bid: None,
+            ask: None,
+        }
+    }
+
+    /// Adds a later value, and the volume traded since the previous one.
+    pub fn update(&mut self, value: Monetary, volume: Monetary) {
+        self.high = self.high.max(value);
+        self.low = self.low.min(value);
+        self.close = value;
+        self.volume += volume;
+    }
+
+    /// Aggregates timestamped values into the bars of `interval` seconds they close in,
+    /// a bar closing at a multiple of the interval. Intervals without values have no bar.
+    pub fn resample(values: &[(i64, Monetary)], interval: i64) -> Vec<Bar> {
+        let interval = interval.max(1);
+        let mut bars = Vec::new();
+        let mut bar = Vec::new();
+        let mut current = None;
+        for (timestamp, value) in values {
+            let close = (timestamp + interval - 1).div_euclid(interval);
+            if current != Some(close) {
+                bars.extend(Bar::aggregate(&bar));
+                bar.clear();
+                current = Some(close);
+            }
+            bar.push(*value);
+        }
+        bars.extend(Bar::aggregate(&bar));
+        bars
+    }
+
+    /// Aggregates consecutive values into one bar, if there are any.
+    pub fn aggregate(values: &[Monetary]) -> Option<Bar> {
+        let (first, rest) = values.split_first()?;
+        let mut bar = Bar::flat(*first);
+        for value in rest {
+            bar.update(*value, 0.0);
+        }
+        Some(bar)
+    }
+}
+
+#[tokio::test]
+async fn test_aggregate() {
+    assert_eq!(Bar::aggregate(&[]), None);
+    assert_eq!(
+        Bar::aggregate(&[3.0, 5.0, 1.0, 2.0]),
+        Some(Bar { open: 3.0, high: 5.0, low: 1.0, close: 2.0, volume: 0.0, bid: None, ask: None })
+    );
+}
+
+#[tokio::test]
+async fn test_warm_up_bars() {
+    // Bars close at multiples of the interval, however many values there are before the first one.
+    let values = [(8, 1.0), (9, 2.0), (10, 3.0), (11, 4.0), (20, 5.0), (31, 6.0), (32, 7.0)];
+    let bars = Bar::resample(&values, 10);
+    let closes = bars.iter().map(|bar| (bar.open, bar.close)).collect::<Vec<_>>();
+    assert_eq!(closes, vec![(1.0, 3.0), (4.0, 5.0), (6.0, 7.0)]);
+    assert_eq!(bars[0].high, 3.0);
+
+    // An interval of zero is taken as one bar per second.
+    assert_eq!(Bar::resample(&values, 0).len(), values.len());
+}
@@ -0,0 +1,122 @@
+use super::Monetary;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value as State;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Relative difference between a checkpointed and an actual balance that is still accepted.
+pub const BALANCE_TOLERANCE: Monetary = 0.001;
+
+/// State of the economy at a given point in time.
+///
+/// Open orders are owned by the environment, the snapshot only remembers who placed them and what they were worth,
+/// so they can be reconciled with the environment's open orders when restoring.
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    pub timestamp: i64,
+    pub balances: Vec<(String, Monetary)>,
+    pub values: Vec<(String, Monetary)>,
+    pub sleeves: Vec<SleeveState>,
+    pub risk: State,
+    #[serde(default)]
+    pub orders: Vec<OrderState>,
+}
+
+/// State of a sleeve, matched by name when restoring.
+#[derive(Serialize, Deserialize)]
+pub struct SleeveState {
+    pub name: String,
+    pub capital: Option<Monetary>,
+    pub balances: Vec<(String, Monetary)>,
+    pub slots: Vec<SlotState>,
+    #[serde(default)]
+    pub portfolio: State,
+}
+
+/// State of a sleeve's strategy on a single market, matched by symbol when restoring.
+#[derive(Serialize, Deserialize)]
+pub struct SlotState {
+    pub symbol: String,
+    pub strategy: State,
+    pub sizer: State,
+}
+
+/// An open order, matched by symbol and ID when restoring.
+#[derive(Serialize, Deserialize)]
+pub struct OrderState {
+    pub symbol: String,
+    pub order_id: u64,
+    /// Value of the order as it was submitted, in the market's quote asset.
+    pub notional: Monetary,
+    /// Name of the sleeve or `arbitrage`, `None` for the economy's own orders.
+    pub owner: Option<String>,
+    /// Whether the order is its sleeve's resting order on the market.
+    #[serde(default)]
+    pub resting: bool,
+}
+
+/// Writes the snapshot to a temporary file first, so a crash never leaves a corrupt checkpoint behind.
+/// Both the file and the rename are synced to disk before returning.
+pub fn save<T: Serialize>(path: &Path, snapshot: &T) -> io::Result<()> {
+    let temporary = path.with_extension("tmp");
+    let mut file = File::create(&temporary)?;
+    file.write_all(&serde_json::to_vec(snapshot)?)?;
+    file.sync_all()?;
+    fs::rename(temporary, path)?;
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    File::open(directory)?.sync_all()
+}
+
+/// Returns `None` if there is no checkpoint yet.
+pub fn load<T: DeserializeOwned>(path: &Path) -> io::Result<Option<T>> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+#[tokio::test]
+async fn test_snapshot() {
+    let directory = std::env::temp_dir().join(format!("checkpoint-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("checkpoint.json");
+    assert!(load::<Snapshot>(&path).unwrap().is_none());
+
+    let snapshot = Snapshot {
+        timestamp: 60,
+        balances: vec![(String::from("BTC"), 0.5), (String::from("USDT"), 100.0)],
+        values: vec![(String::from("BTCUSDT"), 10000.0)],
+        sleeves: vec![SleeveState {
+            name: String::from("rsi"),
+            capital: Some(0.5),
+            balances: vec![(String::from("USDT"), 50.0)],
+            slots: vec![SlotState {
+                symbol: String::from("BTCUSDT"),
+                strategy: serde_json::json!({ "rsi": [1.0, 2.0] }),
+                sizer: serde_json::json!(null),
+            }],
+            portfolio: State::Null,
+        }],
+        risk: serde_json::json!({ "peak": 105.0 }),
+        orders: vec![OrderState {
+            symbol: String::from("BTCUSDT"),
+            order_id: 7,
+            notional: 25.0,
+            owner: Some(String::from("rsi")),
+            resting: true,
+        }],
+    };
+    save(&path, &snapshot).unwrap();
+    let loaded: Snapshot = load(&path).unwrap().unwrap();
+    fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(serde_json::to_value(&loaded).unwrap(), serde_json::to_value(&snapshot).unwrap());
+    assert_eq!(loaded.sleeves[0].slots[0].strategy, serde_json::json!({ "rsi": [1.0, 2.0] }));
+    assert_eq!((loaded.orders[0].order_id, loaded.orders[0].notional), (7, 25.0));
+    assert!(!path.with_extension("tmp").exists());
+}
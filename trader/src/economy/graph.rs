@@ -0,0 +1,266 @@
+use super::{AssetId, Market, MarketId, Monetary};
+
+/// Maximum number of markets a conversion path may traverse.
+const MAX_HOPS: usize = 3;
+
+/// Conversion graph over all markets, used to value assets that have no direct market to each other.
+///
+/// Rates towards and from the target asset are kept in preallocated tables, one row per asset and layer,
+/// where layer `n` holds the best rates over paths of at most `n` markets.
+/// They are only updated once per evaluation tick, and then only the rows of assets close enough
+/// to a market whose value changed to be reached by a path over it.
+pub struct MarketGraph {
+    target: AssetId,
+    dirty: bool,
+    /// Markets whose value changed since the last update.
+    changed: Vec<usize>,
+    /// Markets of each asset, rebuilt when the markets change.
+    edges: Vec<Vec<usize>>,
+    affected: Vec<bool>,
+    into_target: Vec<Vec<Monetary>>,
+    from_target: Vec<Vec<Monetary>>,
+}
+
+impl MarketGraph {
+    pub fn new(target: AssetId) -> MarketGraph {
+        MarketGraph {
+            target,
+            dirty: true,
+            changed: Vec::new(),
+            edges: Vec::new(),
+            affected: Vec::new(),
+            into_target: vec![Vec::new(); MAX_HOPS + 1],
+            from_target: vec![Vec::new(); MAX_HOPS + 1],
+        }
+    }
+
+    /// Grows the tables to hold the given number of assets.
+    pub fn resize(&mut self, assets: usize) {
+        for layer in self.into_target.iter_mut().chain(self.from_target.iter_mut()) {
+            layer.resize(assets, 0.0);
+        }
+        self.affected.resize(assets, false);
+        self.dirty = true;
+    }
+
+    /// Marks all cached rates as outdated, for example after a market was added.
+    pub fn invalidate(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Marks the rates over a market as outdated after its value changed.
+    pub fn changed(&mut self, market: MarketId) {
+        self.changed.push(market.index());
+    }
+
+    /// Updates the cached rates in place if they are outdated.
+    pub fn update(&mut self, markets: &[Market]) {
+        if self.dirty {
+            self.edges.resize_with(self.affected.len(), Vec::new);
+            for edges in &mut self.edges {
+                edges.clear();
+            }
+            for (index, market) in markets.iter().enumerate() {
+                self.edges[market.get_base().index()].push(index);
+                self.edges[market.get_quote().index()].push(index);
+            }
+            for affected in &mut self.affected {
+                *affected = true;
+            }
+        } else if self.changed.is_empty() {
+            return;
+        } else {
+            self.spread_changes(markets);
+        }
+
+        propagate(&mut self.into_target, &self.edges, &self.affected, markets, self.target, true);
+        propagate(&mut self.from_target, &self.edges, &self.affected, markets, self.target, false);
+        for affected in &mut self.affected {
+            *affected = false;
+        }
+        self.changed.clear();
+        self.dirty = false;
+    }
+
+    /// Marks the assets within `MAX_HOPS - 1` markets of a changed market.
+    /// Paths over a changed market can't reach any other asset without exceeding `MAX_HOPS`.
+    fn spread_changes(&mut self, markets: &[Market]) {
+        for &index in &self.changed {
+            self.affected[markets[index].get_base().index()] = true;
+            self.affected[markets[index].get_quote().index()] = true;
+        }
+        for _ in 1..MAX_HOPS {
+            // Assets marked during the pass may already be expanded in it, which only marks more rows than needed.
+            for asset in 0..self.affected.len() {
+                if !self.affected[asset] {
+                    continue;
+                }
+                for &index in &self.edges[asset] {
+                    let market = &markets[index];
+                    self.affected[market.get_base().index()] = true;
+                    self.affected[market.get_quote().index()] = true;
+                }
+            }
+        }
+    }
+
+    /// Amount of the target asset obtained for one unit of the given asset.
+    pub fn into_target(&self, asset: AssetId) -> Monetary {
+        self.into_target[MAX_HOPS].get(asset.index()).copied().unwrap_or(0.0)
+    }
+
+    /// Amount of the given asset obtained for one unit of the target asset.
+    pub fn from_target(&self, asset: AssetId) -> Monetary {
+        self.from_target[MAX_HOPS].get(asset.index()).copied().unwrap_or(0.0)
+    }
+}
+
+/// Finds the best conversion rate between two arbitrary assets.
+/// This allocates temporary tables and is therefore not meant for the evaluation loop.
+pub fn best_rate(markets: &[Market], assets: usize, from: AssetId, to: AssetId) -> Monetary {
+    let mut graph = MarketGraph::new(to);
+    graph.resize(assets);
+    graph.update(markets);
+    graph.into_target(from)
+}
+
+/// Recomputes the rows of the affected assets, layer by layer, starting from the anchor asset.
+///
+/// If `into` is set, the result holds the amount of the anchor obtained for one unit of each asset,
+/// otherwise the amount of each asset obtained for one unit of the anchor.
+/// Rates include the market fees, so every additional hop makes a path less attractive.
+/// Each layer only extends the paths of the previous one, so no path traverses more than `MAX_HOPS` markets.
+fn propagate(layers: &mut [Vec<Monetary>], edges: &[Vec<usize>], affected: &[bool], markets: &[Market], anchor: AssetId, into: bool) {
+    for (asset, affected) in affected.iter().enumerate() {
+        if *affected {
+            layers[0][asset] = if asset == anchor.index() { 1.0 } else { 0.0 };
+        }
+    }
+
+    for hop in 1..layers.len() {
+        let (previous, current) = layers.split_at_mut(hop);
+        let (previous, current) = (&previous[hop - 1], &mut current[0]);
+        for (destination, affected) in affected.iter().enumerate() {
+            if !*affected {
+                continue;
+            }
+            let mut rate = previous[destination];
+            if destination != anchor.index() {
+                for &index in &edges[destination] {
+                    let market = &markets[index];
+                    let base = market.get_base().index();
+                    let quote = market.get_quote().index();
+                    // The rate of the edge arriving at the destination, in the direction of the table.
+                    // Markets without a positive bid or ask have no edge in that direction.
+                    let candidate = match (destination == base, into) {
+                        (true, true) => market.base_to_quote().map(|rate| previous[quote] * rate),
+                        (true, false) => market.quote_to_base().map(|rate| previous[quote] * rate),
+                        (false, true) => market.quote_to_base().map(|rate| previous[base] * rate),
+                        (false, false) => market.base_to_quote().map(|rate| previous[base] * rate),
+                    };
+                    if let Some(candidate) = candidate {
+                        rate = rate.max(candidate);
+                    }
+                }
+            }
+            current[destination] = rate;
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_multi_hop() {
+    let usdt = AssetId::new(0);
+    let btc = AssetId::new(1);
+    let xyz = AssetId::new(2);
+    let mut btc_usdt = Market::new((String::from("BTC"), String::from("USDT")), btc, usdt);
+    btc_usdt.set_value(10000.0);
+    let mut xyz_btc = Market::new((String::from("XYZ"), String::from("BTC")), xyz, btc);
+    xyz_btc.set_value(0.001);
+    let markets = vec![btc_usdt, xyz_btc];
+
+    let mut graph = MarketGraph::new(usdt);
+    graph.resize(3);
+    graph.update(&markets);
+
+    let value = graph.into_target(xyz);
+    assert!(value > 9.9 && value < 10.0);
+    let value = graph.from_target(btc);
+    assert!(value > 0.000099 && value < 0.0001);
+    assert_eq!(graph.into_target(usdt), 1.0);
+    assert!((best_rate(&markets, 3, xyz, btc) - 0.001 * 0.999).abs() < 1e-12);
+}
+
+#[tokio::test]
+async fn test_max_hops() {
+    // A chain of markets listed so that relaxing in place would reach its end in a single round.
+    let assets = (0..5).map(AssetId::new).collect::<Vec<AssetId>>();
+    let markets = (1..5)
+        .map(|index| {
+            let mut market = Market::new((format!("A{}", index), format!("A{}", index - 1)), assets[index], assets[index - 1]);
+            market.set_value(2.0);
+            market
+        })
+        .collect::<Vec<Market>>();
+
+    let mut graph = MarketGraph::new(assets[0]);
+    graph.resize(5);
+    graph.update(&markets);
+
+    assert!(graph.into_target(assets[3]) > 0.0);
+    assert_eq!(graph.into_target(assets[4]), 0.0);
+}
+
+#[tokio::test]
+async fn test_incremental() {
+    let usdt = AssetId::new(0);
+    let btc = AssetId::new(1);
+    let xyz = AssetId::new(2);
+    let mut markets = vec![
+        Market::new((String::from("BTC"), String::from("USDT")), btc, usdt),
+        Market::new((String::from("XYZ"), String::from("BTC")), xyz, btc),
+    ];
+    markets[0].set_value(10000.0);
+    markets[1].set_value(0.001);
+
+    let mut graph = MarketGraph::new(usdt);
+    graph.resize(3);
+    graph.update(&markets);
+
+    markets[0].set_value(20000.0);
+    graph.changed(MarketId::new(0));
+    graph.update(&markets);
+
+    let mut fresh = MarketGraph::new(usdt);
+    fresh.resize(3);
+    fresh.update(&markets);
+    for asset in 0..3 {
+        assert_eq!(graph.into_target(AssetId::new(asset)), fresh.into_target(AssetId::new(asset)));
+        assert_eq!(graph.from_target(AssetId::new(asset)), fresh.from_target(AssetId::new(asset)));
+    }
+}
+
+#[tokio::test]
+async fn test_bid_ask() {
+    use super::BookTicker;
+
+    let usdt = AssetId::new(0);
+    let btc = AssetId::new(1);
+    let mut markets = vec![Market::new((String::from("BTC"), String::from("USDT")), btc, usdt)];
+    markets[0].set_value(10000.0);
+    markets[0].set_book_ticker(BookTicker { bid: 9000.0, bid_quantity: 1.0, ask: 11000.0, ask_quantity: 1.0 });
+
+    // Selling BTC gets the bid, buying it pays the ask.
+    let mut graph = MarketGraph::new(usdt);
+    graph.resize(2);
+    graph.update(&markets);
+    assert!((graph.into_target(btc) - 9000.0 * 0.999).abs() < 1e-9);
+    assert!((graph.from_target(btc) - 0.999 / 11000.0).abs() < 1e-12);
+
+    // A market without an ask can still be sold into, but not bought from.
+    markets[0].set_book_ticker(BookTicker { bid: 9000.0, bid_quantity: 1.0, ask: 0.0, ask_quantity: 0.0 });
+    graph.changed(MarketId::new(0));
+    graph.update(&markets);
+    assert!(graph.into_target(btc) > 0.0);
+    assert_eq!(graph.from_target(btc), 0.0);
+}
@@ -0,0 +1,27 @@
+/// Handle to an asset of the economy, resolved once when the asset is added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AssetId(usize);
+
+impl AssetId {
+    pub(crate) fn new(index: usize) -> AssetId {
+        AssetId(index)
+    }
+
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// Handle to a market of the economy, resolved once when the market is added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MarketId(usize);
+
+impl MarketId {
+    pub(crate) fn new(index: usize) -> MarketId {
+        MarketId(index)
+    }
+
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
@@ -1,4 +1,4 @@
-use super::{Monetary, Order, symbols::{MarketSymbol, AssetSymbol}};
+use super::{AssetId, Bar, BookTicker, Monetary, Order, Stats, Trade, symbols::{MarketSymbol, AssetSymbol}};
 use binance_async::model::ExchangeFilter;
 
 const FEE: Monetary = 0.001;
@@ -6,21 +6,33 @@ const FEE: Monetary = 0.001;
 pub struct Market {
     symbol: MarketSymbol,
     value: Option<Monetary>,
-    base: usize,
-    quote: usize,
+    bar: Option<Bar>,
+    book_ticker: Option<BookTicker>,
+    last_trade: Option<Trade>,
+    stats: Option<Stats>,
+    base: AssetId,
+    quote: AssetId,
     fee: Monetary,
-    filters: Vec<ExchangeFilter>
+    filters: Vec<ExchangeFilter>,
+    active: bool,
+    halted: bool,
 }
 
 impl Market {
-    pub fn new(symbol: (String, String), base: usize, quote: usize) -> Market {
+    pub fn new(symbol: (String, String), base: AssetId, quote: AssetId) -> Market {
         Market {
             symbol: symbol.into(),
             value: None,
+            bar: None,
+            book_ticker: None,
+            last_trade: None,
+            stats: None,
             base,
             quote,
             fee: FEE,
-            filters: Vec::new()
+            filters: Vec::new(),
+            active: true,
+            halted: false,
         }
     }
 
@@ -32,20 +44,18 @@ impl Market {
         self.value
     }
 
+    /// Quote asset obtained for one unit of the base asset when selling at the bid, net of fees.
     pub fn base_to_quote(&self) -> Option<Monetary> {
-        if let Some(value) = self.value {
-            Some(value * (1.0 - self.fee))
-        } else {
-            None
-        }
+        self.get_bid()
+            .filter(|bid| *bid > 0.0)
+            .map(|bid| bid * (1.0 - self.fee))
     }
 
+    /// Base asset obtained for one unit of the quote asset when buying at the ask, net of fees.
     pub fn quote_to_base(&self) -> Option<Monetary> {
-        if let Some(value) = self.value {
-            Some((1.0 / value) * (1.0 - self.fee))
-        } else {
-            None
-        }
+        self.get_ask()
+            .filter(|ask| *ask > 0.0)
+            .map(|ask| (1.0 / ask) * (1.0 - self.fee))
     }
 
     pub fn set_value(&mut self, value: Monetary) {
@@ -53,14 +63,86 @@ impl Market {
         self.value = Some(value);
     }
 
-    pub fn get_base(&self) -> usize {
+    /// The bar of the current evaluation, if the environment aggregates bars and the value changed since the previous one.
+    pub fn get_bar(&self) -> Option<Bar> {
+        self.bar
+    }
+
+    pub fn set_bar(&mut self, bar: Bar) {
+        self.bar = Some(bar);
+    }
+
+    pub fn clear_bar(&mut self) {
+        self.bar = None;
+    }
+
+    pub fn get_book_ticker(&self) -> Option<BookTicker> {
+        self.book_ticker
+    }
+
+    pub fn set_book_ticker(&mut self, book_ticker: BookTicker) {
+        self.book_ticker = Some(book_ticker);
+    }
+
+    /// The best bid, or the value if the environment doesn't report book tickers.
+    pub fn get_bid(&self) -> Option<Monetary> {
+        self.book_ticker.map(|book_ticker| book_ticker.bid).or(self.value)
+    }
+
+    /// The best ask, or the value if the environment doesn't report book tickers.
+    pub fn get_ask(&self) -> Option<Monetary> {
+        self.book_ticker.map(|book_ticker| book_ticker.ask).or(self.value)
+    }
+
+    pub fn get_last_trade(&self) -> Option<Trade> {
+        self.last_trade
+    }
+
+    pub fn set_last_trade(&mut self, trade: Trade) {
+        self.last_trade = Some(trade);
+    }
+
+    /// Statistics of the last 24 hours, if the environment reports them.
+    pub fn get_stats(&self) -> Option<Stats> {
+        self.stats
+    }
+
+    pub fn set_stats(&mut self, stats: Stats) {
+        self.stats = Some(stats);
+    }
+
+    /// Forgets the value of a market that is no longer listed, so it isn't used for valuation anymore.
+    pub fn clear_value(&mut self) {
+        self.value = None;
+        self.book_ticker = None;
+    }
+
+    pub fn get_base(&self) -> AssetId {
         self.base
     }
 
-    pub fn get_quote(&self) -> usize {
+    pub fn get_quote(&self) -> AssetId {
         self.quote
     }
 
+    /// Inactive markets are still used for valuation, but not traded by any strategy.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn set_active(&mut self, active: bool) {
+        self.active = active;
+    }
+
+    /// Halted markets keep their strategies, but no orders are placed on them.
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    pub fn set_halted(&mut self, halted: bool) {
+        self.halted = halted;
+    }
+
     pub fn get_fee(&self) -> Monetary {
         self.fee
     }
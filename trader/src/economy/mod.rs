@@ -1,64 +1,283 @@
+mod arbitrage;
 mod asset;
+mod bar;
+mod checkpoint;
+mod graph;
+mod handles;
 mod market;
+mod risk;
+mod sleeve;
 mod symbols;
+mod ticker;
 
+pub use arbitrage::{ArbitrageScanner, Leg, Opportunity};
 pub use asset::Asset;
+pub use bar::Bar;
+pub use graph::MarketGraph;
+pub use handles::{AssetId, MarketId};
 pub use market::Market;
+pub use risk::{Alert, Rejection, RiskGuard, RiskLimits};
+pub use sleeve::{Sleeve, Slot, StrategyFactory};
 pub use symbols::{AssetSymbol, MarketSymbol};
+pub use ticker::{BookTicker, Stats, Trade};
 
 use crate::{
-    environments::{Environment, Event},
-    indicators::Indicator,
-    traders::{Order, Action, Trader},
+    environments::{Environment, Event, MarketData, OrderError, OrderId},
+    journal::{Entry, Journal, Record, Sink},
+    metrics::Metrics,
+    portfolio::{rebalance, AssetView, Decision, MarketView, PortfolioView},
+    sizers::{PositionSizer, Sizing},
+    traders::{Audit, Order, Action},
 };
-use std::collections::HashMap;
-use std::time::Duration;
+use arbitrage::Execution;
+use checkpoint::{OrderState, SleeveState, SlotState, Snapshot, BALANCE_TOLERANCE};
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use binance_async::model::Side;
-
-const REFERENCE_ASSET: &'static str = "USDT";
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::Instrument;
 
 pub type Monetary = f64;
 
-pub struct Economy<E, T>
+/// If less than this multiple of an order's quantity would remain, the whole balance is ordered instead,
+/// so no unusable dust is left behind.
+const DUST_FACTOR: Monetary = 2.0;
+
+/// Deviations from a portfolio's target weights below this fraction of its equity are not rebalanced.
+const REBALANCE_TOLERANCE: Monetary = 0.01;
+
+/// Something the economy does every `interval` seconds, e.g. recording its valuation.
+/// Evaluations may skip seconds, so the time since the last run is compared instead of the timestamp itself.
+#[derive(Debug, Clone, Copy)]
+struct Schedule {
+    interval: i64,
+    last: Option<i64>,
+}
+
+impl Schedule {
+    fn every(interval: i64) -> Schedule {
+        Schedule {
+            interval,
+            last: None,
+        }
+    }
+
+    /// Whether the interval passed since the last run. The first evaluation only starts the clock,
+    /// and intervals of zero or less never run.
+    fn due(&mut self, timestamp: i64) -> bool {
+        if self.interval <= 0 {
+            return false;
+        }
+        match self.last {
+            Some(last) if timestamp - last < self.interval => false,
+            Some(_) => {
+                self.last = Some(timestamp);
+                true
+            },
+            None => {
+                self.last = Some(timestamp);
+                false
+            },
+        }
+    }
+}
+
+/// Who placed an open order, so its fill can be booked accordingly.
+#[derive(Debug, Clone, Copy)]
+enum Owner {
+    Sleeve(usize),
+    Arbitrage,
+    Economy,
+}
+
+pub struct Economy<E, S>
 where
     E: Environment,
-    T: Trader,
+    S: PositionSizer,
 {
     environment: E,
     markets: Vec<Market>,
     assets: Vec<Asset>,
-    market_lookup: HashMap<String, usize>,
-    asset_lookup: HashMap<String, usize>,
-    traders: Vec<(T, Option<T::Indicators>)>,
-    reference_asset: usize,
+    market_lookup: HashMap<String, MarketId>,
+    asset_lookup: HashMap<String, AssetId>,
+    sleeves: Vec<Sleeve<S>>,
+    /// Owners of the open orders.
+    owners: HashMap<OrderId, Owner>,
+    /// The resting order of each sleeve on a market. A new order of the sleeve replaces it.
+    resting: HashMap<(usize, MarketId), OrderId>,
+    reference_symbol: String,
+    reference_asset: AssetId,
+    graph: MarketGraph,
+    risk: RiskGuard,
+    /// Whether the kill switch is lifted even if the checkpoint restores it.
+    resume: bool,
+    checkpoint: Option<(PathBuf, Schedule)>,
+    expected_balances: HashMap<String, Monetary>,
+    warm_up: i64,
+    universe: Option<Schedule>,
+    timestamp: i64,
+    arbitrage: Option<ArbitrageScanner>,
+    arbitrage_fraction: Option<Monetary>,
+    arbitrage_timeout: i64,
+    execution: Option<Execution>,
+    journal: Journal,
+    valuation: Schedule,
+    metrics: Option<Metrics>,
+    audit: bool,
+    initial: Option<Monetary>,
+    fills: usize,
+    settings: Option<serde_json::Value>,
+    bar_interval: i64,
+    /// Buffers of the evaluation, kept so evaluating doesn't allocate.
+    audits: Vec<(usize, MarketId, Audit)>,
+    orders: Vec<(usize, MarketId, Order)>,
 }
 
-impl<E, T> Economy<E, T>
+impl<E, S> Economy<E, S>
 where
     E: Environment,
-    T: Trader,
+    S: PositionSizer + Serialize + DeserializeOwned,
 {
-    pub fn new(environment: E) -> Economy<E, T> {
+    /// Creates a new economy that values all assets in terms of the given reference asset.
+    pub fn new(environment: E, reference: &str) -> Economy<E, S> {
+        let reference_asset = AssetId::new(0);
+        let mut asset_lookup = HashMap::new();
+        asset_lookup.insert(String::from(reference), reference_asset);
+        let mut graph = MarketGraph::new(reference_asset);
+        graph.resize(1);
+
         Economy {
             environment,
             markets: Vec::new(),
-            assets: Vec::new(),
+            assets: vec![Asset::new(String::from(reference))],
             market_lookup: HashMap::new(),
-            asset_lookup: HashMap::new(),
-            traders: Vec::new(),
-            reference_asset: 0,
+            asset_lookup,
+            sleeves: Vec::new(),
+            owners: HashMap::new(),
+            resting: HashMap::new(),
+            reference_symbol: String::from(reference),
+            reference_asset,
+            graph,
+            risk: RiskGuard::new(RiskLimits::default()),
+            resume: false,
+            checkpoint: None,
+            expected_balances: HashMap::new(),
+            warm_up: 0,
+            universe: None,
+            timestamp: 0,
+            arbitrage: None,
+            arbitrage_fraction: None,
+            arbitrage_timeout: 60,
+            execution: None,
+            journal: Journal::new(),
+            valuation: Schedule::every(3600),
+            metrics: None,
+            audit: false,
+            initial: None,
+            fills: 0,
+            settings: None,
+            bar_interval: 1,
+            audits: Vec::new(),
+            orders: Vec::new(),
         }
     }
 
-    pub async fn run(&mut self) -> Result<(), ()> {
-        self.add_asset(String::from("USDT"));
+    /// Adds a strategy with its own share of the capital. The allocations of all sleeves should not exceed one.
+    pub fn add_sleeve(&mut self, sleeve: Sleeve<S>) {
+        self.sleeves.push(sleeve);
+    }
+
+    /// Replays the given number of seconds of history through the indicators before trading starts.
+    pub fn set_warm_up(&mut self, duration: i64) {
+        self.warm_up = duration;
+    }
+
+    /// Aggregates the history of the warm-up into bars of `interval` seconds, like the environment evaluates them.
+    pub fn set_bar_interval(&mut self, interval: i64) {
+        self.bar_interval = interval;
+    }
+
+    /// Asks the environment for a new selection of markets every `interval` seconds.
+    /// New markets are added, and positions in markets that are no longer selected are closed.
+    pub fn set_universe_interval(&mut self, interval: i64) {
+        self.universe = Some(Schedule::every(interval));
+    }
+
+    /// Reports triangular cycles through the reference asset whose rate exceeds `1 + margin` on every evaluation.
+    /// With a fraction, the best cycle is executed with this fraction of the reference asset not held by any sleeve.
+    pub fn set_arbitrage(&mut self, margin: Monetary, fraction: Option<Monetary>) {
+        self.arbitrage = Some(ArbitrageScanner::new(self.reference_asset, margin));
+        self.arbitrage_fraction = fraction;
+    }
 
+    /// Cancels the order of a leg that didn't fill within `timeout` seconds and unwinds the execution, 60 by default.
+    pub fn set_arbitrage_timeout(&mut self, timeout: i64) {
+        self.arbitrage_timeout = timeout;
+    }
+
+    /// Snapshots the economy to the given file every `interval` seconds, and resumes from it on startup.
+    pub fn set_checkpoint(&mut self, path: PathBuf, interval: i64) {
+        self.checkpoint = Some((path, Schedule::every(interval)));
+    }
+
+    /// Lifts the kill switch, also if it was triggered before a restart and is restored from the checkpoint.
+    /// Drawdowns are measured from the portfolio value at the time trading resumed.
+    pub fn resume_trading(&mut self) {
+        self.resume = true;
+        self.risk.resume();
+    }
+
+    /// Replaces the portfolio-wide risk limits, which are all disabled by default.
+    pub fn set_risk_limits(&mut self, limits: RiskLimits) {
+        self.risk.set_limits(limits);
+    }
+
+    /// Adds a destination for the event journal. Without sinks, records are only emitted as `tracing` events.
+    pub fn add_sink<K>(&mut self, sink: K)
+    where
+        K: Sink + Send + 'static,
+    {
+        self.journal.add_sink(sink);
+    }
+
+    /// Records the value of the portfolio and of each sleeve every `interval` seconds.
+    pub fn set_valuation_interval(&mut self, interval: i64) {
+        self.valuation = Schedule::every(interval);
+    }
+
+    /// Collects runtime metrics of the economy and its environment.
+    pub fn set_metrics(&mut self, metrics: Metrics) {
+        self.journal.add_sink(metrics.clone());
+        self.metrics = Some(metrics);
+    }
+
+    /// Records a `Decision` in the journal whenever a trader of a strategy wants to trade,
+    /// with the indicator outputs, the decisions of the wrappers and the order that was finally submitted.
+    pub fn enable_audit(&mut self) {
+        self.audit = true;
+    }
+
+    /// Records the settings of the run in the journal when it starts.
+    pub fn set_settings(&mut self, settings: serde_json::Value) {
+        self.settings = Some(settings);
+    }
+
+    /// Runs until the environment is finished, which a live environment never is.
+    pub async fn run(&mut self) -> Result<(), ()> {
         let markets = self.environment.initialize().await?;
+        // Until the first evaluation, records carry the time the environment starts at.
+        self.timestamp = self.environment.clock().now();
+        if let Some(settings) = self.settings.take() {
+            self.journal.record(Record::new(self.timestamp, None, None, Entry::Settings { settings }));
+        }
         for market in markets {
-            self.add_market(market.symbol, market.base_asset, market.quote_asset);
+            let id = self.add_market(market.symbol, market.base_asset, market.quote_asset);
+            self.open_slots(id);
         }
 
-        println!(
+        let message = format!(
             "added {} markets: {:?}",
             self.markets.len(),
             self.markets
@@ -66,127 +285,112 @@ where
                 .map(|market| market.get_symbol().as_str())
                 .collect::<Vec<&str>>()
         );
-
-        for market in &self.markets {
-            self.traders.push((
-                T::initialize(
-                    &self.assets[market.get_base()].get_symbol(),
-                    &self.assets[market.get_quote()].get_symbol(),
-                ),
-                None,
-            ));
+        self.journal.record(Record::new(self.timestamp, None, None, Entry::Notice { message }));
+        for sleeve in &self.sleeves {
+            let message = format!("trades on {} markets", sleeve.get_slots().len());
+            self.journal.record(Record::new(self.timestamp, None, Some(sleeve.get_name()), Entry::Notice { message }));
         }
 
+        let orders = self.restore_checkpoint();
+        self.reconcile_orders(orders).await;
+        self.warm_up().await;
+        self.journal.flush().await;
+
         // Poll environment for events.
         loop {
             let event = self.environment.poll().await;
+            if let Some(metrics) = &self.metrics {
+                self.environment.report(metrics);
+            }
             match event {
                 Event::Evaluate(timestamp) => {
-                    let mut actions = Vec::new();
-                    for (market, (trader, indicator)) in
-                        self.markets.iter().zip(self.traders.iter_mut())
-                    {
-                        let action = if let Some(value) = market.get_value() {
-                            if let Some(indicator) = indicator {
-                                trader.evaluate(indicator.evaluate(value))
-                            } else {
-                                *indicator = Some(T::Indicators::initialize(value));
-                                None
-                            }
-                        } else {
-                            None
-                        };
-                        actions.push(action);
+                    let start = Instant::now();
+                    self.evaluate(timestamp)
+                        .instrument(tracing::info_span!("evaluate", timestamp))
+                        .await;
+                    if let Some(metrics) = &self.metrics {
+                        metrics.observe_evaluation(start.elapsed().as_secs_f64());
                     }
-
-                    for (market, order) in self.markets.iter().zip(actions.iter()) {
-                        if let Some(order) = order {
-                            match order {
-                                Order::Limit(Action::Buy, fraction, price) => {
-                                    let mut base_quantity = fraction
-                                        * self.total_balance()
-                                        * self.value_from_to(
-                                            REFERENCE_ASSET,
-                                            self.assets[market.get_base()].get_symbol(),
-                                        );
-                                    let quote_balance = self.assets[market.get_quote()].get_balance();
-                                    if quote_balance > 0.0 {
-                                        if quote_balance / price < 2.0 * base_quantity {
-                                            base_quantity = quote_balance / price;
-                                        }
-
-                                        println!("buy {} {:?}", market.get_symbol(), timestamp);
-                                        if let Ok(order) = market.apply_filters(Order::Limit(Action::Buy, base_quantity, *price)) {
-                                            self.environment.order(market.get_symbol(), order).await.unwrap();
-                                        }
-                                        /*
-                                        self.assets[market.get_base()]
-                                            .add_balance(sell_quantity / price * (1.0 - market.get_fee()));
-                                        self.assets[market.get_quote()].add_balance(-sell_quantity);
-                                        */
-                                    }
-                                },
-                                Order::Limit(Action::Sell, fraction, price) => {
-                                    let mut base_quantity = fraction
-                                        * self.total_balance()
-                                        * self.value_from_to(
-                                            REFERENCE_ASSET,
-                                            self.assets[market.get_base()].get_symbol(),
-                                        );
-                                    let base_balance = self.assets[market.get_base()].get_balance();
-                                    if base_balance > 0.0 {
-                                        if base_balance < 2.0 * base_quantity {
-                                            base_quantity = base_balance;
-                                        }
-                                        println!("sell {} {:?}", market.get_symbol(), timestamp);
-                                        if let Ok(order) = market.apply_filters(Order::Limit(Action::Sell, base_quantity, *price)) {
-                                            self.environment.order(market.get_symbol(), order).await.unwrap();
-                                        }
-                                        /*
-                                        self.assets[market.get_base()].add_balance(-sell_quantity);
-                                        self.assets[market.get_quote()]
-                                            .add_balance(sell_quantity * price * (1.0 - market.get_fee()));
-                                        */
-                                    }
-                                },
-                                _ => {}
+                }
+                Event::ExecutedOrder(query_order, _, fee) => {
+                    let id = if let Some(id) = self.market_lookup.get(&query_order.symbol).copied() {
+                        id
+                    } else {
+                        // E.g. a manual trade, or an order on a market that was removed since.
+                        tracing::warn!(symbol = %query_order.symbol, order_id = query_order.order_id, "executed order on an untracked market");
+                        self.risk.closed(query_order.order_id);
+                        self.owners.remove(&query_order.order_id);
+                        continue;
+                    };
+                    self.risk.closed(query_order.order_id);
+                    let owner = self.owners.remove(&query_order.order_id).unwrap_or(Owner::Economy);
+                    if let Owner::Sleeve(index) = owner {
+                        if self.resting.get(&(index, id)) == Some(&query_order.order_id) {
+                            self.resting.remove(&(index, id));
+                        }
+                    }
+                    let market = &self.markets[id.index()];
+                    let base = market.get_base();
+                    let quote = market.get_quote();
+                    let fee = fee.unwrap_or_else(|| market.get_fee());
+                    // Reported balances already include the fills, and are journaled when they are set.
+                    let estimated = !self.environment.reports_balances();
+                    if estimated {
+                        match query_order.side {
+                            Side::Buy => {
+                                self.assets[base.index()]
+                                    .add_balance(query_order.executed_qty * (1.0 - fee));
+                                self.assets[quote.index()].add_balance(-query_order.executed_qty * query_order.price);
+                            },
+                            Side::Sell => {
+                                self.assets[base.index()]
+                                    .add_balance(-query_order.executed_qty);
+                                self.assets[quote.index()].add_balance(query_order.executed_qty * query_order.price * (1.0 - fee));
+                            },
+                        }
+                    }
+                    if query_order.executed_qty > 0.0 {
+                        self.fills += 1;
+                        let strategy = match owner {
+                            Owner::Sleeve(index) => Some(self.sleeves[index].get_name()),
+                            Owner::Arbitrage => Some("arbitrage"),
+                            Owner::Economy => None,
+                        };
+                        self.journal.record(Record::new(self.timestamp, Some(query_order.symbol.as_str()), strategy, Entry::Fill {
+                            side: query_order.side.clone(),
+                            quantity: query_order.executed_qty,
+                            price: query_order.price,
+                            fee,
+                        }));
+                        if estimated {
+                            for asset in &[base, quote] {
+                                let asset = &self.assets[asset.index()];
+                                self.journal.record(Record::new(self.timestamp, None, None, Entry::Balance {
+                                    asset: asset.get_symbol().to_string(),
+                                    balance: asset.get_balance(),
+                                }));
                             }
                         }
                     }
-
-                    if timestamp % 3600 == 0 {
-                        println!("{} total: {} USDT", timestamp, self.total_balance());
-                        for asset in &self.assets {
-                            let balance = asset.get_balance();
-                            if balance > 0.0 {
-                                println!(
-                                    "{} {} = {} {}",
+                    match owner {
+                        Owner::Sleeve(index) => {
+                            let overdrawn = self.sleeves[index].executed(base, quote, &query_order.side, query_order.executed_qty, query_order.price, fee);
+                            for (asset, balance) in overdrawn {
+                                let alert = Alert::Overdrawn(
+                                    self.sleeves[index].get_name().to_string(),
+                                    self.assets[asset.index()].get_symbol().to_string(),
                                     balance,
-                                    asset.get_symbol(),
-                                    balance
-                                        * self.value_from_to(asset.get_symbol(), REFERENCE_ASSET),
-                                    REFERENCE_ASSET
                                 );
+                                self.journal.record(Record::new(self.timestamp, None, Some(self.sleeves[index].get_name()), Entry::Alert {
+                                    message: alert.to_string(),
+                                }));
+                                self.environment.alert(&alert).await;
                             }
-                        }
-                    }
-                }
-                Event::ExecutedOrder(query_order, quantity) => {
-                    let market = self.get_market(&query_order.symbol).unwrap();
-                    let base = market.get_base();
-                    let quote = market.get_quote();
-                    let fee = market.get_fee();
-                    match query_order.side {
-                        Side::Buy => {
-                            self.assets[base]
-                                .add_balance(query_order.executed_qty * (1.0 - fee));
-                            self.assets[quote].add_balance(-query_order.executed_qty * query_order.price);
                         },
-                        Side::Sell => {
-                            self.assets[base]
-                                .add_balance(-query_order.executed_qty);
-                            self.assets[quote].add_balance(query_order.executed_qty * query_order.price * (1.0 - fee));
+                        Owner::Arbitrage => {
+                            self.arbitrage_executed(query_order.order_id, query_order.executed_qty, query_order.price, fee).await;
                         },
+                        Owner::Economy => {},
                     }
                     self.environment.update_balances(self.assets
                         .iter()
@@ -197,13 +401,39 @@ where
                     ).await;
                 },
                 Event::SetMarketValue(symbol, value) => {
+                    if let Some(id) = self.market_lookup.get(&symbol).copied() {
+                        self.markets[id.index()].set_value(value);
+                        self.graph.changed(id);
+                    }
+                }
+                Event::SetMarketBar(symbol, bar) => {
+                    if let Some(market) = self.get_market_mut(&symbol) {
+                        market.set_bar(bar);
+                    }
+                }
+                Event::SetMarketBookTicker(symbol, book_ticker) => {
+                    if let Some(id) = self.market_lookup.get(&symbol).copied() {
+                        self.markets[id.index()].set_book_ticker(book_ticker);
+                        self.graph.changed(id);
+                    }
+                }
+                Event::MarketTrade(symbol, trade) => {
+                    if let Some(market) = self.get_market_mut(&symbol) {
+                        market.set_last_trade(trade);
+                    }
+                }
+                Event::SetMarketStats(symbol, stats) => {
                     if let Some(market) = self.get_market_mut(&symbol) {
-                        market.set_value(value);
+                        market.set_stats(stats);
                     }
                 }
                 Event::SetAssetBalance(symbol, balance) => {
                     if let Some(asset) = self.get_asset_mut(&symbol) {
                         asset.set_balance(balance);
+                        self.journal.record(Record::new(self.timestamp, None, None, Entry::Balance {
+                            asset: symbol,
+                            balance,
+                        }));
                         self.environment.update_balances(self.assets
                             .iter()
                             .map(|asset|
@@ -213,102 +443,1487 @@ where
                         ).await;
                     }
                 }
+                Event::MarketAdded(market) => {
+                    self.list_market(market).await;
+                }
+                Event::MarketHalted(symbol) => {
+                    if let Some(market) = self.get_market_mut(&symbol) {
+                        market.set_halted(true);
+                        self.journal.record(Record::new(self.timestamp, Some(symbol.as_str()), None, Entry::Notice {
+                            message: String::from("halted"),
+                        }));
+                        if let Err(error) = self.environment.cancel_all(&symbol).await {
+                            tracing::warn!(%error, %symbol, "canceling orders failed");
+                        }
+                    }
+                }
+                Event::MarketResumed(symbol) => {
+                    if let Some(market) = self.get_market_mut(&symbol) {
+                        if market.is_active() {
+                            market.set_halted(false);
+                            self.journal.record(Record::new(self.timestamp, Some(symbol.as_str()), None, Entry::Notice {
+                                message: String::from("resumed"),
+                            }));
+                        }
+                    }
+                }
+                Event::MarketRemoved(symbol) => {
+                    if let Some(id) = self.market_lookup.get(&symbol).copied() {
+                        self.journal.record(Record::new(self.timestamp, Some(symbol.as_str()), None, Entry::Notice {
+                            message: String::from("removed"),
+                        }));
+                        if let Err(error) = self.environment.cancel_all(&symbol).await {
+                            tracing::warn!(%error, %symbol, "canceling orders failed");
+                        }
+                        self.retire_market(id, true).await;
+                    }
+                }
+                Event::Finished => {
+                    self.record_report();
+                    self.journal.flush().await;
+                    return Ok(());
+                }
             }
+            self.journal.flush().await;
         }
     }
 
-    fn add_asset(&mut self, symbol: String) -> usize {
-        if let Some(index) = self.asset_lookup.get(&symbol) {
-            *index
+    async fn evaluate(&mut self, timestamp: i64) {
+        self.timestamp = timestamp;
+        self.graph.update(&self.markets);
+        if !self.expected_balances.is_empty() {
+            self.validate_checkpoint();
+        }
+
+        let total = self.total_balance();
+        if let Some(metrics) = &self.metrics {
+            metrics.set_portfolio_value(total);
+        }
+        if self.initial.is_none() && total > 0.0 {
+            self.initial = Some(total);
+        }
+        if let Some(alert) = self.risk.update(timestamp, total) {
+            self.journal.record(Record::new(timestamp, None, None, Entry::Alert { message: alert.to_string() }));
+            self.environment.alert(&alert).await;
+            if self.risk.get_limits().flatten {
+                self.flatten(timestamp).await;
+            }
+        }
+
+        if self.universe.as_mut().map_or(false, |universe| universe.due(timestamp)) {
+            self.update_universe().await;
+        }
+
+        if total > 0.0 {
+            for sleeve in &mut self.sleeves {
+                if !sleeve.is_funded() {
+                    sleeve.fund(self.assets.iter().map(|asset| asset.get_balance()), &self.graph);
+                }
+            }
+        }
+
+        let mut audits = std::mem::take(&mut self.audits);
+        for (index, sleeve) in self.sleeves.iter_mut().enumerate() {
+            for slot in sleeve.get_slots_mut() {
+                let market = &self.markets[slot.market.index()];
+                slot.action = if let Some(value) = market.get_value() {
+                    // Without a bar, the value didn't change or the environment evaluates every second.
+                    let mut bar = market.get_bar().unwrap_or_else(|| Bar::flat(value));
+                    if let Some(book_ticker) = market.get_book_ticker() {
+                        bar.bid = Some(book_ticker.bid);
+                        bar.ask = Some(book_ticker.ask);
+                    }
+                    slot.sizer.update(value);
+                    if self.audit {
+                        let (order, audit) = slot.strategy.evaluate_audited(&bar);
+                        if let Some(audit) = audit {
+                            audits.push((index, slot.market, audit));
+                        }
+                        order
+                    } else {
+                        slot.strategy.evaluate(&bar)
+                    }
+                } else {
+                    None
+                };
+            }
+        }
+        for sleeve in &self.sleeves {
+            for slot in sleeve.get_slots() {
+                if let Some(Order::Limit(action, fraction, price)) = &slot.action {
+                    let market = &self.markets[slot.market.index()];
+                    self.journal.record(Record::new(timestamp, Some(market.get_symbol().as_str()), Some(sleeve.get_name()), Entry::Signal {
+                        action: action.clone(),
+                        fraction: *fraction,
+                        price: *price,
+                    }));
+                }
+            }
+        }
+
+        for market in &mut self.markets {
+            market.clear_bar();
+        }
+
+        let mut orders = std::mem::take(&mut self.orders);
+        for (index, sleeve) in self.sleeves.iter().enumerate() {
+            let capital = sleeve.equity(&self.graph);
+            for slot in sleeve.get_slots() {
+                let (action, fraction, price) = if let Some(Order::Limit(action, fraction, price)) = &slot.action {
+                    (action, *fraction, *price)
+                } else {
+                    continue;
+                };
+
+                let market = &self.markets[slot.market.index()];
+                let base = market.get_base();
+                let quote = market.get_quote();
+                let mut base_quantity = slot.sizer.size(&Sizing {
+                    action: action.clone(),
+                    fraction,
+                    price,
+                    total: capital,
+                    reference_to_base: self.value_from_to(self.reference_asset, base),
+                    stop: slot.strategy.stop(),
+                });
+                // Quantity of the base asset the sleeve can afford.
+                let available = match action {
+                    Action::Buy => sleeve.get_balance(quote) / price,
+                    Action::Sell => sleeve.get_balance(base),
+                };
+                if available < DUST_FACTOR * base_quantity {
+                    base_quantity = available;
+                }
+                orders.push((index, slot.market, Order::Limit(action.clone(), base_quantity, price)));
+            }
+        }
+        for (index, market, order) in orders.drain(..) {
+            let outcome = self.submit(index, market, order, timestamp, total).await;
+            if let Some(position) = audits.iter().position(|(owner, id, _)| *owner == index && *id == market) {
+                let (_, _, audit) = audits.swap_remove(position);
+                self.record_audit(timestamp, index, market, audit, Some(outcome));
+            }
+        }
+        // Decisions whose order was dropped by a wrapper.
+        for (index, market, audit) in audits.drain(..) {
+            self.record_audit(timestamp, index, market, audit, None);
+        }
+        self.orders = orders;
+        self.audits = audits;
+
+        for index in 0..self.sleeves.len() {
+            if self.sleeves[index].is_funded() && self.sleeves[index].get_portfolio().is_some() {
+                self.evaluate_portfolio(index, timestamp, total).await;
+            }
+        }
+
+        if self.arbitrage.is_some() {
+            self.expire_leg(timestamp).await;
+            self.scan_arbitrage(timestamp, total).await;
+        }
+
+        if self.checkpoint.as_mut().map_or(false, |(_, schedule)| schedule.due(timestamp)) {
+            self.save_checkpoint(timestamp);
+        }
+
+        if self.valuation.due(timestamp) {
+            self.record_valuation(timestamp);
+        }
+    }
+
+    fn record_audit(&mut self, timestamp: i64, owner: usize, id: MarketId, audit: Audit, outcome: Option<Result<Order, String>>) {
+        let (order, rejection) = match outcome {
+            Some(Ok(order)) => (Some(order), None),
+            Some(Err(reason)) => (None, Some(reason)),
+            None => (None, None),
+        };
+        self.journal.record(Record::new(
+            timestamp,
+            Some(self.markets[id.index()].get_symbol().as_str()),
+            Some(self.sleeves[owner].get_name()),
+            Entry::Decision {
+                indicators: audit.indicators,
+                notes: audit.notes,
+                signal: audit.order,
+                order,
+                rejection,
+            },
+        ));
+    }
+
+    /// Values the portfolio one last time and compares it with the first valuation.
+    fn record_report(&mut self) {
+        self.graph.update(&self.markets);
+        self.record_valuation(self.timestamp);
+        let total = self.total_balance();
+        let initial = self.initial.unwrap_or(total);
+        self.journal.record(Record::new(self.timestamp, None, None, Entry::Report {
+            initial,
+            total,
+            profit: if initial > 0.0 { total / initial - 1.0 } else { 0.0 },
+            fills: self.fills,
+        }));
+    }
+
+    fn record_valuation(&mut self, timestamp: i64) {
+        let total = self.total_balance();
+        self.journal.record(Record::new(timestamp, None, None, Entry::Valuation { total }));
+        for (index, asset) in self.assets.iter().enumerate() {
+            let balance = asset.get_balance();
+            if balance > 0.0 {
+                self.journal.record(Record::new(timestamp, None, None, Entry::Holding {
+                    asset: asset.get_symbol().to_string(),
+                    balance,
+                    value: balance * self.graph.into_target(AssetId::new(index)),
+                }));
+            }
+        }
+        for sleeve in &self.sleeves {
+            self.journal.record(Record::new(timestamp, None, Some(sleeve.get_name()), Entry::Equity {
+                equity: sleeve.equity(&self.graph),
+                pnl: sleeve.pnl(&self.graph),
+            }));
+        }
+    }
+
+    /// Feeds the history of each market through the indicators of the strategies trading on it.
+    /// Strategies restored from a checkpoint are skipped, and the traders don't decide on the history.
+    async fn warm_up(&mut self) {
+        for index in 0..self.markets.len() {
+            self.warm_up_market(MarketId::new(index)).await;
+        }
+    }
+
+    async fn warm_up_market(&mut self, market: MarketId) {
+        if self.warm_up <= 0 {
+            return;
+        }
+
+        let cold = |slot: &Slot<S>| slot.market == market && !slot.strategy.is_initialized();
+        if !self.sleeves.iter().any(|sleeve| sleeve.get_slots().iter().any(cold)) {
+            return;
+        }
+
+        self.journal.record(Record::new(self.timestamp, Some(self.markets[market.index()].get_symbol().as_str()), None, Entry::Notice {
+            message: String::from("warming up"),
+        }));
+        let values = self.environment.history(self.markets[market.index()].get_symbol(), self.warm_up).await;
+        let bars = Bar::resample(&values, self.bar_interval);
+        for sleeve in &mut self.sleeves {
+            for slot in sleeve.get_slots_mut() {
+                if cold(slot) {
+                    for bar in &bars {
+                        slot.sizer.update(bar.close);
+                        slot.strategy.warm_up(bar);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Adds the markets the environment newly selected and retires the ones it dropped.
+    async fn update_universe(&mut self) {
+        let selected = if let Ok(selected) = self.environment.universe().await {
+            selected
+        } else {
+            return;
+        };
+        let symbols = selected
+            .iter()
+            .map(|market| market.symbol.clone())
+            .collect::<HashSet<String>>();
+
+        for index in 0..self.markets.len() {
+            let market = &self.markets[index];
+            if market.is_active() && !symbols.contains(market.get_symbol().as_str()) {
+                self.journal.record(Record::new(self.timestamp, Some(market.get_symbol().as_str()), None, Entry::Notice {
+                    message: String::from("retiring"),
+                }));
+                self.retire_market(MarketId::new(index), false).await;
+            }
+        }
+
+        for market in selected {
+            self.list_market(market).await;
+        }
+    }
+
+    /// Adds a market, or reactivates it if it was retired before, and starts its strategies.
+    async fn list_market(&mut self, market: MarketData) {
+        let id = match self.market_lookup.get(&market.symbol) {
+            Some(id) if self.markets[id.index()].is_active() => return,
+            Some(id) => {
+                let id = *id;
+                self.markets[id.index()].set_active(true);
+                self.markets[id.index()].set_halted(false);
+                id
+            },
+            None => self.add_market(market.symbol, market.base_asset, market.quote_asset),
+        };
+        self.journal.record(Record::new(self.timestamp, Some(self.markets[id.index()].get_symbol().as_str()), None, Entry::Notice {
+            message: String::from("adding"),
+        }));
+        self.open_slots(id);
+        self.warm_up_market(id).await;
+    }
+
+    /// Stops all strategies on the market and sells what each sleeve holds of the base asset,
+    /// unless the sleeve still trades the asset on another market.
+    ///
+    /// A delisted market can't be traded anymore, so the asset is sold on another market instead.
+    /// If there is none, the position is stranded and an alert is raised.
+    async fn retire_market(&mut self, id: MarketId, delisted: bool) {
+        self.markets[id.index()].set_active(false);
+        for sleeve in &mut self.sleeves {
+            sleeve.remove_slots(id);
+        }
+
+        let base = self.markets[id.index()].get_base();
+        let exit = if delisted {
+            self.markets[id.index()].set_halted(true);
+            self.markets[id.index()].clear_value();
+            self.graph.changed(id);
+            self.markets
+                .iter()
+                .position(|market|
+                    !market.is_halted() &&
+                    market.get_value().is_some() &&
+                    (market.get_base() == base || market.get_quote() == base)
+                )
+                .map(MarketId::new)
+        } else {
+            Some(id)
+        };
+
+        let mut stranded = 0.0;
+        let mut orders = Vec::new();
+        for (index, sleeve) in self.sleeves.iter().enumerate() {
+            let balance = sleeve.get_balance(base);
+            let still_traded = sleeve.get_slots().iter().any(|slot| {
+                let other = &self.markets[slot.market.index()];
+                other.get_base() == base || other.get_quote() == base
+            });
+            if balance <= 0.0 || still_traded || base == self.reference_asset {
+                continue;
+            }
+
+            let exit = if let Some(exit) = exit {
+                exit
+            } else {
+                stranded += balance;
+                continue;
+            };
+            let market = &self.markets[exit.index()];
+            let value = if let Some(value) = market.get_value() {
+                value
+            } else {
+                continue;
+            };
+            let order = if market.get_base() == base {
+                Order::Limit(Action::Sell, balance, value)
+            } else {
+                Order::Limit(Action::Buy, balance / value, value)
+            };
+
+            if let Ok(order) = market.apply_filters(order) {
+                orders.push((exit, order, Owner::Sleeve(index)));
+            }
+        }
+        for (exit, order, owner) in orders {
+            // Failures are logged, the position stays with the sleeve.
+            let _ = self.place(self.timestamp, exit, order, owner).await;
+        }
+
+        if stranded > 0.0 {
+            let alert = Alert::Stranded(self.assets[base.index()].get_symbol().to_string(), stranded);
+            self.journal.record(Record::new(self.timestamp, None, None, Entry::Alert { message: alert.to_string() }));
+            self.environment.alert(&alert).await;
+        }
+    }
+
+    /// Submits an order of a sleeve, reduced to what the sleeve can afford, if the risk limits allow it.
+    /// Returns the submitted order, or why there was none.
+    async fn submit(&mut self, owner: usize, id: MarketId, order: Order, timestamp: i64, total: Monetary) -> Result<Order, String> {
+        let (action, quantity, price) = if let Order::Limit(action, quantity, price) = order {
+            (action, quantity, price)
         } else {
-            let index = self.assets.len();
+            return Err(String::from("market orders are not supported"));
+        };
 
-            if symbol == REFERENCE_ASSET {
-                self.reference_asset = index;
+        let market = &self.markets[id.index()];
+        if market.is_halted() {
+            return Err(String::from("halted"));
+        }
+        let sleeve = &self.sleeves[owner];
+        let base = market.get_base();
+        let quote = market.get_quote();
+        let available = match action {
+            Action::Buy => sleeve.get_balance(quote) / price,
+            Action::Sell => sleeve.get_balance(base),
+        };
+        let quantity = quantity.min(available);
+        if quantity <= 0.0 {
+            return Err(String::from("no balance"));
+        }
+
+        let order = if let Ok(order) = market.apply_filters(Order::Limit(action.clone(), quantity, price)) {
+            order
+        } else {
+            self.journal.record(Record::new(timestamp, Some(market.get_symbol().as_str()), Some(sleeve.get_name()), Entry::Rejected {
+                action,
+                quantity,
+                price,
+                reason: String::from("filters"),
+            }));
+            return Err(String::from("filters"));
+        };
+
+        let held = self.assets[base.index()].get_balance() * self.graph.into_target(base);
+        let (position, exposure) = match action {
+            Action::Buy => (Some(held), Some(held / total + quantity * self.graph.into_target(base) / total)),
+            Action::Sell => (None, None),
+        };
+        match self.risk.check(timestamp, id, quantity * price, self.graph.into_target(quote), total, position, exposure) {
+            Ok(()) => {
+                let mut replaced = None;
+                let placed = match self.resting.remove(&(owner, id)) {
+                    Some(resting) => match self.environment.replace(market.get_symbol(), resting, order.clone()).await {
+                        // Filled or canceled in the meantime, its update is still to come.
+                        Err(OrderError::Unknown(_)) => self.environment.order(market.get_symbol(), order.clone()).await,
+                        placed => {
+                            replaced = Some(resting);
+                            placed
+                        },
+                    },
+                    None => self.environment.order(market.get_symbol(), order.clone()).await,
+                };
+                if let (Some(resting), Ok(_)) = (replaced, &placed) {
+                    // The replaced order no longer binds any value, what it filled is still reported to its owner.
+                    self.risk.closed(resting);
+                }
+                let order_id = match placed {
+                    Ok(order_id) => order_id,
+                    Err(error) => {
+                        let reason = error.to_string();
+                        self.journal.record(Record::new(timestamp, Some(market.get_symbol().as_str()), Some(sleeve.get_name()), Entry::Rejected {
+                            action,
+                            quantity,
+                            price,
+                            reason: reason.clone(),
+                        }));
+                        return Err(reason);
+                    },
+                };
+                if let Order::Limit(action, quantity, price) = &order {
+                    self.journal.record(Record::new(timestamp, Some(market.get_symbol().as_str()), Some(sleeve.get_name()), Entry::Submitted {
+                        action: action.clone(),
+                        quantity: *quantity,
+                        price: *price,
+                    }));
+                }
+                self.risk.submitted(timestamp, id, order_id, quantity * price);
+                self.owners.insert(order_id, Owner::Sleeve(owner));
+                self.resting.insert((owner, id), order_id);
+                Ok(order)
+            },
+            Err(rejection) => {
+                let reason = format!("{:?}", rejection);
+                self.journal.record(Record::new(timestamp, Some(market.get_symbol().as_str()), Some(sleeve.get_name()), Entry::Rejected {
+                    action,
+                    quantity,
+                    price,
+                    reason: reason.clone(),
+                }));
+                Err(reason)
             }
+        }
+    }
+
+    /// Lets a sleeve's portfolio strategy decide, and turns target weights into one order per asset
+    /// over its market to the reference asset. Sells are submitted first. Buys the sleeve can't afford yet
+    /// are reduced, the rest is bought on a later evaluation once the sells have filled.
+    async fn evaluate_portfolio(&mut self, index: usize, timestamp: i64, total: Monetary) {
+        let sleeve = &self.sleeves[index];
+        let assets = &self.assets;
+        let graph = &self.graph;
+        let view = PortfolioView {
+            timestamp,
+            reference: &self.reference_symbol,
+            markets: self.markets
+                .iter()
+                .filter(|market| !market.is_halted())
+                .map(|market| MarketView {
+                    symbol: market.get_symbol(),
+                    base: assets[market.get_base().index()].get_symbol(),
+                    quote: assets[market.get_quote().index()].get_symbol(),
+                    value: market.get_value(),
+                })
+                .collect(),
+            assets: assets
+                .iter()
+                .enumerate()
+                .map(|(id, asset)| AssetView {
+                    symbol: asset.get_symbol(),
+                    balance: sleeve.get_balance(AssetId::new(id)),
+                    value: graph.into_target(AssetId::new(id)),
+                })
+                .collect(),
+            equity: sleeve.equity(graph),
+        };
+        let decision = self.sleeves[index].get_portfolio_mut().and_then(|portfolio| portfolio.evaluate(&view));
+        let equity = view.equity;
+        drop(view);
+
+        let mut orders = Vec::new();
+        match decision {
+            Some(Decision::Orders(requested)) => {
+                for (symbol, order) in requested {
+                    if let Some(id) = self.market_lookup.get(&symbol) {
+                        orders.push((*id, order));
+                    }
+                }
+            },
+            Some(Decision::Weights(requested)) => {
+                let mut weights = vec![0.0; self.assets.len()];
+                for (symbol, weight) in requested {
+                    if let Some(id) = self.asset_lookup.get(&symbol) {
+                        weights[id.index()] = weight;
+                    }
+                }
+                let values = (0..self.assets.len())
+                    .map(|id| self.sleeves[index].get_balance(AssetId::new(id)) * self.graph.into_target(AssetId::new(id)))
+                    .collect::<Vec<Monetary>>();
 
-            self.asset_lookup.insert(symbol.clone(), index);
+                let changes = rebalance(&values, &weights, equity, REBALANCE_TOLERANCE);
+                for (asset, change) in changes.into_iter().enumerate() {
+                    let asset = AssetId::new(asset);
+                    if change == 0.0 || asset == self.reference_asset {
+                        continue;
+                    }
+                    let reference = self.reference_asset;
+                    let direct = self.markets.iter().position(|market|
+                        (market.get_base() == asset && market.get_quote() == reference) ||
+                        (market.get_base() == reference && market.get_quote() == asset)
+                    );
+                    let id = if let Some(id) = direct {
+                        MarketId::new(id)
+                    } else {
+                        let message = format!("can't rebalance {} without a market to {}", self.assets[asset.index()].get_symbol(), self.reference_symbol);
+                        self.journal.record(Record::new(timestamp, None, Some(self.sleeves[index].get_name()), Entry::Notice { message }));
+                        continue;
+                    };
+                    let market = &self.markets[id.index()];
+                    let price = if let Some(price) = market.get_value() {
+                        price
+                    } else {
+                        continue;
+                    };
+                    let order = if market.get_base() == asset {
+                        let action = if change > 0.0 { Action::Buy } else { Action::Sell };
+                        Order::Limit(action, change.abs() / price, price)
+                    } else {
+                        // The reference asset is the base, so it is sold to buy the asset and vice versa.
+                        let action = if change > 0.0 { Action::Sell } else { Action::Buy };
+                        Order::Limit(action, change.abs(), price)
+                    };
+                    orders.push((id, order));
+                }
+            },
+            None => {},
+        }
+
+        // Sells of the asset being reduced come first, so their proceeds are available for the buys.
+        orders.sort_by_key(|(id, order)| match order {
+            Order::Limit(Action::Sell, _, _) => self.markets[id.index()].get_base() == self.reference_asset,
+            Order::Limit(Action::Buy, _, _) => self.markets[id.index()].get_base() != self.reference_asset,
+            Order::Market(_, _) => true,
+        });
+        for (id, order) in orders {
+            self.submit(index, id, order, timestamp, total).await.ok();
+        }
+    }
+
+    async fn scan_arbitrage(&mut self, timestamp: i64, total: Monetary) {
+        let opportunities = if let Some(scanner) = &mut self.arbitrage {
+            scanner.scan(&self.markets)
+        } else {
+            return;
+        };
+        for opportunity in &opportunities {
+            let message = format!("{} at {:.4}", self.describe(opportunity), opportunity.rate);
+            self.journal.record(Record::new(timestamp, None, Some("arbitrage"), Entry::Notice { message }));
+        }
+
+        let fraction = match self.arbitrage_fraction {
+            Some(fraction) if self.execution.is_none() => fraction,
+            _ => return,
+        };
+        let opportunity = if let Some(opportunity) = opportunities.into_iter().next() {
+            opportunity
+        } else {
+            return;
+        };
+
+        let allocated = self.sleeves
+            .iter()
+            .map(|sleeve| sleeve.get_balance(self.reference_asset))
+            .sum::<Monetary>();
+        let amount = (self.assets[self.reference_asset.index()].get_balance() - allocated) * fraction;
+        let first = &opportunity.legs[0];
+        let notional = match self.leg_order(first, amount) {
+            Some(Order::Limit(_, quantity, price)) if amount > 0.0 => quantity * price,
+            _ => return,
+        };
+        let quote_value = self.graph.into_target(self.markets[first.market.index()].get_quote());
+        if let Err(rejection) = self.risk.check(timestamp, first.market, notional, quote_value, total, None, None) {
+            let market = &self.markets[first.market.index()];
+            if let Some(Order::Limit(action, quantity, price)) = self.leg_order(first, amount) {
+                self.journal.record(Record::new(timestamp, Some(market.get_symbol().as_str()), Some("arbitrage"), Entry::Rejected {
+                    action,
+                    quantity,
+                    price,
+                    reason: format!("{:?}", rejection),
+                }));
+            }
+            return;
+        }
+
+        self.execution = Some(Execution {
+            opportunity,
+            leg: 0,
+            amount,
+            order: None,
+            invested: amount,
+        });
+        self.place_leg().await;
+    }
+
+    fn describe(&self, opportunity: &Opportunity) -> String {
+        let mut path = self.assets[opportunity.legs[0].from.index()].get_symbol().to_string();
+        for leg in opportunity.legs.iter() {
+            path.push_str(" → ");
+            path.push_str(&self.assets[leg.to.index()].get_symbol());
+        }
+        path
+    }
+
+    /// Order converting `amount` of the leg's `from` asset, selling at the bid or buying at the ask.
+    fn leg_order(&self, leg: &Leg, amount: Monetary) -> Option<Order> {
+        let market = &self.markets[leg.market.index()];
+        if market.is_halted() {
+            return None;
+        }
+        let price = match leg.action {
+            Action::Sell => market.get_bid(),
+            Action::Buy => market.get_ask(),
+        };
+        let price = price.filter(|price| *price > 0.0)?;
+        let order = match leg.action {
+            Action::Sell => Order::Limit(Action::Sell, amount, price),
+            Action::Buy => Order::Limit(Action::Buy, amount / price, price),
+        };
+        market.apply_filters(order).ok()
+    }
+
+    /// Places the order of the current leg, or unwinds the execution if it can't be placed.
+    async fn place_leg(&mut self) {
+        let (market, order) = if let Some(execution) = &self.execution {
+            let leg = &execution.opportunity.legs[execution.leg];
+            (leg.market, self.leg_order(leg, execution.amount))
+        } else {
+            return;
+        };
+
+        if let Some(order) = order {
+            if let Ok(order_id) = self.place(self.timestamp, market, order, Owner::Arbitrage).await {
+                if let Some(execution) = &mut self.execution {
+                    execution.order = Some((order_id, self.timestamp));
+                }
+                return;
+            }
+        }
+        self.unwind().await;
+    }
+
+    /// Cancels the order of the current leg if it didn't fill within the timeout, and unwinds the execution.
+    /// If the order can't be canceled, it already finished or the environment can't be reached, so this is retried
+    /// on the next evaluation unless the order's report arrived in between.
+    async fn expire_leg(&mut self, timestamp: i64) {
+        let (market, order_id) = match &self.execution {
+            Some(Execution { opportunity, leg, order: Some((order_id, placed)), .. }) if timestamp - placed >= self.arbitrage_timeout => {
+                (opportunity.legs[*leg].market, *order_id)
+            },
+            _ => return,
+        };
+        let symbol = self.markets[market.index()].get_symbol();
+        if let Err(error) = self.environment.cancel(symbol, order_id).await {
+            tracing::warn!(%error, %symbol, order_id, "failed to cancel arbitrage leg");
+            return;
+        }
+        self.unwind().await;
+    }
+
+    /// Continues the execution with what the filled leg returned.
+    /// Partially filled legs continue with the filled part only, the rest stays in the intermediate asset.
+    async fn arbitrage_executed(&mut self, order_id: OrderId, quantity: Monetary, price: Monetary, fee: Monetary) {
+        let mut execution = match self.execution.take() {
+            Some(execution) if execution.order.map(|(order, _)| order) == Some(order_id) => execution,
+            execution => {
+                // The leg timed out and the execution was unwound before the order's report arrived.
+                self.execution = execution;
+                if quantity > 0.0 {
+                    let message = format!("order {} of an unwound execution filled {} after it was canceled", order_id, quantity);
+                    self.journal.record(Record::new(self.timestamp, None, Some("arbitrage"), Entry::Notice { message }));
+                }
+                return;
+            }
+        };
+
+        if quantity <= 0.0 {
+            self.execution = Some(execution);
+            self.unwind().await;
+            return;
+        }
+
+        let received = match execution.opportunity.legs[execution.leg].action {
+            Action::Sell => quantity * price * (1.0 - fee),
+            Action::Buy => quantity * (1.0 - fee),
+        };
+        if execution.leg == execution.opportunity.legs.len() - 1 {
+            let message = format!(
+                "{} completed: {} {} → {} {}",
+                self.describe(&execution.opportunity),
+                execution.invested,
+                self.reference_symbol,
+                received,
+                self.reference_symbol
+            );
+            self.journal.record(Record::new(self.timestamp, None, Some("arbitrage"), Entry::Notice { message }));
+            return;
+        }
+
+        execution.leg += 1;
+        execution.amount = received;
+        execution.order = None;
+        self.execution = Some(execution);
+        self.place_leg().await;
+    }
+
+    /// Sells what a failed execution holds back into the reference asset, over a direct market.
+    async fn unwind(&mut self) {
+        let execution = if let Some(execution) = self.execution.take() {
+            execution
+        } else {
+            return;
+        };
+        let message = format!("{} failed at leg {}", self.describe(&execution.opportunity), execution.leg + 1);
+        self.journal.record(Record::new(self.timestamp, None, Some("arbitrage"), Entry::Notice { message }));
+        if execution.leg == 0 {
+            return;
+        }
+
+        let held = execution.opportunity.legs[execution.leg].from;
+        let reference = self.reference_asset;
+        let exit = self.markets
+            .iter()
+            .position(|market|
+                (market.get_base() == held && market.get_quote() == reference) ||
+                (market.get_base() == reference && market.get_quote() == held)
+            )
+            .map(|exit| Leg::new(MarketId::new(exit), &self.markets, held));
+
+        if let Some(exit) = exit {
+            if let Some(order) = self.leg_order(&exit, execution.amount) {
+                if self.place(self.timestamp, exit.market, order, Owner::Economy).await.is_ok() {
+                    return;
+                }
+            }
+        }
+
+        let alert = Alert::Stranded(self.assets[held.index()].get_symbol().to_string(), execution.amount);
+        self.journal.record(Record::new(self.timestamp, None, None, Entry::Alert { message: alert.to_string() }));
+        self.environment.alert(&alert).await;
+    }
+
+    fn save_checkpoint(&mut self, timestamp: i64) {
+        if let Some((path, _)) = &self.checkpoint {
+            if let Err(error) = self.snapshot(timestamp).and_then(|snapshot| checkpoint::save(path, &snapshot)) {
+                let message = format!("failed to save checkpoint: {}", error);
+                self.journal.record(Record::new(timestamp, None, None, Entry::Notice { message }));
+            }
+        }
+    }
+
+    fn snapshot(&self, timestamp: i64) -> io::Result<Snapshot> {
+        let mut sleeves = Vec::new();
+        for sleeve in &self.sleeves {
+            let mut slots = Vec::new();
+            for slot in sleeve.get_slots() {
+                slots.push(SlotState {
+                    symbol: self.markets[slot.market.index()].get_symbol().to_string(),
+                    strategy: slot.strategy.save()?,
+                    sizer: serde_json::to_value(&slot.sizer)?,
+                });
+            }
+            sleeves.push(SleeveState {
+                name: sleeve.get_name().to_string(),
+                capital: sleeve.get_capital(),
+                balances: self.assets
+                    .iter()
+                    .enumerate()
+                    .map(|(index, asset)| (asset.get_symbol().to_string(), sleeve.get_balance(AssetId::new(index))))
+                    .collect(),
+                slots,
+                portfolio: if let Some(portfolio) = sleeve.get_portfolio() {
+                    portfolio.save()?
+                } else {
+                    serde_json::Value::Null
+                },
+            });
+        }
+
+        Ok(Snapshot {
+            timestamp,
+            balances: self.assets
+                .iter()
+                .map(|asset| (asset.get_symbol().to_string(), asset.get_balance()))
+                .collect(),
+            values: self.markets
+                .iter()
+                .filter_map(|market| market.get_value().map(|value| (market.get_symbol().to_string(), value)))
+                .collect(),
+            sleeves,
+            risk: serde_json::to_value(&self.risk)?,
+            orders: self.risk
+                .get_open_orders()
+                .map(|(order_id, id, notional)| {
+                    let (owner, resting) = match self.owners.get(&order_id) {
+                        Some(Owner::Sleeve(index)) => (
+                            Some(self.sleeves[*index].get_name().to_string()),
+                            self.resting.get(&(*index, id)) == Some(&order_id),
+                        ),
+                        Some(Owner::Arbitrage) => (Some(String::from("arbitrage")), false),
+                        Some(Owner::Economy) | None => (None, false),
+                    };
+                    OrderState {
+                        symbol: self.markets[id.index()].get_symbol().to_string(),
+                        order_id,
+                        notional,
+                        owner,
+                        resting,
+                    }
+                })
+                .collect(),
+        })
+    }
+
+    /// Restores the state of all sleeves and markets that still exist. Balances are only remembered,
+    /// they are validated against the environment's balances on the first evaluation.
+    /// Returns the checkpointed open orders, to be reconciled with the environment's.
+    fn restore_checkpoint(&mut self) -> Vec<OrderState> {
+        let path = if let Some((path, _)) = &self.checkpoint {
+            path.clone()
+        } else {
+            return Vec::new();
+        };
+
+        let snapshot: Snapshot = match checkpoint::load(&path) {
+            Ok(Some(snapshot)) => snapshot,
+            Ok(None) => return Vec::new(),
+            Err(error) => {
+                let message = format!("failed to load checkpoint: {}", error);
+                self.journal.record(Record::new(self.timestamp, None, None, Entry::Notice { message }));
+                return Vec::new();
+            }
+        };
+
+        for (symbol, value) in &snapshot.values {
+            if let Some(market) = self.get_market_mut(symbol) {
+                market.set_value(*value);
+            }
+        }
+        self.graph.invalidate();
+
+        let mut restored = 0;
+        for state in snapshot.sleeves {
+            let sleeve = if let Some(sleeve) = self.sleeves.iter_mut().find(|sleeve| sleeve.get_name() == state.name) {
+                sleeve
+            } else {
+                continue;
+            };
+
+            let balances = state.balances
+                .iter()
+                .filter_map(|(symbol, balance)| self.asset_lookup.get(symbol).map(|id| (*id, *balance)))
+                .collect::<Vec<(AssetId, Monetary)>>();
+            sleeve.restore(state.capital, &balances);
+
+            if let (Some(portfolio), false) = (sleeve.get_portfolio_mut(), state.portfolio.is_null()) {
+                match portfolio.restore(state.portfolio) {
+                    Ok(()) => restored += 1,
+                    Err(error) => {
+                        let message = format!("failed to restore: {}", error);
+                        self.journal.record(Record::new(self.timestamp, None, Some(state.name.as_str()), Entry::Notice { message }));
+                    },
+                }
+            }
+
+            for slot_state in state.slots {
+                let market = if let Some(market) = self.market_lookup.get(&slot_state.symbol) {
+                    *market
+                } else {
+                    continue;
+                };
+                if let Some(slot) = sleeve.get_slots_mut().iter_mut().find(|slot| slot.market == market) {
+                    match (slot.strategy.restore(slot_state.strategy), serde_json::from_value::<S>(slot_state.sizer)) {
+                        (Ok(()), Ok(sizer)) => {
+                            slot.sizer = sizer;
+                            restored += 1;
+                        },
+                        _ => {
+                            self.journal.record(Record::new(self.timestamp, Some(slot_state.symbol.as_str()), Some(state.name.as_str()), Entry::Notice {
+                                message: String::from("failed to restore"),
+                            }));
+                        }
+                    }
+                }
+            }
+        }
+
+        match serde_json::from_value::<RiskGuard>(snapshot.risk) {
+            Ok(risk) => {
+                let limits = self.risk.get_limits().clone();
+                self.risk = risk;
+                self.risk.set_limits(limits);
+                self.risk.resize(self.markets.len());
+                if self.resume && self.risk.is_halted() {
+                    self.risk.resume();
+                    self.journal.record(Record::new(self.timestamp, None, None, Entry::Notice {
+                        message: String::from("kill switch lifted"),
+                    }));
+                }
+            },
+            Err(error) => {
+                let message = format!("failed to restore risk guard: {}", error);
+                self.journal.record(Record::new(self.timestamp, None, None, Entry::Notice { message }));
+            }
+        }
+
+        self.expected_balances = snapshot.balances.into_iter().collect();
+        let message = format!("restored {} strategies from checkpoint at {}", restored, snapshot.timestamp);
+        self.journal.record(Record::new(self.timestamp, None, None, Entry::Notice { message }));
+        snapshot.orders
+    }
+
+    /// Takes over the orders the environment still has open. Checkpointed orders keep their owner and submitted value,
+    /// any others are attributed to the economy at the value of their unfilled quantity.
+    async fn reconcile_orders(&mut self, orders: Vec<OrderState>) {
+        let mut checkpointed = orders
+            .into_iter()
+            .map(|order| (order.order_id, order))
+            .collect::<HashMap<OrderId, OrderState>>();
+
+        let mut reconciled = 0;
+        for index in 0..self.markets.len() {
+            let id = MarketId::new(index);
+            let symbol = self.markets[index].get_symbol().to_string();
+            let open = match self.environment.open_orders(&symbol).await {
+                Ok(open) => open,
+                Err(OrderError::Unsupported) => break,
+                Err(error) => {
+                    tracing::warn!(%error, %symbol, "querying open orders failed");
+                    continue;
+                },
+            };
+            for query_order in open {
+                let (notional, owner, resting) = match checkpointed.remove(&query_order.order_id) {
+                    Some(state) => {
+                        let owner = match state.owner.as_deref() {
+                            Some("arbitrage") => Owner::Arbitrage,
+                            Some(name) => self.sleeves
+                                .iter()
+                                .position(|sleeve| sleeve.get_name() == name)
+                                .map(Owner::Sleeve)
+                                .unwrap_or(Owner::Economy),
+                            None => Owner::Economy,
+                        };
+                        (state.notional, owner, state.resting)
+                    },
+                    None => ((query_order.orig_qty - query_order.executed_qty) * query_order.price, Owner::Economy, false),
+                };
+                self.risk.restored(id, query_order.order_id, notional);
+                self.owners.insert(query_order.order_id, owner);
+                if let (Owner::Sleeve(sleeve), true) = (owner, resting) {
+                    self.resting.insert((sleeve, id), query_order.order_id);
+                }
+                reconciled += 1;
+            }
+        }
+
+        if reconciled > 0 || !checkpointed.is_empty() {
+            let message = format!("took over {} open orders, {} checkpointed orders are no longer open", reconciled, checkpointed.len());
+            self.journal.record(Record::new(self.timestamp, None, None, Entry::Notice { message }));
+        }
+    }
+
+    /// Compares the checkpointed balances with the actual ones.
+    /// Strategies of markets with a mismatching asset are reset, as their view of the positions is no longer valid,
+    /// and all sleeves are funded again from the actual balances.
+    fn validate_checkpoint(&mut self) {
+        let mut mismatch = false;
+
+        for (symbol, expected) in self.expected_balances.drain() {
+            let id = if let Some(id) = self.asset_lookup.get(&symbol) {
+                *id
+            } else {
+                continue;
+            };
+
+            let balance = self.assets[id.index()].get_balance();
+            if (expected - balance).abs() > BALANCE_TOLERANCE * expected.max(balance) {
+                let message = format!("checkpoint balance mismatch for {}: expected {}, got {}", symbol, expected, balance);
+                self.journal.record(Record::new(self.timestamp, None, None, Entry::Alert { message }));
+                mismatch = true;
+                for (index, market) in self.markets.iter().enumerate() {
+                    if market.get_base() == id || market.get_quote() == id {
+                        for sleeve in &mut self.sleeves {
+                            sleeve.reset(
+                                MarketId::new(index),
+                                &self.assets[market.get_base().index()].get_symbol(),
+                                &self.assets[market.get_quote().index()].get_symbol(),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        if mismatch {
+            for sleeve in &mut self.sleeves {
+                sleeve.restore(None, &[]);
+            }
+        }
+    }
+
+    /// Sells all assets that have a direct market to the reference asset, bypassing the risk limits.
+    async fn flatten(&mut self, timestamp: i64) {
+        let mut orders = Vec::new();
+        for (index, market) in self.markets.iter().enumerate() {
+            let value = match market.get_value() {
+                Some(value) if !market.is_halted() => value,
+                _ => continue,
+            };
+
+            let order = if market.get_quote() == self.reference_asset {
+                let balance = self.assets[market.get_base().index()].get_balance();
+                if balance > 0.0 {
+                    Some(Order::Limit(Action::Sell, balance, value))
+                } else {
+                    None
+                }
+            } else if market.get_base() == self.reference_asset {
+                let balance = self.assets[market.get_quote().index()].get_balance();
+                if balance > 0.0 {
+                    Some(Order::Limit(Action::Buy, balance / value, value))
+                } else {
+                    None
+                }
+            } else {
+                None
+            };
+
+            if let Some(order) = order {
+                if let Ok(order) = market.apply_filters(order) {
+                    orders.push((MarketId::new(index), order));
+                }
+            }
+        }
+        for (id, order) in orders {
+            let _ = self.place(timestamp, id, order, Owner::Economy).await;
+        }
+    }
+
+    /// Places an order on behalf of its owner and records it as submitted.
+    /// Failures are logged, the ID is returned if the environment accepted the order.
+    async fn place(&mut self, timestamp: i64, id: MarketId, order: Order, owner: Owner) -> Result<OrderId, OrderError> {
+        let placed = self.environment.order(self.markets[id.index()].get_symbol(), order.clone()).await;
+        let symbol = self.markets[id.index()].get_symbol();
+        match &placed {
+            Ok(order_id) => {
+                let strategy = match owner {
+                    Owner::Sleeve(index) => Some(self.sleeves[index].get_name()),
+                    Owner::Arbitrage => Some("arbitrage"),
+                    Owner::Economy => None,
+                };
+                if let Order::Limit(action, quantity, price) = &order {
+                    self.risk.submitted(timestamp, id, *order_id, quantity * price);
+                    self.journal.record(Record::new(timestamp, Some(symbol.as_str()), strategy, Entry::Submitted {
+                        action: action.clone(),
+                        quantity: *quantity,
+                        price: *price,
+                    }));
+                }
+                self.owners.insert(*order_id, owner);
+            },
+            Err(error) => tracing::warn!(%error, %symbol, "placing order failed"),
+        }
+        placed
+    }
+
+    fn add_asset(&mut self, symbol: String) -> AssetId {
+        if let Some(id) = self.asset_lookup.get(&symbol) {
+            *id
+        } else {
+            let id = AssetId::new(self.assets.len());
+            self.asset_lookup.insert(symbol.clone(), id);
             self.assets.push(Asset::new(symbol));
-            index
+            self.graph.resize(self.assets.len());
+            id
         }
     }
 
-    fn add_market(&mut self, symbol: String, base: String, quote: String) -> usize {
-        let base_index = self.add_asset(base.clone());
-        let quote_index = self.add_asset(quote.clone());
-        let index = self.markets.len();
-        self.market_lookup.insert(symbol.clone(), index);
+    fn add_market(&mut self, symbol: String, base: String, quote: String) -> MarketId {
+        let base_id = self.add_asset(base.clone());
+        let quote_id = self.add_asset(quote.clone());
+        let id = MarketId::new(self.markets.len());
+        self.market_lookup.insert(symbol.clone(), id);
         self.markets
-            .push(Market::new((base, quote), base_index, quote_index));
-        index
+            .push(Market::new((base, quote), base_id, quote_id));
+        self.graph.invalidate();
+        self.risk.resize(self.markets.len());
+        for sleeve in &mut self.sleeves {
+            sleeve.resize(self.assets.len());
+        }
+        id
+    }
+
+    /// Starts the strategies of all sleeves that trade on the market.
+    fn open_slots(&mut self, id: MarketId) {
+        let market = &self.markets[id.index()];
+        for sleeve in &mut self.sleeves {
+            if sleeve.accepts(market.get_symbol()) {
+                sleeve.add_slot(
+                    id,
+                    &self.assets[market.get_base().index()].get_symbol(),
+                    &self.assets[market.get_quote().index()].get_symbol(),
+                );
+            }
+        }
     }
 
     fn get_market_mut(&mut self, symbol: &str) -> Option<&mut Market> {
-        if let Some(index) = self.market_lookup.get(symbol) {
-            Some(self.markets.get_mut(*index).unwrap())
+        if let Some(id) = self.market_lookup.get(symbol) {
+            Some(self.markets.get_mut(id.index()).unwrap())
         } else {
             None
         }
     }
 
     fn get_asset_mut(&mut self, symbol: &str) -> Option<&mut Asset> {
-        if let Some(index) = self.asset_lookup.get(symbol) {
-            Some(self.assets.get_mut(*index).unwrap())
+        if let Some(id) = self.asset_lookup.get(symbol) {
+            Some(self.assets.get_mut(id.index()).unwrap())
         } else {
             None
         }
     }
     
     fn get_market(&self, symbol: &str) -> Option<&Market> {
-        if let Some(index) = self.market_lookup.get(symbol) {
-            Some(self.markets.get(*index).unwrap())
+        if let Some(id) = self.market_lookup.get(symbol) {
+            Some(self.markets.get(id.index()).unwrap())
         } else {
             None
         }
     }
 
     fn get_asset(&self, symbol: &str) -> Option<&Asset> {
-        if let Some(index) = self.asset_lookup.get(symbol) {
-            Some(self.assets.get(*index).unwrap())
+        if let Some(id) = self.asset_lookup.get(symbol) {
+            Some(self.assets.get(id.index()).unwrap())
         } else {
             None
         }
     }
 
-    fn value_from_to(&self, from: &str, to: &str) -> Monetary {
+    /// Converts one unit of an asset into another, following the best path through the market graph.
+    /// Returns `0.0` if no path between the assets exists.
+    fn value_from_to(&self, from: AssetId, to: AssetId) -> Monetary {
         if from == to {
-            return 1.0;
-        }
-
-        if let Some(index) = self.market_lookup.get(&format!("{}{}", from, to)) {
-            if let Some(market) = self.markets.get(*index) {
-                if let Some(value) = market.base_to_quote() {
-                    return value;
-                }
-            }
-        }
-
-        if let Some(index) = self.market_lookup.get(&format!("{}{}", to, from)) {
-            if let Some(market) = self.markets.get(*index) {
-                if let Some(value) = market.quote_to_base() {
-                    return value;
-                }
-            }
+            1.0
+        } else if to == self.reference_asset {
+            self.graph.into_target(from)
+        } else if from == self.reference_asset {
+            self.graph.from_target(to)
+        } else {
+            graph::best_rate(&self.markets, self.assets.len(), from, to)
         }
-
-        0.0
     }
 
     fn total_balance(&self) -> Monetary {
         let mut total = 0.0;
 
-        for asset in &self.assets {
+        for (index, asset) in self.assets.iter().enumerate() {
             let balance = asset.get_balance();
             if balance > 0.0 {
-                total += balance * self.value_from_to(asset.get_symbol(), REFERENCE_ASSET);
+                total += balance * self.graph.into_target(AssetId::new(index));
             }
         }
 
         total
     }
 }
+
+#[tokio::test]
+async fn test_schedule() {
+    let mut schedule = Schedule::every(60);
+    assert!(!schedule.due(30));
+    assert!(!schedule.due(60));
+    // Evaluations may skip the exact second.
+    assert!(schedule.due(95));
+    assert!(!schedule.due(150));
+    assert!(schedule.due(155));
+
+    let mut disabled = Schedule::every(0);
+    assert!(!disabled.due(0));
+    assert!(!disabled.due(60));
+}
+
+/// Environment that hands out the given events and records what is placed on it.
+#[cfg(test)]
+struct Stub {
+    events: std::collections::VecDeque<Event>,
+    orders: Vec<(String, Order)>,
+    canceled: Vec<String>,
+    balances: bool,
+}
+
+#[cfg(test)]
+impl Stub {
+    fn new(events: Vec<Event>) -> Stub {
+        Stub {
+            events: events.into_iter().collect(),
+            orders: Vec::new(),
+            canceled: Vec::new(),
+            balances: false,
+        }
+    }
+}
+
+#[cfg(test)]
+#[async_trait::async_trait]
+impl Environment for Stub {
+    async fn initialize(&mut self) -> Result<Vec<MarketData>, ()> {
+        let market = |symbol: &str, base: &str, quote: &str| MarketData {
+            symbol: String::from(symbol),
+            status: String::from("TRADING"),
+            base_asset: String::from(base),
+            base_asset_precision: 8,
+            quote_asset: String::from(quote),
+            quote_precision: 8,
+            order_types: vec![String::from("LIMIT")],
+            iceberg_allowed: false,
+            filters: Vec::new(),
+        };
+        Ok(vec![market("BTCUSDT", "BTC", "USDT"), market("ETHUSDT", "ETH", "USDT"), market("BTCETH", "BTC", "ETH")])
+    }
+
+    async fn poll(&mut self) -> Event {
+        self.events.pop_front().unwrap_or(Event::Finished)
+    }
+
+    async fn order(&mut self, symbol: &str, order: Order) -> Result<OrderId, OrderError> {
+        self.orders.push((String::from(symbol), order));
+        Ok(self.orders.len() as OrderId)
+    }
+
+    async fn cancel(&mut self, symbol: &str, id: OrderId) -> Result<(), OrderError> {
+        self.canceled.push(format!("{} {}", symbol, id));
+        Ok(())
+    }
+
+    async fn cancel_all(&mut self, symbol: &str) -> Result<(), OrderError> {
+        self.canceled.push(String::from(symbol));
+        Ok(())
+    }
+
+    async fn open_orders(&mut self, _symbol: &str) -> Result<Vec<binance_async::model::Order>, OrderError> {
+        Ok(Vec::new())
+    }
+
+    async fn update_balances(&self, _balances: Vec<(&AssetSymbol, Monetary)>) {}
+
+    async fn alert(&self, _alert: &Alert) {}
+
+    fn reports_balances(&self) -> bool {
+        self.balances
+    }
+}
+
+/// Never trades.
+#[cfg(test)]
+struct Hold;
+
+#[cfg(test)]
+impl crate::traders::Strategy for Hold {
+    fn evaluate(&mut self, _bar: &Bar) -> Option<Order> {
+        None
+    }
+
+    fn warm_up(&mut self, _bar: &Bar) {}
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn save(&self) -> Result<serde_json::Value, serde_json::Error> {
+        Ok(serde_json::Value::Null)
+    }
+
+    fn restore(&mut self, _state: serde_json::Value) -> Result<(), serde_json::Error> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_retire_market() {
+    use crate::sizers::FixedFractional;
+    use crate::traders::Strategy;
+
+    // The strategy never trades, so only retiring the market places orders.
+    let events = vec![
+        Event::SetAssetBalance(String::from("USDT"), 1000.0),
+        Event::SetAssetBalance(String::from("BTC"), 1.0),
+        Event::SetMarketValue(String::from("BTCUSDT"), 100.0),
+        Event::SetMarketValue(String::from("ETHUSDT"), 10.0),
+        Event::SetMarketValue(String::from("BTCETH"), 10.0),
+        Event::Evaluate(60),
+        // The sleeve holds BTC, but only traded it on the delisted market, so it is sold on the other one.
+        Event::MarketRemoved(String::from("BTCUSDT")),
+    ];
+    let mut economy = Economy::<Stub, FixedFractional>::new(Stub::new(events), "USDT");
+    let factory = |_: &str, _: &str| -> Box<dyn Strategy> { Box::new(Hold) };
+    economy.add_sleeve(Sleeve::with_factory("hold", 1.0, &["BTCUSDT", "ETHUSDT"], Box::new(factory)));
+    economy.run().await.unwrap();
+
+    assert_eq!(economy.environment.canceled, vec![String::from("BTCUSDT")]);
+    match &economy.environment.orders[..] {
+        [(symbol, Order::Limit(Action::Sell, quantity, price))] => {
+            assert_eq!((symbol.as_str(), *quantity, *price), ("BTCETH", 1.0, 10.0));
+        },
+        orders => panic!("unexpected orders {:?}", orders),
+    }
+    let id = economy.market_lookup["BTCUSDT"];
+    assert!(!economy.markets[id.index()].is_active());
+    assert!(economy.sleeves[0].get_slots().iter().all(|slot| slot.market != id));
+}
+
+#[tokio::test]
+async fn test_resume_trading() {
+    use crate::sizers::FixedFractional;
+
+    let directory = std::env::temp_dir().join(format!("trader-test-resume-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("checkpoint.json");
+
+    let limits = RiskLimits {
+        max_drawdown: Some(0.25),
+        ..RiskLimits::default()
+    };
+    let mut risk = RiskGuard::new(limits.clone());
+    assert!(risk.update(0, 100.0).is_none());
+    assert!(matches!(risk.update(60, 70.0), Some(Alert::Drawdown(_))));
+    checkpoint::save(&path, &Snapshot {
+        timestamp: 60,
+        balances: Vec::new(),
+        values: Vec::new(),
+        sleeves: Vec::new(),
+        risk: serde_json::to_value(&risk).unwrap(),
+        orders: Vec::new(),
+    }).unwrap();
+
+    let restart = |resume: bool| {
+        let mut economy = Economy::<Stub, FixedFractional>::new(Stub::new(Vec::new()), "USDT");
+        economy.set_risk_limits(limits.clone());
+        economy.set_checkpoint(path.clone(), 3600);
+        if resume {
+            economy.resume_trading();
+        }
+        assert!(economy.restore_checkpoint().is_empty());
+        economy
+    };
+
+    // The kill switch survives a restart.
+    assert!(restart(false).risk.is_halted());
+
+    // Until it is lifted, then drawdowns are measured from the value trading resumed at.
+    let mut economy = restart(true);
+    std::fs::remove_dir_all(&directory).unwrap();
+    assert!(!economy.risk.is_halted());
+    assert!(economy.risk.update(120, 60.0).is_none());
+    assert!(matches!(economy.risk.update(180, 50.0), Some(Alert::Drawdown(_))));
+}
+
+#[tokio::test]
+async fn test_arbitrage_timeout() {
+    use crate::sizers::FixedFractional;
+
+    // USDT → BTC → ETH → USDT returns 10% before fees, the first leg buys at the ask.
+    let events = vec![
+        Event::SetAssetBalance(String::from("USDT"), 1000.0),
+        Event::SetMarketValue(String::from("BTCUSDT"), 90.0),
+        Event::SetMarketBookTicker(String::from("BTCUSDT"), BookTicker { bid: 99.0, bid_quantity: 1.0, ask: 100.0, ask_quantity: 1.0 }),
+        Event::SetMarketValue(String::from("BTCETH"), 11.0),
+        Event::SetMarketValue(String::from("ETHUSDT"), 10.0),
+        Event::Evaluate(60),
+        Event::Evaluate(119),
+        // The cycle closed and the leg never filled, so it is canceled without starting another execution.
+        Event::SetMarketValue(String::from("BTCETH"), 10.0),
+        Event::Evaluate(120),
+    ];
+    let mut economy = Economy::<Stub, FixedFractional>::new(Stub::new(events), "USDT");
+    economy.set_arbitrage(0.01, Some(0.5));
+    economy.run().await.unwrap();
+
+    match &economy.environment.orders[..] {
+        [(symbol, Order::Limit(Action::Buy, quantity, price))] => {
+            assert_eq!((symbol.as_str(), *quantity, *price), ("BTCUSDT", 5.0, 100.0));
+        },
+        orders => panic!("unexpected orders {:?}", orders),
+    }
+    assert_eq!(economy.environment.canceled, vec![String::from("BTCUSDT 1")]);
+    assert!(economy.execution.is_none());
+}
+
+#[tokio::test]
+async fn test_reported_balances() {
+    use crate::sizers::FixedFractional;
+
+    let order = binance_async::model::Order {
+        symbol: String::from("BTCUSDT"),
+        order_id: 1,
+        client_order_id: String::new(),
+        price: 100.0,
+        orig_qty: 1.0,
+        executed_qty: 1.0,
+        status: String::from("FILLED"),
+        time_in_force: String::from("GTC"),
+        type_name: String::from("LIMIT"),
+        side: Side::Buy,
+        stop_price: 0.0,
+        iceberg_qty: String::new(),
+        time: 0,
+    };
+    let events = vec![
+        Event::SetAssetBalance(String::from("USDT"), 1000.0),
+        Event::SetMarketValue(String::from("BTCUSDT"), 100.0),
+        // The exchange reports the balances after the fill, which are not to be counted twice.
+        Event::ExecutedOrder(order, 1.0, Some(0.0)),
+        Event::SetAssetBalance(String::from("BTC"), 1.0),
+        Event::SetAssetBalance(String::from("USDT"), 900.0),
+    ];
+    let mut stub = Stub::new(events);
+    stub.balances = true;
+    let mut economy = Economy::<Stub, FixedFractional>::new(stub, "USDT");
+    economy.run().await.unwrap();
+
+    assert_eq!(economy.get_asset_mut("BTC").unwrap().get_balance(), 1.0);
+    assert_eq!(economy.get_asset_mut("USDT").unwrap().get_balance(), 900.0);
+}
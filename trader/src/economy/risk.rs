@@ -0,0 +1,306 @@
+use super::{MarketId, Monetary};
+use crate::environments::OrderId;
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+/// Portfolio-wide limits, all of them disabled by default.
+/// Exposures and losses are fractions of the total portfolio value.
+#[derive(Debug, Clone, Default)]
+pub struct RiskLimits {
+    /// Maximum fraction of the portfolio held in a single asset, after buying it.
+    pub max_asset_exposure: Option<Monetary>,
+    /// Maximum fraction of the portfolio bound in a single market, in its open orders and,
+    /// when buying, in the base asset already held.
+    pub max_market_exposure: Option<Monetary>,
+    pub max_open_orders: Option<usize>,
+    pub max_orders_per_minute: Option<usize>,
+    /// Maximum loss since the start of the (UTC) day, triggers the kill switch until the next day.
+    pub max_daily_loss: Option<Monetary>,
+    /// Maximum loss from the peak portfolio value, triggers the kill switch until trading is resumed.
+    pub max_drawdown: Option<Monetary>,
+    /// Sell everything into the reference asset once the kill switch triggers.
+    pub flatten: bool,
+}
+
+/// Reason why the kill switch was triggered, or another event that needs attention.
+#[derive(Debug, Clone)]
+pub enum Alert {
+    DailyLoss(Monetary),
+    Drawdown(Monetary),
+    /// Asset and balance that could not be sold, as there is no market left to sell it on.
+    Stranded(String, Monetary),
+    /// Sleeve, asset and balance of a sleeve that spent more than it had, its attribution no longer adds up.
+    Overdrawn(String, String, Monetary),
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Alert::DailyLoss(loss) => write!(f, "kill switch triggered: daily loss of {:.2}%", loss * 100.0),
+            Alert::Drawdown(drawdown) => write!(f, "kill switch triggered: drawdown of {:.2}%", drawdown * 100.0),
+            Alert::Stranded(asset, balance) => write!(f, "{} {} stranded without a market to sell it on", balance, asset),
+            Alert::Overdrawn(sleeve, asset, balance) => write!(f, "sleeve {} overdrawn to {} {}", sleeve, balance, asset),
+        }
+    }
+}
+
+/// Reason why a single order was not submitted.
+#[derive(Debug, Clone)]
+pub enum Rejection {
+    Halted,
+    AssetExposure(Monetary),
+    MarketExposure(Monetary),
+    OpenOrders(usize),
+    OrderRate(usize),
+}
+
+/// Which limit triggered the kill switch.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+enum Halt {
+    DailyLoss,
+    Drawdown,
+}
+
+/// Keeps track of the portfolio and order flow and enforces the risk limits.
+/// Only the portfolio history is serialized, the economy checkpoints the open orders itself by symbol
+/// and hands them back with `restored`. Limits and the order rate start fresh.
+#[derive(Serialize, Deserialize)]
+pub struct RiskGuard {
+    #[serde(skip)]
+    limits: RiskLimits,
+    halt: Option<Halt>,
+    peak: Monetary,
+    day: Option<i64>,
+    day_start: Monetary,
+    /// Portfolio value of the last update.
+    total: Monetary,
+    /// Market and value of each open order, in the market's quote asset, as it was submitted.
+    #[serde(skip)]
+    open: HashMap<OrderId, (MarketId, Monetary)>,
+    /// Value of the open orders of each market, in the market's quote asset.
+    #[serde(skip)]
+    open_notional: Vec<Monetary>,
+    #[serde(skip)]
+    recent_orders: VecDeque<i64>,
+}
+
+impl RiskGuard {
+    pub fn new(limits: RiskLimits) -> RiskGuard {
+        RiskGuard {
+            limits,
+            halt: None,
+            peak: 0.0,
+            day: None,
+            day_start: 0.0,
+            total: 0.0,
+            open: HashMap::new(),
+            open_notional: Vec::new(),
+            recent_orders: VecDeque::new(),
+        }
+    }
+
+    pub fn get_limits(&self) -> &RiskLimits {
+        &self.limits
+    }
+
+    pub fn set_limits(&mut self, limits: RiskLimits) {
+        self.limits = limits;
+    }
+
+    pub fn resize(&mut self, markets: usize) {
+        self.open_notional.resize(markets, 0.0);
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halt.is_some()
+    }
+
+    /// Lifts the kill switch, drawdowns are measured from the current portfolio value from now on.
+    pub fn resume(&mut self) {
+        self.halt = None;
+        self.peak = self.total;
+    }
+
+    /// Updates the peak and daily portfolio value.
+    /// Returns an alert if this update triggered the kill switch.
+    pub fn update(&mut self, timestamp: i64, total: Monetary) -> Option<Alert> {
+        let day = timestamp.div_euclid(24 * 60 * 60);
+        if self.day != Some(day) {
+            self.day = Some(day);
+            self.day_start = total;
+            if self.halt == Some(Halt::DailyLoss) {
+                self.halt = None;
+            }
+        }
+        self.peak = self.peak.max(total);
+        self.total = total;
+
+        if self.halt.is_some() {
+            return None;
+        }
+
+        let alert = if self.day_start > 0.0 && self.limits.max_daily_loss.map_or(false, |max| 1.0 - total / self.day_start > max) {
+            Some(Alert::DailyLoss(1.0 - total / self.day_start))
+        } else if self.peak > 0.0 && self.limits.max_drawdown.map_or(false, |max| 1.0 - total / self.peak > max) {
+            Some(Alert::Drawdown(1.0 - total / self.peak))
+        } else {
+            None
+        };
+
+        self.halt = match alert {
+            Some(Alert::DailyLoss(_)) => Some(Halt::DailyLoss),
+            Some(_) => Some(Halt::Drawdown),
+            None => None,
+        };
+        alert
+    }
+
+    /// Checks whether an order may be submitted.
+    ///
+    /// `notional` is the order's value in the quote asset, `quote_value` the value of one unit of the quote asset
+    /// in the reference asset. `position` is the value of the market's base asset already held, in the reference asset,
+    /// and `exposure` the fraction of the portfolio held in the bought asset after a fill. Both are only given for buy orders.
+    #[allow(clippy::too_many_arguments)]
+    pub fn check(
+        &mut self,
+        timestamp: i64,
+        market: MarketId,
+        notional: Monetary,
+        quote_value: Monetary,
+        total: Monetary,
+        position: Option<Monetary>,
+        exposure: Option<Monetary>,
+    ) -> Result<(), Rejection> {
+        if self.halt.is_some() {
+            return Err(Rejection::Halted);
+        }
+
+        while let Some(oldest) = self.recent_orders.front() {
+            if *oldest <= timestamp - 60 {
+                self.recent_orders.pop_front();
+            } else {
+                break;
+            }
+        }
+        if let Some(max) = self.limits.max_orders_per_minute {
+            if self.recent_orders.len() >= max {
+                return Err(Rejection::OrderRate(self.recent_orders.len()));
+            }
+        }
+
+        if let Some(max) = self.limits.max_open_orders {
+            if self.open.len() >= max {
+                return Err(Rejection::OpenOrders(self.open.len()));
+            }
+        }
+
+        if total > 0.0 {
+            if let Some(max) = self.limits.max_market_exposure {
+                let open = self.open_notional.get(market.index()).copied().unwrap_or(0.0);
+                let market_exposure = ((open + notional) * quote_value + position.unwrap_or(0.0)) / total;
+                if market_exposure > max {
+                    return Err(Rejection::MarketExposure(market_exposure));
+                }
+            }
+
+            if let (Some(max), Some(exposure)) = (self.limits.max_asset_exposure, exposure) {
+                if exposure > max {
+                    return Err(Rejection::AssetExposure(exposure));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Records a submitted order.
+    pub fn submitted(&mut self, timestamp: i64, market: MarketId, order_id: OrderId, notional: Monetary) {
+        self.recent_orders.push_back(timestamp);
+        self.restored(market, order_id, notional);
+    }
+
+    /// Records an order that was already open, e.g. when restoring a checkpoint. It doesn't count towards the order rate.
+    pub fn restored(&mut self, market: MarketId, order_id: OrderId, notional: Monetary) {
+        if let Some((market, notional)) = self.open.insert(order_id, (market, notional)) {
+            if let Some(open) = self.open_notional.get_mut(market.index()) {
+                *open -= notional;
+            }
+        }
+        if let Some(open) = self.open_notional.get_mut(market.index()) {
+            *open += notional;
+        }
+    }
+
+    /// ID, market and value as submitted of each open order.
+    pub fn get_open_orders(&self) -> impl Iterator<Item = (OrderId, MarketId, Monetary)> + '_ {
+        self.open.iter().map(|(order_id, (market, notional))| (*order_id, *market, *notional))
+    }
+
+    /// Records an order that is no longer open, whether it was filled or not.
+    /// Its value is released as it was submitted, whatever price it was filled at.
+    pub fn closed(&mut self, order_id: OrderId) {
+        if let Some((market, notional)) = self.open.remove(&order_id) {
+            if let Some(open) = self.open_notional.get_mut(market.index()) {
+                *open -= notional;
+                if !self.open.values().any(|(other, _)| *other == market) {
+                    // Without open orders left, rounding errors of the sums are dropped.
+                    *open = 0.0;
+                }
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_risk_guard() {
+    let market = MarketId::new(0);
+    let mut guard = RiskGuard::new(RiskLimits {
+        max_orders_per_minute: Some(2),
+        max_market_exposure: Some(0.5),
+        max_drawdown: Some(0.1),
+        ..RiskLimits::default()
+    });
+    guard.resize(1);
+
+    assert!(guard.update(0, 100.0).is_none());
+    assert!(guard.check(0, market, 60.0, 1.0, 100.0, None, None).is_err());
+    assert!(guard.check(0, market, 10.0, 1.0, 100.0, None, None).is_ok());
+    // The position held counts towards the exposure of buys.
+    assert!(guard.check(0, market, 10.0, 1.0, 100.0, Some(45.0), None).is_err());
+    guard.submitted(0, market, 1, 10.0);
+    guard.submitted(1, market, 2, 10.0);
+    assert!(guard.check(2, market, 10.0, 1.0, 100.0, None, None).is_err());
+    assert!(guard.check(61, market, 10.0, 1.0, 100.0, None, None).is_ok());
+    assert!(guard.check(61, market, 31.0, 1.0, 100.0, None, None).is_err());
+    // Released as submitted, regardless of the fill price.
+    guard.closed(1);
+    assert!(guard.check(61, market, 31.0, 1.0, 100.0, None, None).is_ok());
+    guard.closed(2);
+    assert_eq!(guard.open_notional[0], 0.0);
+
+    assert!(guard.update(100, 120.0).is_none());
+    assert!(guard.update(200, 105.0).is_some());
+    assert!(guard.is_halted());
+    assert!(guard.check(300, market, 1.0, 1.0, 100.0, None, None).is_err());
+    // A drawdown halt outlasts the day, until trading is resumed.
+    assert!(guard.update(24 * 60 * 60, 105.0).is_none());
+    assert!(guard.is_halted());
+    guard.resume();
+    assert!(!guard.is_halted());
+    assert!(guard.update(24 * 60 * 60 + 1, 100.0).is_none());
+}
+
+#[tokio::test]
+async fn test_daily_loss() {
+    let mut guard = RiskGuard::new(RiskLimits {
+        max_daily_loss: Some(0.05),
+        ..RiskLimits::default()
+    });
+    assert!(guard.update(0, 100.0).is_none());
+    assert!(matches!(guard.update(60, 90.0), Some(Alert::DailyLoss(_))));
+    assert!(guard.is_halted());
+    // Lifted with the next day.
+    assert!(guard.update(24 * 60 * 60, 90.0).is_none());
+    assert!(!guard.is_halted());
+}
@@ -0,0 +1,270 @@
+use super::{AssetId, MarketGraph, MarketId, Monetary};
+use crate::indicators::Indicator;
+use crate::portfolio::PortfolioStrategy;
+use crate::sizers::PositionSizer;
+use crate::traders::{Order, Strategy, Trader, TraderStrategy};
+use binance_async::model::Side;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Creates the strategy of a market, given its base and quote asset.
+pub type StrategyFactory = Box<dyn Fn(&str, &str) -> Box<dyn Strategy>>;
+
+/// A strategy running on a subset of the markets with its own share of the capital.
+///
+/// Each sleeve keeps virtual balances that are only changed by its own orders,
+/// so its profit and loss can be attributed independently of the other sleeves.
+pub struct Sleeve<S>
+where
+    S: PositionSizer,
+{
+    name: String,
+    allocation: Monetary,
+    symbols: Vec<String>,
+    factory: Option<StrategyFactory>,
+    slots: Vec<Slot<S>>,
+    portfolio: Option<Box<dyn PortfolioStrategy>>,
+    balances: Vec<Monetary>,
+    capital: Option<Monetary>,
+}
+
+/// A strategy instance of a sleeve on a single market.
+pub struct Slot<S> {
+    pub market: MarketId,
+    pub strategy: Box<dyn Strategy>,
+    pub sizer: S,
+    pub action: Option<Order>,
+}
+
+impl<S> Sleeve<S>
+where
+    S: PositionSizer,
+{
+    /// Creates a sleeve trading with `T` on the given market symbols, or on all markets if none are given.
+    /// `allocation` is the sleeve's share of the portfolio at the time it is funded.
+    pub fn new<T>(name: &str, allocation: Monetary, symbols: &[&str]) -> Sleeve<S>
+    where
+        T: Trader + Serialize + DeserializeOwned + 'static,
+        T::Indicators: Serialize + DeserializeOwned,
+        <T::Indicators as Indicator>::Output: Serialize,
+    {
+        Sleeve::with_factory(name, allocation, symbols, Box::new(TraderStrategy::<T>::boxed))
+    }
+
+    pub fn with_factory(name: &str, allocation: Monetary, symbols: &[&str], factory: StrategyFactory) -> Sleeve<S> {
+        Sleeve {
+            name: String::from(name),
+            allocation,
+            symbols: symbols.iter().map(|symbol| String::from(*symbol)).collect(),
+            factory: Some(factory),
+            slots: Vec::new(),
+            portfolio: None,
+            balances: Vec::new(),
+            capital: None,
+        }
+    }
+
+    /// Creates a sleeve that trades across all markets with a single portfolio strategy.
+    pub fn with_portfolio(name: &str, allocation: Monetary, portfolio: Box<dyn PortfolioStrategy>) -> Sleeve<S> {
+        Sleeve {
+            name: String::from(name),
+            allocation,
+            symbols: Vec::new(),
+            factory: None,
+            slots: Vec::new(),
+            portfolio: Some(portfolio),
+            balances: Vec::new(),
+            capital: None,
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_allocation(&self) -> Monetary {
+        self.allocation
+    }
+
+    pub fn get_slots(&self) -> &[Slot<S>] {
+        &self.slots
+    }
+
+    pub fn get_slots_mut(&mut self) -> &mut [Slot<S>] {
+        &mut self.slots
+    }
+
+    pub fn get_portfolio(&self) -> Option<&dyn PortfolioStrategy> {
+        self.portfolio.as_deref()
+    }
+
+    pub fn get_portfolio_mut(&mut self) -> Option<&mut (dyn PortfolioStrategy + 'static)> {
+        self.portfolio.as_deref_mut()
+    }
+
+    /// Whether the sleeve runs a strategy on the given market. Portfolio sleeves have no per-market strategies.
+    pub fn accepts(&self, symbol: &str) -> bool {
+        self.factory.is_some() && (self.symbols.is_empty() || self.symbols.iter().any(|accepted| accepted == symbol))
+    }
+
+    pub fn add_slot(&mut self, market: MarketId, base: &str, quote: &str) {
+        if let Some(factory) = &self.factory {
+            self.slots.push(Slot {
+                market,
+                strategy: factory(base, quote),
+                sizer: S::initialize(),
+                action: None,
+            });
+        }
+    }
+
+    pub fn remove_slots(&mut self, market: MarketId) {
+        self.slots.retain(|slot| slot.market != market);
+    }
+
+    /// Starts the strategy of the given market from scratch.
+    pub fn reset(&mut self, market: MarketId, base: &str, quote: &str) {
+        if let Some(factory) = &self.factory {
+            for slot in &mut self.slots {
+                if slot.market == market {
+                    slot.strategy = factory(base, quote);
+                }
+            }
+        }
+    }
+
+    pub fn resize(&mut self, assets: usize) {
+        self.balances.resize(assets, 0.0);
+    }
+
+    pub fn get_balance(&self, asset: AssetId) -> Monetary {
+        self.balances.get(asset.index()).copied().unwrap_or(0.0)
+    }
+
+    pub fn get_capital(&self) -> Option<Monetary> {
+        self.capital
+    }
+
+    pub fn is_funded(&self) -> bool {
+        self.capital.is_some()
+    }
+
+    /// Takes the sleeve's share of each of the portfolio's balances.
+    pub fn fund<I>(&mut self, balances: I, graph: &MarketGraph)
+    where
+        I: Iterator<Item = Monetary>,
+    {
+        for (own, balance) in self.balances.iter_mut().zip(balances) {
+            *own = balance * self.allocation;
+        }
+        self.capital = Some(self.equity(graph));
+    }
+
+    /// Sets the capital and balances, e.g. from a checkpoint. Without capital the sleeve is funded again.
+    pub fn restore(&mut self, capital: Option<Monetary>, balances: &[(AssetId, Monetary)]) {
+        for balance in &mut self.balances {
+            *balance = 0.0;
+        }
+        for (asset, balance) in balances {
+            if let Some(own) = self.balances.get_mut(asset.index()) {
+                *own = *balance;
+            }
+        }
+        self.capital = capital;
+    }
+
+    /// Value of the sleeve's balances, in the reference asset.
+    pub fn equity(&self, graph: &MarketGraph) -> Monetary {
+        self.balances
+            .iter()
+            .enumerate()
+            .map(|(index, balance)| balance * graph.into_target(AssetId::new(index)))
+            .sum()
+    }
+
+    pub fn pnl(&self, graph: &MarketGraph) -> Monetary {
+        self.equity(graph) - self.capital.unwrap_or(0.0)
+    }
+
+    /// Books an executed order of this sleeve.
+    /// Returns the assets whose balance it took below zero, they are kept as they are so the attribution still adds up.
+    pub fn executed(&mut self, base: AssetId, quote: AssetId, side: &Side, quantity: Monetary, price: Monetary, fee: Monetary) -> Vec<(AssetId, Monetary)> {
+        let (base_change, quote_change) = match side {
+            Side::Buy => (quantity * (1.0 - fee), -quantity * price),
+            Side::Sell => (-quantity, quantity * price * (1.0 - fee)),
+        };
+        let mut overdrawn = Vec::new();
+        for (asset, change) in vec![(base, base_change), (quote, quote_change)] {
+            if let Some(balance) = self.balances.get_mut(asset.index()) {
+                *balance += change;
+                if *balance < 0.0 {
+                    overdrawn.push((asset, *balance));
+                }
+            }
+        }
+        overdrawn
+    }
+}
+
+#[tokio::test]
+async fn test_sleeve_funding() {
+    use super::Market;
+    use crate::sizers::FixedFractional;
+
+    let usdt = AssetId::new(0);
+    let btc = AssetId::new(1);
+    let mut market = Market::new((String::from("BTC"), String::from("USDT")), btc, usdt);
+    market.set_value(100.0);
+    let mut graph = MarketGraph::new(usdt);
+    graph.resize(2);
+    graph.update(&[market]);
+
+    let factory = |_: &str, _: &str| -> Box<dyn Strategy> { unreachable!() };
+    let mut sleeve = Sleeve::<FixedFractional>::with_factory("test", 0.25, &[], Box::new(factory));
+    sleeve.resize(2);
+    assert!(!sleeve.is_funded());
+    sleeve.fund(vec![1000.0, 2.0].into_iter(), &graph);
+    assert!(sleeve.is_funded());
+    assert_eq!((sleeve.get_balance(usdt), sleeve.get_balance(btc)), (250.0, 0.5));
+    assert_eq!(sleeve.get_capital(), Some(250.0 + 0.5 * graph.into_target(btc)));
+    assert_eq!(sleeve.pnl(&graph), 0.0);
+}
+
+#[tokio::test]
+async fn test_sleeve_pnl() {
+    use super::Market;
+    use crate::sizers::FixedFractional;
+
+    let usdt = AssetId::new(0);
+    let btc = AssetId::new(1);
+    let mut markets = vec![Market::new((String::from("BTC"), String::from("USDT")), btc, usdt)];
+    markets[0].set_value(100.0);
+    let mut graph = MarketGraph::new(usdt);
+    graph.resize(2);
+    graph.update(&markets);
+
+    let factory = |_: &str, _: &str| -> Box<dyn Strategy> { unreachable!() };
+    let mut trading = Sleeve::<FixedFractional>::with_factory("trading", 0.5, &[], Box::new(factory));
+    let mut holding = Sleeve::<FixedFractional>::with_factory("holding", 0.5, &[], Box::new(factory));
+    for sleeve in vec![&mut trading, &mut holding] {
+        sleeve.resize(2);
+        sleeve.fund(vec![400.0, 0.0].into_iter(), &graph);
+    }
+
+    // Only the sleeve that placed the order is booked, net of fees.
+    assert!(trading.executed(btc, usdt, &Side::Buy, 1.0, 100.0, 0.001).is_empty());
+    assert_eq!((trading.get_balance(usdt), trading.get_balance(btc)), (100.0, 0.999));
+    assert_eq!(holding.get_balance(usdt), 200.0);
+
+    markets[0].set_value(110.0);
+    graph.changed(MarketId::new(0));
+    graph.update(&markets);
+    let pnl = 100.0 + 0.999 * graph.into_target(btc) - 200.0;
+    assert!((trading.pnl(&graph) - pnl).abs() < 1e-9);
+    assert_eq!(holding.pnl(&graph), 0.0);
+
+    // Selling more than the sleeve holds is reported, not hidden.
+    let overdrawn = trading.executed(btc, usdt, &Side::Sell, 1.0, 110.0, 0.0);
+    assert_eq!(overdrawn.len(), 1);
+    assert_eq!(overdrawn[0].0, btc);
+    assert!((trading.get_balance(btc) + 0.001).abs() < 1e-9);
+}
@@ -0,0 +1,62 @@
+use super::Monetary;
+use serde::{Deserialize, Serialize};
+
+/// Best bid and ask of a market, and the quantities offered at them.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BookTicker {
+    pub bid: Monetary,
+    pub bid_quantity: Monetary,
+    pub ask: Monetary,
+    pub ask_quantity: Monetary,
+}
+
+impl BookTicker {
+    pub fn mid(&self) -> Monetary {
+        (self.bid + self.ask) / 2.0
+    }
+
+    /// The spread as a fraction of the mid price.
+    pub fn spread(&self) -> Monetary {
+        (self.ask - self.bid) / self.mid()
+    }
+}
+
+/// A trade on a market, at `timestamp` in seconds.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Trade {
+    pub price: Monetary,
+    pub quantity: Monetary,
+    pub timestamp: i64,
+}
+
+/// Statistics of a market over the last 24 hours.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    pub open: Monetary,
+    pub high: Monetary,
+    pub low: Monetary,
+    pub close: Monetary,
+    pub volume: Monetary,
+    pub trades: u64,
+}
+
+impl Stats {
+    /// Relative change from the open to the close.
+    pub fn change(&self) -> Monetary {
+        if self.open > 0.0 {
+            self.close / self.open - 1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_ticker() {
+    let book_ticker = BookTicker { bid: 99.0, bid_quantity: 1.0, ask: 101.0, ask_quantity: 2.0 };
+    assert_eq!(book_ticker.mid(), 100.0);
+    assert_eq!(book_ticker.spread(), 0.02);
+
+    let stats = Stats { open: 100.0, high: 120.0, low: 90.0, close: 110.0, volume: 5.0, trades: 3 };
+    assert!((stats.change() - 0.1).abs() < 1e-9);
+}
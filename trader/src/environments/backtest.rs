@@ -0,0 +1,322 @@
+use super::{fill_seconds, replay_clock, Bars, Book, Clock, DataSource, Environment, Event, FillModel, MarketData, MarketValueChange, OrderError, OrderId, RollingStats};
+use crate::economy::{Alert, AssetSymbol, Monetary, Stats, Trade};
+use crate::traders::Order;
+use crate::universe::{Candidate, UniverseSelector};
+use async_trait::async_trait;
+use binance_async::model::{ExchangeInformation, Order as QueryOrder, PriceStats};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// Seconds of values fetched from the data source at once.
+const CHUNK: i64 = 3600 * 24;
+
+/// Replays the stored values of `[start, end)` without contacting the exchange.
+///
+/// Markets are taken from a stored exchange info snapshot, orders are filled by the fill model against the stored values
+/// and, where the source has them, the stored order books. Stored values with a volume are also reported as trades,
+/// and the 24 hour statistics of the markets are rolled over the stored values before each evaluation.
+/// Every second, or every multiple of the evaluation interval, is evaluated, even if no value changed,
+/// so two runs over the same data produce the same events. After the last second, `Event::Finished` is returned.
+/// The seconds are replayed as fast as possible, or at a multiple of real time if a speed is set.
+pub struct Backtest {
+    start: i64,
+    end: i64,
+    timestamp: i64,
+    fetched: i64,
+    buffer: Vec<MarketValueChange>,
+    books: Vec<Book>,
+    source: Box<dyn DataSource + Send>,
+    exchange_info: ExchangeInformation,
+    stats: Vec<PriceStats>,
+    selector: Box<dyn UniverseSelector + Send + Sync>,
+    events: VecDeque<Event>,
+    fills: FillModel,
+    bars: Bars,
+    rolling: RollingStats,
+    speed: Option<f64>,
+    clock: Box<dyn Clock>,
+}
+
+impl Backtest {
+    /// `exchange_info` is the response of Binance's `/api/v3/exchangeInfo` endpoint, saved as JSON.
+    pub fn new<U>(selector: U, source: Box<dyn DataSource + Send>, start: i64, end: i64, exchange_info: &Path) -> io::Result<Backtest>
+    where
+        U: UniverseSelector + Send + Sync + 'static,
+    {
+        Ok(Backtest {
+            start,
+            end,
+            timestamp: start,
+            fetched: start,
+            buffer: Vec::new(),
+            books: Vec::new(),
+            source,
+            exchange_info: serde_json::from_reader(File::open(exchange_info)?)?,
+            stats: Vec::new(),
+            selector: Box::new(selector),
+            events: VecDeque::new(),
+            fills: FillModel::new(),
+            bars: Bars::new(),
+            rolling: RollingStats::new(),
+            speed: None,
+            clock: replay_clock(start, None),
+        })
+    }
+
+    /// Statistics the universe selector and the markets see, the response of Binance's `/api/v3/ticker/24hr` endpoint saved as JSON.
+    /// Without them, selectors that require statistics reject all markets.
+    pub fn set_stats(&mut self, stats: &Path) -> io::Result<()> {
+        self.stats = serde_json::from_reader(File::open(stats)?)?;
+        Ok(())
+    }
+
+    pub fn set_fill_model(&mut self, fills: FillModel) {
+        self.fills = fills;
+    }
+
+    /// Only evaluates seconds that are a multiple of `interval`, with the bars of the values since the previous evaluation.
+    pub fn set_evaluation_interval(&mut self, interval: i64) {
+        self.bars.set_interval(interval);
+    }
+
+    /// Replays `speed` seconds per second of real time instead of as fast as possible.
+    pub fn set_speed(&mut self, speed: Option<f64>) {
+        self.speed = speed;
+    }
+
+    /// Sets the balance the backtest starts with.
+    pub fn set_balance(&mut self, asset: &str, balance: Monetary) {
+        self.events.push_back(Event::SetAssetBalance(String::from(asset), balance));
+    }
+
+    async fn fetch(&mut self) {
+        let to = std::cmp::min(self.fetched + CHUNK, self.end);
+        // Descending, so popping from the back yields the values in ascending order.
+        self.buffer = self.source.values(self.fetched, to).await;
+        self.buffer.reverse();
+        // Books later than the last value of the previous chunk may still be left.
+        let mut books = self.source.books(self.fetched, to).await;
+        books.reverse();
+        books.append(&mut self.books);
+        self.books = books;
+        self.fetched = to;
+    }
+}
+
+#[async_trait]
+impl Environment for Backtest {
+    async fn initialize(&mut self) -> Result<Vec<MarketData>, ()> {
+        self.clock = replay_clock(self.start, self.speed);
+        // The saved statistics are a single snapshot, replaced by the rolled ones once a market's values are replayed.
+        for stats in &self.stats {
+            self.events.push_back(Event::SetMarketStats(stats.symbol.clone(), Stats {
+                open: stats.open_price,
+                high: stats.high_price,
+                low: stats.low_price,
+                close: stats.last_price,
+                volume: stats.volume,
+                trades: stats.count,
+            }));
+        }
+        self.universe().await
+    }
+
+    async fn universe(&mut self) -> Result<Vec<MarketData>, ()> {
+        let mut stats = self.stats
+            .iter()
+            .map(|stat| (stat.symbol.clone(), stat.clone()))
+            .collect::<HashMap<String, PriceStats>>();
+        let candidates = self.exchange_info.symbols
+            .iter()
+            .map(|market| Candidate {
+                stats: stats.remove(&market.symbol),
+                market: market.clone(),
+            })
+            .collect();
+
+        Ok(self.selector
+            .select(candidates)
+            .into_iter()
+            .map(|candidate| candidate.market)
+            .collect())
+    }
+
+    async fn poll(&mut self) -> Event {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return event;
+            }
+
+            if let Some((order, fee)) = self.fills.next() {
+                let executed = order.executed_qty;
+                return Event::ExecutedOrder(order, executed, Some(fee));
+            }
+
+            if self.buffer.is_empty() && self.fetched < self.end {
+                self.fetch().await;
+                continue;
+            }
+
+            // Books change how orders are filled, the economy only sees their best levels.
+            while self.books.last().map_or(false, |book| book.timestamp <= self.timestamp) {
+                let book = self.books.pop().unwrap();
+                if let Some(book_ticker) = book.ticker() {
+                    self.events.push_back(Event::SetMarketBookTicker(book.symbol.clone(), book_ticker));
+                }
+                self.fills.set_book(book);
+            }
+
+            if let Some(next) = self.buffer.last() {
+                if next.timestamp <= self.timestamp {
+                    let next = self.buffer.pop().unwrap();
+                    self.fills.trade(&next.symbol, next.value, next.volume, next.timestamp);
+                    self.bars.update(&next.symbol, next.value, next.volume);
+                    self.rolling.update(&next.symbol, next.timestamp, next.value, next.volume);
+                    if let Some(volume) = next.volume {
+                        self.events.push_back(Event::MarketTrade(next.symbol.clone(), Trade {
+                            price: next.value,
+                            quantity: volume,
+                            timestamp: next.timestamp,
+                        }));
+                    }
+                    return Event::SetMarketValue(next.symbol, next.value);
+                }
+            }
+
+            if self.timestamp < self.end {
+                self.timestamp += 1;
+                self.clock.wait_until(self.timestamp).await;
+                self.fills.advance(self.timestamp);
+                if self.bars.closes(self.timestamp - 1) {
+                    let bars = self.bars.close();
+                    self.events.extend(bars);
+                    let stats = self.rolling.close(self.timestamp - 1);
+                    self.events.extend(stats);
+                    self.events.push_back(Event::Evaluate(self.timestamp - 1));
+                }
+                continue;
+            }
+
+            return Event::Finished;
+        }
+    }
+
+    async fn order(&mut self, symbol: &str, order: Order) -> Result<OrderId, OrderError> {
+        Ok(self.fills.place(symbol, order, self.timestamp))
+    }
+
+    async fn cancel(&mut self, _symbol: &str, id: OrderId) -> Result<(), OrderError> {
+        if self.fills.cancel(id) {
+            Ok(())
+        } else {
+            Err(OrderError::Unknown(id))
+        }
+    }
+
+    async fn cancel_all(&mut self, symbol: &str) -> Result<(), OrderError> {
+        self.fills.cancel_all(symbol);
+        Ok(())
+    }
+
+    async fn open_orders(&mut self, symbol: &str) -> Result<Vec<QueryOrder>, OrderError> {
+        Ok(self.fills.open_orders(symbol))
+    }
+
+    async fn update_balances(&self, _balances: Vec<(&AssetSymbol, Monetary)>) {}
+
+    async fn alert(&self, _alert: &Alert) {}
+
+    /// Only stored values before the start of the backtest are used.
+    async fn history(&mut self, symbol: &str, duration: i64) -> Vec<(i64, Monetary)> {
+        let to = self.start;
+        let from = to - duration;
+
+        let changes = self.source.series(symbol, from, to).await;
+
+        fill_seconds(&changes, from, to)
+    }
+
+    fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
+    }
+}
+
+#[tokio::test]
+async fn test_deterministic() {
+    use super::FileSource;
+    use crate::economy::{Bar, Economy, Sleeve};
+    use crate::journal::FileSink;
+    use crate::sizers::FixedFractional;
+    use crate::traders::{Action, Strategy};
+    use crate::universe::Trading;
+    use std::collections::BTreeMap;
+
+    /// Buys and sells at the close of every other bar.
+    struct Alternate(bool);
+
+    impl Strategy for Alternate {
+        fn evaluate(&mut self, bar: &Bar) -> Option<Order> {
+            self.0 = !self.0;
+            let action = if self.0 { Action::Buy } else { Action::Sell };
+            Some(Order::Limit(action, 0.5, bar.close))
+        }
+
+        fn warm_up(&mut self, _bar: &Bar) {}
+
+        fn is_initialized(&self) -> bool {
+            true
+        }
+
+        fn save(&self) -> Result<serde_json::Value, serde_json::Error> {
+            Ok(serde_json::Value::Null)
+        }
+
+        fn restore(&mut self, _state: serde_json::Value) -> Result<(), serde_json::Error> {
+            Ok(())
+        }
+    }
+
+    let directory = std::env::temp_dir().join("trader-test-deterministic");
+    std::fs::create_dir_all(&directory).unwrap();
+    let exchange_info = directory.join("exchange_info.json");
+    serde_json::to_writer(File::create(&exchange_info).unwrap(), &ExchangeInformation {
+        timezone: String::from("UTC"),
+        server_time: 0,
+        rate_limits: Vec::new(),
+        symbols: vec![MarketData {
+            symbol: String::from("BTCUSDT"),
+            status: String::from("TRADING"),
+            base_asset: String::from("BTC"),
+            base_asset_precision: 8,
+            quote_asset: String::from("USDT"),
+            quote_precision: 8,
+            order_types: vec![String::from("LIMIT")],
+            iceberg_allowed: false,
+            filters: Vec::new(),
+        }],
+    }).unwrap();
+
+    let mut journals = Vec::new();
+    for run in 0..2 {
+        let mut series = BTreeMap::new();
+        series.insert(String::from("BTCUSDT"), (0..600).map(|timestamp| (timestamp, 100.0 + (timestamp as Monetary / 10.0).sin(), Some(1.0))).collect());
+        let mut backtest = Backtest::new(Trading, Box::new(FileSource::new(series)), 0, 600, &exchange_info).unwrap();
+        backtest.set_balance("USDT", 1000.0);
+        backtest.set_evaluation_interval(60);
+
+        let path = directory.join(format!("journal-{}.jsonl", run));
+        let _ = std::fs::remove_file(&path);
+        let mut economy = Economy::<_, FixedFractional>::new(backtest, "USDT");
+        economy.set_bar_interval(60);
+        let factory = |_: &str, _: &str| -> Box<dyn Strategy> { Box::new(Alternate(false)) };
+        economy.add_sleeve(Sleeve::with_factory("alternate", 1.0, &["BTCUSDT"], Box::new(factory)));
+        economy.add_sink(FileSink::new(&path).unwrap());
+        economy.run().await.unwrap();
+        journals.push(std::fs::read_to_string(&path).unwrap());
+    }
+
+    assert!(journals[0].contains("\"type\":\"fill\""));
+    assert_eq!(journals[0], journals[1]);
+}
@@ -0,0 +1,78 @@
+use super::Event;
+use crate::economy::{Bar, Monetary};
+use std::collections::HashMap;
+
+/// Aggregates the values of each market between two evaluations into bars.
+///
+/// With an interval of one second there is nothing to aggregate, so no bars are built
+/// and the economy evaluates the value of each market instead.
+pub struct Bars {
+    interval: i64,
+    open: HashMap<String, Bar>,
+}
+
+impl Bars {
+    pub fn new() -> Bars {
+        Bars {
+            interval: 1,
+            open: HashMap::new(),
+        }
+    }
+
+    pub fn set_interval(&mut self, interval: i64) {
+        self.interval = interval;
+    }
+
+    /// Whether an evaluation at `timestamp` closes the bars.
+    pub fn closes(&self, timestamp: i64) -> bool {
+        timestamp % self.interval == 0
+    }
+
+    /// Whether a bar closes in `(previous, timestamp]`, for environments that may skip seconds.
+    pub fn closes_since(&self, previous: i64, timestamp: i64) -> bool {
+        timestamp.div_euclid(self.interval) > previous.div_euclid(self.interval)
+    }
+
+    /// Adds a value of a market, with the volume traded since its previous value if it is known.
+    pub fn update(&mut self, symbol: &str, value: Monetary, volume: Option<Monetary>) {
+        if self.interval <= 1 {
+            return;
+        }
+        let volume = volume.unwrap_or(0.0);
+        match self.open.get_mut(symbol) {
+            Some(bar) => bar.update(value, volume),
+            None => {
+                let mut bar = Bar::flat(value);
+                bar.volume = volume;
+                self.open.insert(String::from(symbol), bar);
+            },
+        }
+    }
+
+    /// Closes the bars of all markets whose value changed, ordered by symbol so replays emit the same events.
+    pub fn close(&mut self) -> Vec<Event> {
+        let mut bars = self.open.drain().collect::<Vec<(String, Bar)>>();
+        bars.sort_by(|a, b| a.0.cmp(&b.0));
+        bars.into_iter().map(|(symbol, bar)| Event::SetMarketBar(symbol, bar)).collect()
+    }
+}
+
+#[tokio::test]
+async fn test_bars() {
+    let mut bars = Bars::new();
+    bars.set_interval(60);
+    bars.update("ETHUSDT", 10.0, None);
+    bars.update("BTCUSDT", 100.0, Some(1.0));
+    bars.update("BTCUSDT", 90.0, Some(2.0));
+    bars.update("BTCUSDT", 95.0, None);
+
+    let events = bars.close();
+    match &events[..] {
+        [Event::SetMarketBar(first, bar), Event::SetMarketBar(second, _)] => {
+            assert_eq!((first.as_str(), second.as_str()), ("BTCUSDT", "ETHUSDT"));
+            assert_eq!(*bar, Bar { open: 100.0, high: 100.0, low: 90.0, close: 95.0, volume: 3.0, bid: None, ask: None });
+        },
+        events => panic!("unexpected events {:?}", events),
+    }
+    assert!(bars.close().is_empty());
+}
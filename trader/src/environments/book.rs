@@ -0,0 +1,82 @@
+use crate::economy::{BookTicker, Monetary};
+use binance_async::model::Side;
+use serde::{Deserialize, Serialize};
+
+/// A snapshot of the order book of a market, as `(price, quantity)` levels with the best price first.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Book {
+    pub symbol: String,
+    pub timestamp: i64,
+    pub bids: Vec<(Monetary, Monetary)>,
+    pub asks: Vec<(Monetary, Monetary)>,
+}
+
+/// The part of an order executed against the book.
+#[derive(Debug, PartialEq)]
+pub struct Execution {
+    pub quantity: Monetary,
+    /// Average price of the executed quantity.
+    pub price: Monetary,
+    /// Quantity left on the book within the limit.
+    pub remaining: Monetary,
+}
+
+impl Book {
+    /// The best levels of both sides, if neither is empty.
+    pub fn ticker(&self) -> Option<BookTicker> {
+        let (bid, bid_quantity) = *self.bids.first()?;
+        let (ask, ask_quantity) = *self.asks.first()?;
+        Some(BookTicker { bid, bid_quantity, ask, ask_quantity })
+    }
+
+    /// Takes up to `quantity` from the levels on the other side, without paying more than `limit`.
+    /// The taken quantity is removed from the book, so later orders on the same snapshot get worse prices.
+    pub fn take(&mut self, side: &Side, quantity: Monetary, limit: Option<Monetary>) -> Execution {
+        let within = |price: Monetary| match (side, limit) {
+            (Side::Buy, Some(limit)) => price <= limit,
+            (Side::Sell, Some(limit)) => price >= limit,
+            (_, None) => true,
+        };
+        let levels = match side {
+            Side::Buy => &mut self.asks,
+            Side::Sell => &mut self.bids,
+        };
+
+        let mut taken = 0.0;
+        let mut quote = 0.0;
+        for level in levels.iter_mut() {
+            if taken >= quantity || !within(level.0) {
+                break;
+            }
+            let take = level.1.min(quantity - taken);
+            level.1 -= take;
+            taken += take;
+            quote += take * level.0;
+        }
+        levels.retain(|level| level.1 > 0.0);
+
+        Execution {
+            quantity: taken,
+            price: if taken > 0.0 { quote / taken } else { 0.0 },
+            remaining: levels.iter().filter(|level| within(level.0)).map(|level| level.1).sum(),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_take() {
+    let mut book = Book {
+        symbol: String::from("BTCUSDT"),
+        timestamp: 0,
+        bids: vec![(99.0, 1.0), (98.0, 2.0)],
+        asks: vec![(101.0, 1.0), (102.0, 1.0), (104.0, 5.0)],
+    };
+
+    assert_eq!(book.ticker(), Some(BookTicker { bid: 99.0, bid_quantity: 1.0, ask: 101.0, ask_quantity: 1.0 }));
+    let execution = book.take(&Side::Buy, 1.5, Some(102.0));
+    assert_eq!(execution, Execution { quantity: 1.5, price: 152.0 / 1.5, remaining: 0.5 });
+    let execution = book.take(&Side::Buy, 2.0, Some(102.0));
+    assert_eq!(execution, Execution { quantity: 0.5, price: 102.0, remaining: 0.0 });
+    let execution = book.take(&Side::Sell, 2.0, None);
+    assert_eq!(execution, Execution { quantity: 2.0, price: 98.5, remaining: 1.0 });
+}
@@ -0,0 +1,119 @@
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+/// The time of an environment. Everything that depends on time asks the clock instead of the system,
+/// so timeouts and intervals behave the same whether a run is live, replayed in real time or as fast as possible.
+#[async_trait]
+pub trait Clock: Send + Sync {
+    /// Milliseconds since the unix epoch.
+    fn now_millis(&self) -> i64;
+
+    /// Seconds since the unix epoch.
+    fn now(&self) -> i64 {
+        self.now_millis().div_euclid(1000)
+    }
+
+    /// Returns once the clock reached `timestamp`, in seconds.
+    async fn wait_until(&self, timestamp: i64);
+}
+
+/// The time of the system.
+pub struct SystemClock;
+
+#[async_trait]
+impl Clock for SystemClock {
+    fn now_millis(&self) -> i64 {
+        SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_millis() as i64
+    }
+
+    async fn wait_until(&self, timestamp: i64) {
+        let remaining = timestamp * 1000 - self.now_millis();
+        if remaining > 0 {
+            tokio::time::delay_for(Duration::from_millis(remaining as u64)).await;
+        }
+    }
+}
+
+/// A clock that only moves when it is waited on, and then jumps to the awaited time right away.
+/// Replays with it run as fast as possible.
+pub struct SimulatedClock {
+    millis: AtomicI64,
+}
+
+impl SimulatedClock {
+    pub fn new(timestamp: i64) -> SimulatedClock {
+        SimulatedClock {
+            millis: AtomicI64::new(timestamp * 1000),
+        }
+    }
+}
+
+#[async_trait]
+impl Clock for SimulatedClock {
+    fn now_millis(&self) -> i64 {
+        self.millis.load(Ordering::SeqCst)
+    }
+
+    async fn wait_until(&self, timestamp: i64) {
+        self.millis.fetch_max(timestamp * 1000, Ordering::SeqCst);
+    }
+}
+
+/// A clock running `speed` times as fast as the system's, starting at `origin`.
+/// It stands still until it is first waited on, so the time spent on initialization isn't skipped.
+pub struct ScaledClock {
+    origin: i64,
+    speed: f64,
+    started: Mutex<Option<Instant>>,
+}
+
+impl ScaledClock {
+    pub fn new(origin: i64, speed: f64) -> ScaledClock {
+        ScaledClock {
+            origin,
+            speed,
+            started: Mutex::new(None),
+        }
+    }
+}
+
+#[async_trait]
+impl Clock for ScaledClock {
+    fn now_millis(&self) -> i64 {
+        let elapsed = self.started.lock().unwrap().map_or(0.0, |started| started.elapsed().as_millis() as f64);
+        self.origin * 1000 + (elapsed * self.speed) as i64
+    }
+
+    async fn wait_until(&self, timestamp: i64) {
+        self.started.lock().unwrap().get_or_insert_with(Instant::now);
+        let remaining = timestamp * 1000 - self.now_millis();
+        if remaining > 0 {
+            tokio::time::delay_for(Duration::from_millis((remaining as f64 / self.speed) as u64)).await;
+        }
+    }
+}
+
+/// Clock of a replay starting at `origin`, at a multiple of real time or as fast as possible.
+pub fn replay_clock(origin: i64, speed: Option<f64>) -> Box<dyn Clock> {
+    match speed {
+        Some(speed) => Box::new(ScaledClock::new(origin, speed)),
+        None => Box::new(SimulatedClock::new(origin)),
+    }
+}
+
+#[tokio::test]
+async fn test_clock() {
+    let clock = SimulatedClock::new(100);
+    clock.wait_until(160).await;
+    clock.wait_until(130).await;
+    assert_eq!(clock.now(), 160);
+
+    let clock = ScaledClock::new(100, 1000.0);
+    assert_eq!(clock.now(), 100);
+    let start = Instant::now();
+    clock.wait_until(103).await;
+    assert!(clock.now() >= 103);
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
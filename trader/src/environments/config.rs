@@ -0,0 +1,122 @@
+use super::FillConfig;
+use crate::economy::Monetary;
+use crate::traders::ConfigError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Settings of a simulation or backtest, everything needed besides the data and the strategy to reproduce a run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimulationConfig {
+    /// Balance of each asset the run starts with.
+    pub balances: BTreeMap<String, Monetary>,
+    /// First second of the run, the first recorded ticker if not set.
+    pub start: Option<i64>,
+    /// Second the run ends at. With a start and an end, the run is a backtest.
+    pub end: Option<i64>,
+    /// Seconds between evaluations of the strategies.
+    pub evaluation_interval: i64,
+    /// Whether a simulation switches to the live tickers once it caught up with the recorded ones.
+    /// Otherwise, it keeps replaying the recorded tickers as they come in.
+    pub realtime: bool,
+    /// Seconds replayed per second of real time, as many as possible if not set.
+    pub speed: Option<f64>,
+    pub fills: FillConfig,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> SimulationConfig {
+        SimulationConfig {
+            balances: BTreeMap::new(),
+            start: None,
+            end: None,
+            evaluation_interval: 1,
+            realtime: true,
+            speed: None,
+            fills: FillConfig::default(),
+        }
+    }
+}
+
+fn invalid(path: &str, reason: &str) -> Result<(), ConfigError> {
+    Err(ConfigError::Invalid(String::from(path), String::from(reason)))
+}
+
+impl SimulationConfig {
+    /// Reads a TOML file if the path ends in `.toml`, and JSON otherwise.
+    pub fn load(path: &Path) -> Result<SimulationConfig, ConfigError> {
+        let content = fs::read_to_string(path).map_err(ConfigError::Io)?;
+        let config = if path.extension().map_or(false, |extension| extension == "toml") {
+            toml::from_str::<SimulationConfig>(&content).map_err(|error| ConfigError::Parse(error.to_string()))?
+        } else {
+            serde_json::from_str::<SimulationConfig>(&content).map_err(|error| ConfigError::Parse(error.to_string()))?
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        for (asset, balance) in &self.balances {
+            if *balance < 0.0 {
+                return invalid(&format!("balances.{}", asset), "balance must not be negative");
+            }
+        }
+        if let (Some(start), Some(end)) = (self.start, self.end) {
+            if start >= end {
+                return invalid("end", "end must be after the start");
+            }
+        }
+        if self.evaluation_interval <= 0 {
+            return invalid("evaluation_interval", "interval must be positive");
+        }
+        if self.speed.map_or(false, |speed| speed <= 0.0) {
+            return invalid("speed", "speed must be positive");
+        }
+
+        let fills = &self.fills;
+        if fills.latency < 0 || fills.expiry.map_or(false, |expiry| expiry <= 0) {
+            return invalid("fills", "latency must not be negative and expiry must be positive");
+        }
+        if !(fills.participation > 0.0 && fills.participation <= 1.0) {
+            return invalid("fills.participation", "participation must be in (0, 1]");
+        }
+        if !(0.0..1.0).contains(&fills.maker_fee) || !(0.0..1.0).contains(&fills.taker_fee) {
+            return invalid("fills", "fees must be in [0, 1)");
+        }
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_config() {
+    let config: SimulationConfig = toml::from_str(r#"
+        start = 1600000000
+        end = 1600086400
+        evaluation_interval = 60
+
+        [balances]
+        USDT = 150.0
+        BTC = 0.01
+
+        [fills]
+        queue = { behind = 2.0 }
+        taker_fee = 0.00075
+    "#).unwrap();
+
+    assert!(config.validate().is_ok());
+    assert!(config.realtime);
+    assert_eq!(config.balances.len(), 2);
+    assert_eq!(config.fills.queue, super::Queue::Behind(2.0));
+    assert_eq!((config.fills.maker_fee, config.fills.taker_fee), (0.001, 0.00075));
+
+    let config = SimulationConfig {
+        evaluation_interval: 0,
+        ..config
+    };
+    match config.validate() {
+        Err(ConfigError::Invalid(path, _)) => assert_eq!(path, "evaluation_interval"),
+        _ => panic!("expected validation error"),
+    }
+}
@@ -0,0 +1,81 @@
+use crate::economy::Monetary;
+use std::convert::{TryFrom, TryInto};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const MAGIC: &[u8; 8] = b"TRDRCOL2";
+
+/// Writes a series in the binary columnar format: the magic bytes, the number of values,
+/// the first timestamp, the gaps between timestamps as `u32`, the values and the volumes as `f64`, all little endian.
+/// Unknown volumes are written as NaN.
+pub fn write(path: &Path, series: &[(i64, Monetary, Option<Monetary>)]) -> io::Result<()> {
+    let mut bytes = Vec::with_capacity(MAGIC.len() + 16 + series.len() * 20);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&(series.len() as u64).to_le_bytes());
+    let first = series.first().map_or(0, |(timestamp, _, _)| *timestamp);
+    bytes.extend_from_slice(&first.to_le_bytes());
+    let mut previous = first;
+    for (timestamp, _, _) in series {
+        let gap = u32::try_from(timestamp - previous).map_err(|_| io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("gap between {} and {} doesn't fit a binary series", previous, timestamp),
+        ))?;
+        bytes.extend_from_slice(&gap.to_le_bytes());
+        previous = *timestamp;
+    }
+    for (_, value, _) in series {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    for (_, _, volume) in series {
+        bytes.extend_from_slice(&volume.unwrap_or(Monetary::NAN).to_le_bytes());
+    }
+    fs::write(path, bytes)
+}
+
+pub fn read(path: &Path) -> io::Result<Vec<(i64, Monetary, Option<Monetary>)>> {
+    let bytes = fs::read(path)?;
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("{} is not a binary series", path.display()));
+
+    if bytes.len() < MAGIC.len() + 16 || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(invalid());
+    }
+    let count = usize::try_from(u64::from_le_bytes(bytes[8..16].try_into().unwrap())).map_err(|_| invalid())?;
+    let mut timestamp = i64::from_le_bytes(bytes[16..24].try_into().unwrap());
+    let gaps = &bytes[24..];
+    let length = count.checked_mul(20).ok_or_else(invalid)?;
+    if gaps.len() != length {
+        return Err(invalid());
+    }
+    let (gaps, values) = gaps.split_at(count * 4);
+    let (values, volumes) = values.split_at(count * 8);
+
+    let mut series = Vec::with_capacity(count);
+    for ((gap, value), volume) in gaps.chunks_exact(4).zip(values.chunks_exact(8)).zip(volumes.chunks_exact(8)) {
+        timestamp += u32::from_le_bytes(gap.try_into().unwrap()) as i64;
+        let volume = Monetary::from_le_bytes(volume.try_into().unwrap());
+        let volume = if volume.is_nan() { None } else { Some(volume) };
+        series.push((timestamp, Monetary::from_le_bytes(value.try_into().unwrap()), volume));
+    }
+    Ok(series)
+}
+
+#[tokio::test]
+async fn test_binary() {
+    let directory = std::env::temp_dir().join("trader-test-binary");
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("BTCUSDT.bin");
+
+    let series = vec![(60, 9000.0, Some(1.5)), (61, 9100.0, None), (120, 9200.0, Some(0.0))];
+    write(&path, &series).unwrap();
+    assert_eq!(read(&path).unwrap(), series);
+
+    let error = write(&path, &[(0, 9000.0, None), (1 << 33, 9100.0, None)]).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+    bytes.extend_from_slice(&0i64.to_le_bytes());
+    fs::write(&path, bytes).unwrap();
+    assert_eq!(read(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,266 @@
+use super::{binary, Book, DataSource, MarketValueChange};
+use crate::economy::Monetary;
+use async_trait::async_trait;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BinaryHeap};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+/// Keeps all series in memory and merges them by time when values are requested.
+///
+/// A directory may contain any mix of the following files:
+/// - `*.csv` exported from the `tickers` table, with the columns `symbol,value,timestamp`.
+/// - Binance kline or aggTrades archives, e.g. `BTCUSDT-1m-2020-01.zip` or `BTCUSDT-aggTrades-2020-01.zip`,
+///   zipped or extracted. The symbol is taken from the file name. Klines are valued at their close, at their close time.
+///   Both keep their traded volume.
+/// - `*.bin` series in the binary columnar format, e.g. `BTCUSDT.bin`, as written by `save`.
+/// - `*.jsonl` order book recordings, one JSON `Book` per line.
+///
+/// If several files have a value for the same market and second, the file sorting last by name wins,
+/// and their volumes are added up.
+pub struct FileSource {
+    series: BTreeMap<String, Vec<(i64, Monetary, Option<Monetary>)>>,
+    books: Vec<Book>,
+}
+
+impl FileSource {
+    pub fn new(series: BTreeMap<String, Vec<(i64, Monetary, Option<Monetary>)>>) -> FileSource {
+        let mut source = FileSource {
+            series,
+            books: Vec::new(),
+        };
+        for series in source.series.values_mut() {
+            normalize(series);
+        }
+        source
+    }
+
+    pub fn set_books(&mut self, mut books: Vec<Book>) {
+        books.sort_by(|a, b| (a.timestamp, &a.symbol).cmp(&(b.timestamp, &b.symbol)));
+        self.books = books;
+    }
+
+    pub fn load(directory: &Path) -> io::Result<FileSource> {
+        let mut paths = fs::read_dir(directory)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        paths.sort();
+
+        let mut series = BTreeMap::new();
+        let mut books = Vec::new();
+        for path in paths {
+            let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default().to_string();
+            let symbol = name.split(|c| c == '-' || c == '.').next().unwrap_or_default().to_string();
+            match path.extension().and_then(|extension| extension.to_str()) {
+                Some("bin") => {
+                    series.entry(symbol).or_insert_with(Vec::new).extend(binary::read(&path)?);
+                },
+                Some("csv") => {
+                    parse(BufReader::new(File::open(&path)?), &symbol, &name, &mut series)?;
+                },
+                Some("zip") => {
+                    let mut archive = zip::ZipArchive::new(File::open(&path)?)
+                        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+                    for index in 0..archive.len() {
+                        let file = archive
+                            .by_index(index)
+                            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+                        parse(BufReader::new(file), &symbol, &name, &mut series)?;
+                    }
+                },
+                Some("jsonl") => {
+                    for line in BufReader::new(File::open(&path)?).lines() {
+                        let line = line?;
+                        if !line.trim().is_empty() {
+                            books.push(serde_json::from_str::<Book>(&line)?);
+                        }
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        let mut source = FileSource::new(series);
+        source.set_books(books);
+        Ok(source)
+    }
+
+    /// Writes every series to `<symbol>.bin` in the given directory, which loads much faster than archives,
+    /// and the books to `books.jsonl`.
+    pub fn save(&self, directory: &Path) -> io::Result<()> {
+        fs::create_dir_all(directory)?;
+        for (symbol, series) in &self.series {
+            binary::write(&directory.join(format!("{}.bin", symbol)), series)?;
+        }
+        if !self.books.is_empty() {
+            let mut file = io::BufWriter::new(File::create(directory.join("books.jsonl"))?);
+            for book in &self.books {
+                writeln!(file, "{}", serde_json::to_string(book)?)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses one CSV file line by line, its format is told apart by the number of columns.
+fn parse<R>(reader: R, symbol: &str, name: &str, series: &mut BTreeMap<String, Vec<(i64, Monetary, Option<Monetary>)>>) -> io::Result<()>
+where
+    R: BufRead,
+{
+    let invalid = |line: &str| io::Error::new(io::ErrorKind::InvalidData, format!("unexpected line in {}: {}", name, line));
+
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let fields = line.trim().split(',').collect::<Vec<&str>>();
+        let (symbol, timestamp, value, volume) = match fields.len() {
+            // symbol, value, timestamp
+            3 => (fields[0], fields[2].parse::<i64>().ok(), fields[1].parse::<Monetary>().ok(), Some(None)),
+            // agg trade id, price, quantity, first trade id, last trade id, time, is buyer maker, [is best match]
+            7 | 8 => (
+                symbol,
+                fields[5].parse::<i64>().ok().map(|time| time / 1000),
+                fields[1].parse::<Monetary>().ok(),
+                fields[2].parse::<Monetary>().ok().map(Some),
+            ),
+            // open time, open, high, low, close, volume, close time, quote volume, trades, taker base, taker quote, ignore
+            12 => (
+                symbol,
+                fields[6].parse::<i64>().ok().map(|time| time / 1000),
+                fields[4].parse::<Monetary>().ok(),
+                fields[5].parse::<Monetary>().ok().map(Some),
+            ),
+            1 if fields[0].is_empty() => continue,
+            _ => return Err(invalid(&line)),
+        };
+        match (timestamp, value, volume) {
+            (Some(timestamp), Some(value), Some(volume)) => {
+                series.entry(String::from(symbol)).or_insert_with(Vec::new).push((timestamp, value, volume));
+            },
+            // Header line.
+            _ if number == 0 => {},
+            _ => return Err(invalid(&line)),
+        }
+    }
+    Ok(())
+}
+
+/// Sorts a series by time and keeps the last value and the total volume of each second.
+fn normalize(series: &mut Vec<(i64, Monetary, Option<Monetary>)>) {
+    series.sort_by_key(|(timestamp, _, _)| *timestamp);
+    let mut normalized: Vec<(i64, Monetary, Option<Monetary>)> = Vec::with_capacity(series.len());
+    for (timestamp, value, volume) in series.drain(..) {
+        match normalized.last_mut() {
+            Some(last) if last.0 == timestamp => {
+                last.1 = value;
+                last.2 = match (last.2, volume) {
+                    (Some(last), Some(volume)) => Some(last + volume),
+                    (last, volume) => last.or(volume),
+                };
+            },
+            _ => normalized.push((timestamp, value, volume)),
+        }
+    }
+    *series = normalized;
+}
+
+/// Index of the first value at or after `timestamp`.
+fn position(series: &[(i64, Monetary, Option<Monetary>)], timestamp: i64) -> usize {
+    match series.binary_search_by_key(&timestamp, |(timestamp, _, _)| *timestamp) {
+        Ok(index) | Err(index) => index,
+    }
+}
+
+#[async_trait]
+impl DataSource for FileSource {
+    async fn values(&mut self, from: i64, to: i64) -> Vec<MarketValueChange> {
+        let symbols = self.series.keys().collect::<Vec<&String>>();
+        let series = self.series.values().collect::<Vec<&Vec<(i64, Monetary, Option<Monetary>)>>>();
+
+        // K-way merge, the heap holds the next value of each series. Ties are broken by symbol.
+        let mut heap = BinaryHeap::new();
+        for (index, series) in series.iter().enumerate() {
+            let start = position(series, from);
+            if start < series.len() && series[start].0 < to {
+                heap.push(Reverse((series[start].0, index, start)));
+            }
+        }
+
+        let mut changes = Vec::new();
+        while let Some(Reverse((timestamp, index, position))) = heap.pop() {
+            changes.push(MarketValueChange {
+                symbol: symbols[index].clone(),
+                value: series[index][position].1,
+                volume: series[index][position].2,
+                timestamp,
+            });
+            if let Some((next, _, _)) = series[index].get(position + 1) {
+                if *next < to {
+                    heap.push(Reverse((*next, index, position + 1)));
+                }
+            }
+        }
+        changes
+    }
+
+    async fn series(&mut self, symbol: &str, from: i64, to: i64) -> Vec<(i64, Monetary)> {
+        if let Some(series) = self.series.get(symbol) {
+            series[position(series, from)..position(series, to)]
+                .iter()
+                .map(|(timestamp, value, _)| (*timestamp, *value))
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    async fn books(&mut self, from: i64, to: i64) -> Vec<Book> {
+        let position = |timestamp: i64| match self.books.binary_search_by(|book| {
+            if book.timestamp < timestamp { Ordering::Less } else { Ordering::Greater }
+        }) {
+            Ok(index) | Err(index) => index,
+        };
+        self.books[position(from)..position(to)].to_vec()
+    }
+}
+
+#[tokio::test]
+async fn test_merge() {
+    let mut input = BTreeMap::new();
+    input.insert(String::from("ETHUSDT"), vec![(3, 200.0, Some(1.0)), (1, 190.0, None), (3, 201.0, Some(2.0))]);
+    input.insert(String::from("BTCUSDT"), vec![(1, 9000.0, None), (2, 9100.0, Some(0.5)), (5, 9200.0, None)]);
+    let mut source = FileSource::new(input);
+
+    let changes = source.values(1, 5).await
+        .into_iter()
+        .map(|change| (change.timestamp, change.symbol, change.value, change.volume))
+        .collect::<Vec<(i64, String, Monetary, Option<Monetary>)>>();
+    assert_eq!(changes, vec![
+        (1, String::from("BTCUSDT"), 9000.0, None),
+        (1, String::from("ETHUSDT"), 190.0, None),
+        (2, String::from("BTCUSDT"), 9100.0, Some(0.5)),
+        (3, String::from("ETHUSDT"), 201.0, Some(3.0)),
+    ]);
+
+    let directory = std::env::temp_dir().join("trader-test-merge");
+    source.save(&directory).unwrap();
+    let mut loaded = FileSource::load(&directory).unwrap();
+    assert_eq!(loaded.series("BTCUSDT", 0, 10).await, vec![(1, 9000.0), (2, 9100.0), (5, 9200.0)]);
+    assert_eq!(loaded.values(2, 3).await[0].volume, Some(0.5));
+}
+
+#[tokio::test]
+async fn test_parse() {
+    let mut series = BTreeMap::new();
+    parse("symbol,value,timestamp\nBTCUSDT,9000.5,60\n".as_bytes(), "export", "export.csv", &mut series).unwrap();
+    parse(
+        "1577836800000,7195.24,7196.25,7175.46,7177.02,511.81,1577836859999,3675857.30,7640,216.92,1557802.08,0\n".as_bytes(),
+        "BTCUSDT",
+        "BTCUSDT-1m-2020-01.csv",
+        &mut series,
+    ).unwrap();
+    parse("1,0.02,3.5,1,2,1577836801500,True,True\n".as_bytes(), "ETHBTC", "ETHBTC-aggTrades-2020-01.csv", &mut series).unwrap();
+    // Klines are valued at their close time, not at their open time.
+    assert_eq!(series["BTCUSDT"], vec![(60, 9000.5, None), (1577836859, 7177.02, Some(511.81))]);
+    assert_eq!(series["ETHBTC"], vec![(1577836801, 0.02, Some(3.5))]);
+}
@@ -0,0 +1,138 @@
+mod binary;
+mod files;
+
+pub use files::FileSource;
+
+use super::Book;
+use crate::economy::Monetary;
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+pub struct MarketValueChange {
+    pub symbol: String,
+    pub value: Monetary,
+    /// Quantity traded since the previous value, in the base asset, if the source recorded it.
+    pub volume: Option<Monetary>,
+    pub timestamp: i64,
+}
+
+/// Stored values of markets, replayed by a backtest.
+#[async_trait]
+pub trait DataSource {
+    /// Value changes of all markets in `[from, to)`, ordered by timestamp and symbol.
+    async fn values(&mut self, from: i64, to: i64) -> Vec<MarketValueChange>;
+
+    /// Value changes of one market in `[from, to)`, ordered by timestamp.
+    async fn series(&mut self, symbol: &str, from: i64, to: i64) -> Vec<(i64, Monetary)>;
+
+    /// Order book snapshots of all markets in `[from, to)`, ordered by timestamp.
+    async fn books(&mut self, _from: i64, _to: i64) -> Vec<Book> {
+        Vec::new()
+    }
+}
+
+struct TickerRow {
+    symbol: String,
+    value: Monetary,
+    timestamp: i64,
+}
+
+struct BookRow {
+    symbol: String,
+    timestamp: i64,
+    /// Casts are nullable to sqlx, even though the columns are not.
+    bids: Option<String>,
+    asks: Option<String>,
+}
+
+/// Reads the `tickers` and `depths` tables.
+pub struct PostgresSource {
+    pool: PgPool,
+}
+
+impl PostgresSource {
+    pub async fn new(url: &str) -> PostgresSource {
+        PostgresSource {
+            pool: PgPool::new(url).await.unwrap(),
+        }
+    }
+}
+
+#[async_trait]
+impl DataSource for PostgresSource {
+    /// The `tickers` table has no volume.
+    async fn values(&mut self, from: i64, to: i64) -> Vec<MarketValueChange> {
+        sqlx::query_as!(
+                TickerRow,
+                "
+                    SELECT symbol, value, timestamp
+                    FROM tickers
+                    WHERE timestamp >= $1::BIGINT
+                    AND timestamp < $2::BIGINT
+                    ORDER BY timestamp ASC, symbol ASC
+                ",
+                from,
+                to
+            )
+            .fetch_all(&self.pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|row| MarketValueChange {
+                symbol: row.symbol,
+                value: row.value,
+                volume: None,
+                timestamp: row.timestamp,
+            })
+            .collect()
+    }
+
+    async fn series(&mut self, symbol: &str, from: i64, to: i64) -> Vec<(i64, Monetary)> {
+        sqlx::query_as!(
+                TickerRow,
+                "
+                    SELECT symbol, value, timestamp
+                    FROM tickers
+                    WHERE symbol = $1
+                    AND timestamp >= $2::BIGINT
+                    AND timestamp < $3::BIGINT
+                    ORDER BY timestamp ASC
+                ",
+                symbol,
+                from,
+                to
+            )
+            .fetch_all(&self.pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|row| (row.timestamp, row.value))
+            .collect()
+    }
+
+    async fn books(&mut self, from: i64, to: i64) -> Vec<Book> {
+        sqlx::query_as!(
+                BookRow,
+                "
+                    SELECT symbol, timestamp, bids::TEXT AS bids, asks::TEXT AS asks
+                    FROM depths
+                    WHERE timestamp >= $1::BIGINT
+                    AND timestamp < $2::BIGINT
+                    ORDER BY timestamp ASC, symbol ASC
+                ",
+                from,
+                to
+            )
+            .fetch_all(&self.pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter_map(|row| Some(Book {
+                bids: serde_json::from_str(&row.bids?).ok()?,
+                asks: serde_json::from_str(&row.asks?).ok()?,
+                symbol: row.symbol,
+                timestamp: row.timestamp,
+            }))
+            .collect()
+    }
+}
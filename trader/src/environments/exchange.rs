@@ -0,0 +1,191 @@
+use super::{Event, MarketData};
+use crate::economy::Monetary;
+use crate::universe::{Candidate, UniverseSelector};
+use binance_async::{model::{KlineSummaries, PriceStats}, Binance};
+use std::collections::{HashMap, HashSet};
+
+/// Selects the exchange's markets and keeps track of their statuses, for environments connected to Binance.
+pub struct Listings {
+    selector: Box<dyn UniverseSelector + Send + Sync>,
+    statuses: HashMap<String, String>,
+}
+
+impl Listings {
+    pub fn new<U>(selector: U) -> Listings
+    where
+        U: UniverseSelector + Send + Sync + 'static,
+    {
+        Listings {
+            selector: Box::new(selector),
+            statuses: HashMap::new(),
+        }
+    }
+
+    pub async fn select(&mut self, binance: &Binance) -> Result<Vec<MarketData>, ()> {
+        let mut stats = binance
+            .get_24h_price_stats_all()
+            .map_err(|_| ())?
+            .await
+            .map_err(|_| ())?
+            .into_iter()
+            .map(|stat| (stat.symbol.clone(), stat))
+            .collect::<HashMap<String, PriceStats>>();
+        let exchange_info = binance.get_exchange_info().map_err(|_| ())?.await.map_err(|_| ())?;
+        for market in &exchange_info.symbols {
+            self.statuses.insert(market.symbol.clone(), market.status.clone());
+        }
+
+        let candidates = exchange_info.symbols
+            .into_iter()
+            .map(|market| Candidate {
+                stats: stats.remove(&market.symbol),
+                market,
+            })
+            .collect();
+
+        Ok(self.selector
+            .select(candidates)
+            .into_iter()
+            .map(|candidate| candidate.market)
+            .collect())
+    }
+
+    /// Compares the exchange's markets with the last check and returns events for listings, status changes and delistings.
+    /// New listings have no statistics yet, so they are only added if the selector doesn't require any.
+    pub async fn check(&mut self, binance: &Binance) -> Vec<Event> {
+        let exchange_info = match binance.get_exchange_info() {
+            Ok(request) => request.await,
+            Err(error) => Err(error),
+        };
+        let exchange_info = match exchange_info {
+            Ok(exchange_info) => exchange_info,
+            Err(error) => {
+                tracing::warn!(%error, "checking markets failed");
+                return Vec::new();
+            },
+        };
+
+        let mut events = Vec::new();
+        let mut listed = HashSet::new();
+        for market in exchange_info.symbols {
+            listed.insert(market.symbol.clone());
+            match self.statuses.get(&market.symbol) {
+                Some(status) if *status == market.status => {},
+                Some(_) if market.status == "TRADING" => {
+                    events.push(Event::MarketResumed(market.symbol.clone()));
+                },
+                Some(_) => {
+                    events.push(Event::MarketHalted(market.symbol.clone()));
+                },
+                None => {
+                    let candidate = Candidate {
+                        market: market.clone(),
+                        stats: None,
+                    };
+                    if !self.selector.select(vec![candidate]).is_empty() {
+                        events.push(Event::MarketAdded(market.clone()));
+                    }
+                },
+            }
+            self.statuses.insert(market.symbol, market.status);
+        }
+
+        let removed = self.statuses
+            .keys()
+            .filter(|symbol| !listed.contains(*symbol))
+            .cloned()
+            .collect::<Vec<String>>();
+        for symbol in removed {
+            self.statuses.remove(&symbol);
+            events.push(Event::MarketRemoved(symbol));
+        }
+
+        events
+    }
+}
+
+/// Values of a market in `[from, to)` from one minute klines, valued at their close when they close.
+/// Returns no values if the klines can't be fetched.
+pub async fn klines(binance: &Binance, symbol: &str, from: i64, to: i64) -> Vec<(i64, Monetary)> {
+    let mut changes = Vec::new();
+    let mut start = from;
+    while start < to {
+        let fetched = match binance.get_klines(symbol, "1m", 1000, (start * 1000) as u64, (to * 1000 - 1) as u64) {
+            Ok(request) => request.await,
+            Err(error) => Err(error),
+        };
+        let klines = match fetched {
+            Ok(KlineSummaries::AllKlineSummaries(klines)) => klines,
+            Err(error) => {
+                tracing::warn!(%error, symbol, "fetching klines failed");
+                return Vec::new();
+            },
+        };
+        if let Some(last) = klines.last() {
+            start = last.close_time / 1000 + 1;
+        } else {
+            break;
+        }
+        for kline in klines {
+            changes.push((kline.close_time / 1000, kline.close));
+        }
+    }
+    changes
+}
+
+#[tokio::test]
+async fn test_listings() {
+    use super::mock::MockExchange;
+    use crate::universe::Candidate;
+
+    /// Selects every market but the ones of the given base asset.
+    struct Except(&'static str);
+
+    impl UniverseSelector for Except {
+        fn select(&self, candidates: Vec<Candidate>) -> Vec<Candidate> {
+            candidates.into_iter().filter(|candidate| candidate.market.base_asset != self.0).collect()
+        }
+    }
+
+    let market = |symbol: &str, base: &str, status: &str| MarketData {
+        symbol: String::from(symbol),
+        status: String::from(status),
+        base_asset: String::from(base),
+        base_asset_precision: 8,
+        quote_asset: String::from("USDT"),
+        quote_precision: 8,
+        order_types: vec![String::from("LIMIT")],
+        iceberg_allowed: false,
+        filters: Vec::new(),
+    };
+    let exchange = MockExchange::start(vec![market("BTCUSDT", "BTC", "TRADING"), market("ETHUSDT", "ETH", "TRADING")], Vec::new());
+    let binance = exchange.binance();
+    let mut listings = Listings::new(Except("XYZ"));
+    assert_eq!(listings.select(&binance).await.unwrap().len(), 2);
+    assert!(listings.check(&binance).await.is_empty());
+
+    exchange.set_markets(vec![
+        market("BTCUSDT", "BTC", "BREAK"),
+        market("ETHUSDT", "ETH", "TRADING"),
+        market("NEWUSDT", "NEW", "TRADING"),
+        market("XYZUSDT", "XYZ", "TRADING"),
+    ]);
+    match &listings.check(&binance).await[..] {
+        [Event::MarketHalted(halted), Event::MarketAdded(added)] => {
+            assert_eq!((halted.as_str(), added.symbol.as_str()), ("BTCUSDT", "NEWUSDT"));
+        },
+        events => panic!("unexpected events {:?}", events),
+    }
+
+    exchange.set_markets(vec![
+        market("BTCUSDT", "BTC", "TRADING"),
+        market("NEWUSDT", "NEW", "TRADING"),
+        market("XYZUSDT", "XYZ", "TRADING"),
+    ]);
+    match &listings.check(&binance).await[..] {
+        [Event::MarketResumed(resumed), Event::MarketRemoved(removed)] => {
+            assert_eq!((resumed.as_str(), removed.as_str()), ("BTCUSDT", "ETHUSDT"));
+        },
+        events => panic!("unexpected events {:?}", events),
+    }
+}
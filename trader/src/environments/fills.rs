@@ -0,0 +1,392 @@
+use super::{Book, OrderId};
+use crate::economy::Monetary;
+use crate::traders::{Action, Order};
+use binance_async::model::{Order as QueryOrder, Side};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// Where resting orders are assumed to be in the queue of their price level.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Queue {
+    /// Orders are filled as soon as the price touches their limit.
+    Front,
+    /// Orders are filled by the volume traded at their limit after this much volume, in the base asset, traded there.
+    /// If the traded volume isn't known, touching the limit doesn't fill.
+    Behind(Monetary),
+    /// Orders are only filled once the price trades through their limit.
+    Back,
+}
+
+/// Price paid by orders taking liquidity, as a fraction of the price:
+/// `fixed` plus `impact` per unit of the quote asset traded.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Slippage {
+    pub fixed: Monetary,
+    pub impact: Monetary,
+}
+
+struct Pending {
+    order: QueryOrder,
+    limit: Option<Monetary>,
+    arrival: i64,
+    expiry: Option<i64>,
+    resting: bool,
+    /// Volume that has to trade at the limit before the order is filled on touch.
+    ahead: Monetary,
+    /// Quote asset and fee weighted quantity of all fills so far.
+    quote: Monetary,
+    fees: Monetary,
+}
+
+/// Decides when and at which price simulated orders are filled.
+///
+/// Orders reach the exchange `latency` seconds after they were placed. Market orders and limit orders crossing
+/// the last price are filled right away as takers, paying slippage. Other limit orders rest on the book as makers,
+/// and are filled at their limit once later prices reach them, according to the queue assumption. If the traded
+/// volume is known, each update fills at most `participation` of it. Orders still open after `expiry` seconds
+/// expire with whatever was filled.
+///
+/// Once a book is known for a market, orders taking liquidity walk its levels instead of paying slippage.
+/// Marketable limit orders rest with what is left within their limit, market orders expire with it.
+pub struct FillModel {
+    config: FillConfig,
+    prices: HashMap<String, Monetary>,
+    books: HashMap<String, Book>,
+    pending: Vec<Pending>,
+    finished: VecDeque<(QueryOrder, Monetary)>,
+    next_id: OrderId,
+}
+
+/// Parameters of a `FillModel`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FillConfig {
+    /// Seconds until an order reaches the exchange.
+    pub latency: i64,
+    pub queue: Queue,
+    /// Fraction of the traded volume an order can fill.
+    pub participation: Monetary,
+    /// Seconds after which open orders expire.
+    pub expiry: Option<i64>,
+    pub maker_fee: Monetary,
+    pub taker_fee: Monetary,
+    pub slippage: Slippage,
+}
+
+impl Default for FillConfig {
+    fn default() -> FillConfig {
+        FillConfig {
+            latency: 0,
+            queue: Queue::Back,
+            participation: 1.0,
+            expiry: None,
+            maker_fee: 0.001,
+            taker_fee: 0.001,
+            slippage: Slippage {
+                fixed: 0.0005,
+                impact: 0.0,
+            },
+        }
+    }
+}
+
+impl Default for FillModel {
+    fn default() -> FillModel {
+        FillModel::with_config(FillConfig::default())
+    }
+}
+
+impl FillModel {
+    pub fn new() -> FillModel {
+        FillModel::default()
+    }
+
+    pub fn with_config(config: FillConfig) -> FillModel {
+        FillModel {
+            config,
+            prices: HashMap::new(),
+            books: HashMap::new(),
+            pending: Vec::new(),
+            finished: VecDeque::new(),
+            next_id: 1,
+        }
+    }
+
+    pub fn set_latency(&mut self, latency: i64) {
+        self.config.latency = latency;
+    }
+
+    pub fn set_queue(&mut self, queue: Queue) {
+        self.config.queue = queue;
+    }
+
+    pub fn set_participation(&mut self, participation: Monetary) {
+        self.config.participation = participation;
+    }
+
+    pub fn set_expiry(&mut self, expiry: Option<i64>) {
+        self.config.expiry = expiry;
+    }
+
+    pub fn set_fees(&mut self, maker_fee: Monetary, taker_fee: Monetary) {
+        self.config.maker_fee = maker_fee;
+        self.config.taker_fee = taker_fee;
+    }
+
+    pub fn set_slippage(&mut self, slippage: Slippage) {
+        self.config.slippage = slippage;
+    }
+
+    /// Replaces the book of a market, e.g. with a newer snapshot.
+    pub fn set_book(&mut self, book: Book) {
+        self.books.insert(book.symbol.clone(), book);
+    }
+
+    pub fn place(&mut self, symbol: &str, order: Order, timestamp: i64) -> OrderId {
+        let (side, quantity, limit) = match order {
+            Order::Limit(Action::Buy, quantity, price) => (Side::Buy, quantity, Some(price)),
+            Order::Limit(Action::Sell, quantity, price) => (Side::Sell, quantity, Some(price)),
+            Order::Market(Action::Buy, quantity) => (Side::Buy, quantity, None),
+            Order::Market(Action::Sell, quantity) => (Side::Sell, quantity, None),
+        };
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.pending.push(Pending {
+            order: QueryOrder {
+                symbol: String::from(symbol),
+                order_id: id,
+                client_order_id: String::new(),
+                price: limit.unwrap_or(0.0),
+                orig_qty: quantity,
+                executed_qty: 0.0,
+                status: String::from("NEW"),
+                time_in_force: String::from("GTC"),
+                type_name: String::from(if limit.is_some() { "LIMIT" } else { "MARKET" }),
+                side,
+                stop_price: 0.0,
+                iceberg_qty: String::new(),
+                time: timestamp as u64 * 1000,
+            },
+            limit,
+            arrival: timestamp + self.config.latency,
+            expiry: self.config.expiry.map(|expiry| timestamp + expiry),
+            resting: false,
+            ahead: match self.config.queue {
+                Queue::Behind(volume) => volume,
+                _ => 0.0,
+            },
+            quote: 0.0,
+            fees: 0.0,
+        });
+        id
+    }
+
+    /// Returns whether the order was still open.
+    pub fn cancel(&mut self, id: OrderId) -> bool {
+        let open = self.pending.iter().any(|pending| pending.order.order_id == id);
+        self.finish(|pending| pending.order.order_id == id, "CANCELED");
+        open
+    }
+
+    pub fn cancel_all(&mut self, symbol: &str) {
+        self.finish(|pending| pending.order.symbol == symbol, "CANCELED");
+    }
+
+    pub fn open_orders(&self, symbol: &str) -> Vec<QueryOrder> {
+        self.pending
+            .iter()
+            .filter(|pending| pending.order.symbol == symbol)
+            .map(|pending| pending.order.clone())
+            .collect()
+    }
+
+    /// Returns the next order that was filled, canceled or expired, with the average fee rate of its fills.
+    pub fn next(&mut self) -> Option<(QueryOrder, Monetary)> {
+        self.finished.pop_front()
+    }
+
+    /// Lets orders arrive at the exchange and expires old ones, called every second.
+    pub fn advance(&mut self, timestamp: i64) {
+        self.arrive(timestamp);
+        self.finish(|pending| pending.expiry.map_or(false, |expiry| expiry <= timestamp), "EXPIRED");
+    }
+
+    /// A trade at `price` on the market. `volume` is the traded quantity if it is known, otherwise it is unlimited.
+    pub fn trade(&mut self, symbol: &str, price: Monetary, volume: Option<Monetary>, timestamp: i64) {
+        // Orders arriving now rest on the book before this trade.
+        self.arrive(timestamp);
+        self.prices.insert(String::from(symbol), price);
+
+        let participation = self.config.participation;
+        let mut available = volume.map(|volume| volume * participation);
+        let queue = self.config.queue;
+        let fee = self.config.maker_fee;
+        for pending in self.pending.iter_mut().filter(|pending| pending.resting && pending.order.symbol == symbol) {
+            let limit = pending.limit.unwrap();
+            let through = match pending.order.side {
+                Side::Buy => price < limit,
+                Side::Sell => price > limit,
+            };
+            // Volume that fills the queue ahead of the order doesn't fill the order.
+            let mut queued = 0.0;
+            let fillable = through || (price == limit && match queue {
+                Queue::Front => true,
+                Queue::Behind(_) => {
+                    if let Some(volume) = volume {
+                        queued = pending.ahead.max(0.0).min(volume);
+                        pending.ahead -= queued;
+                    }
+                    pending.ahead <= 0.0
+                },
+                Queue::Back => false,
+            });
+            if fillable {
+                let mut quantity = pending.order.orig_qty - pending.order.executed_qty;
+                if let (Some(available), Some(volume)) = (&mut available, volume) {
+                    quantity = quantity.min(available.min((volume - queued) * participation));
+                    *available -= quantity;
+                }
+                fill(pending, quantity, limit, fee);
+            }
+        }
+
+        self.finish(|pending| pending.order.executed_qty >= pending.order.orig_qty, "FILLED");
+    }
+
+    fn arrive(&mut self, timestamp: i64) {
+        let (slippage, fee) = (self.config.slippage, self.config.taker_fee);
+        for pending in self.pending.iter_mut().filter(|pending| !pending.resting && pending.arrival <= timestamp) {
+            if let Some(book) = self.books.get_mut(&pending.order.symbol) {
+                pending.resting = true;
+                let execution = book.take(&pending.order.side, pending.order.orig_qty, pending.limit);
+                tracing::debug!(
+                    symbol = %pending.order.symbol,
+                    quantity = execution.quantity,
+                    price = execution.price,
+                    remaining = execution.remaining,
+                    "taken from book"
+                );
+                if execution.quantity > 0.0 {
+                    fill(pending, execution.quantity, execution.price, fee);
+                }
+                continue;
+            }
+
+            let price = match self.prices.get(&pending.order.symbol) {
+                Some(price) => *price,
+                None => continue,
+            };
+            pending.resting = true;
+
+            let quantity = pending.order.orig_qty;
+            let slipped = slippage.fixed + slippage.impact * quantity * price;
+            let (crossing, taken) = match pending.order.side {
+                Side::Buy => (pending.limit.map_or(true, |limit| limit >= price), price * (1.0 + slipped)),
+                Side::Sell => (pending.limit.map_or(true, |limit| limit <= price), price * (1.0 - slipped)),
+            };
+            if crossing {
+                // Slippage never makes a limit order pay more than its limit.
+                let taken = match (pending.limit, &pending.order.side) {
+                    (Some(limit), Side::Buy) => taken.min(limit),
+                    (Some(limit), Side::Sell) => taken.max(limit),
+                    (None, _) => taken,
+                };
+                fill(pending, quantity, taken, fee);
+            }
+        }
+
+        self.finish(|pending| pending.order.executed_qty >= pending.order.orig_qty, "FILLED");
+        // Market orders that arrived and aren't filled ran out of liquidity.
+        self.finish(|pending| pending.resting && pending.limit.is_none(), "EXPIRED");
+    }
+
+    fn finish<F>(&mut self, condition: F, status: &str)
+    where
+        F: Fn(&Pending) -> bool,
+    {
+        let mut index = 0;
+        while index < self.pending.len() {
+            if condition(&self.pending[index]) {
+                let mut pending = self.pending.remove(index);
+                pending.order.status = String::from(status);
+                let fee = if pending.order.executed_qty > 0.0 {
+                    pending.order.price = pending.quote / pending.order.executed_qty;
+                    pending.fees / pending.order.executed_qty
+                } else {
+                    0.0
+                };
+                self.finished.push_back((pending.order, fee));
+            } else {
+                index += 1;
+            }
+        }
+    }
+}
+
+fn fill(pending: &mut Pending, quantity: Monetary, price: Monetary, fee: Monetary) {
+    pending.order.executed_qty += quantity;
+    pending.quote += quantity * price;
+    pending.fees += quantity * fee;
+}
+
+#[tokio::test]
+async fn test_fills() {
+    let mut model = FillModel::new();
+    model.set_queue(Queue::Behind(1.0));
+    model.set_latency(1);
+    model.set_participation(0.5);
+    model.set_fees(0.0, 0.001);
+    model.set_slippage(Slippage { fixed: 0.01, impact: 0.0 });
+    model.trade("BTCUSDT", 100.0, None, 0);
+
+    // Rests as maker. The first volume of 1 traded at the limit fills the queue ahead of it,
+    // then it fills against half of the volume after that.
+    model.place("BTCUSDT", Order::Limit(Action::Buy, 2.0, 99.0), 0);
+    model.trade("BTCUSDT", 99.0, Some(1.5), 1);
+    assert_eq!(model.open_orders("BTCUSDT")[0].executed_qty, 0.25);
+    model.trade("BTCUSDT", 99.0, Some(2.0), 2);
+    assert_eq!(model.open_orders("BTCUSDT")[0].executed_qty, 1.25);
+    assert!(model.next().is_none());
+    model.trade("BTCUSDT", 98.0, None, 3);
+    let (order, fee) = model.next().unwrap();
+    assert_eq!((order.status.as_str(), order.executed_qty, order.price, fee), ("FILLED", 2.0, 99.0, 0.0));
+
+    // Crosses the last price on arrival and takes liquidity, slippage is capped by the limit.
+    model.place("BTCUSDT", Order::Limit(Action::Sell, 1.0, 97.5), 3);
+    model.advance(3);
+    assert!(model.next().is_none());
+    model.advance(4);
+    let (order, fee) = model.next().unwrap();
+    assert_eq!((order.status.as_str(), order.price, fee), ("FILLED", 97.5, 0.001));
+
+    let id = model.place("BTCUSDT", Order::Limit(Action::Buy, 1.0, 80.0), 4);
+    assert_eq!(model.open_orders("BTCUSDT").len(), 1);
+    assert!(model.cancel(id));
+    assert!(!model.cancel(id));
+    let (order, _) = model.next().unwrap();
+    assert_eq!((order.order_id, order.status.as_str()), (id, "CANCELED"));
+
+    model.set_expiry(Some(10));
+    model.place("BTCUSDT", Order::Limit(Action::Buy, 1.0, 90.0), 4);
+    model.advance(13);
+    assert!(model.next().is_none());
+    model.advance(14);
+    let (order, _) = model.next().unwrap();
+    assert_eq!((order.status.as_str(), order.executed_qty), ("EXPIRED", 0.0));
+
+    // Walks the book, and expires with the part that found no liquidity.
+    model.set_expiry(None);
+    model.set_book(Book {
+        symbol: String::from("BTCUSDT"),
+        timestamp: 14,
+        bids: vec![(97.0, 1.0)],
+        asks: vec![(99.0, 1.0), (100.0, 1.0)],
+    });
+    model.place("BTCUSDT", Order::Market(Action::Buy, 3.0), 14);
+    model.advance(15);
+    let (order, _) = model.next().unwrap();
+    assert_eq!((order.status.as_str(), order.executed_qty, order.price), ("EXPIRED", 2.0, 99.5));
+}
@@ -0,0 +1,530 @@
+use super::{fill_seconds, klines, store_alert, store_balances, Bars, Clock, Environment, Event, Listings, MarketData, OrderError, OrderId, SystemClock, Tickers};
+use crate::economy::{Alert, AssetSymbol, Monetary};
+use crate::metrics::Metrics;
+use crate::traders::{Action, Order};
+use crate::universe::UniverseSelector;
+use async_trait::async_trait;
+use binance_async::{
+    model::{
+        Side,
+        Order as QueryOrder,
+        OrderRequest,
+        OrderStatus,
+        websocket::{Subscription, BinanceWebsocketMessage, UserOrderUpdate}
+    },
+    error::Error as BinanceError,
+    Binance,
+    BinanceWebsocket
+};
+use sqlx::PgPool;
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+use tokio::stream::StreamExt;
+
+/// Binance's code for cancels of orders it doesn't know, e.g. because they were already filled.
+const UNKNOWN_ORDER: i64 = -2011;
+
+/// Tells orders the exchange refused apart from requests that didn't reach it.
+fn order_error(error: failure::Error) -> OrderError {
+    match error.downcast_ref::<BinanceError>() {
+        Some(BinanceError::BinanceError { msg, .. }) => OrderError::Rejected(msg.clone()),
+        _ => OrderError::Connection(error.to_string()),
+    }
+}
+
+/// Listen keys expire after 60 minutes without a keep-alive.
+const KEEP_ALIVE: i64 = 30 * 60;
+
+/// Seconds without messages after which the streams are considered lost, tickers arrive every second.
+const STALE: i64 = 60;
+
+/// Longest wait in seconds between attempts to connect the streams again.
+const MAX_BACKOFF: u64 = 60;
+
+/// Trades on Binance.
+///
+/// Orders are placed through the REST API. Fills, cancels and balance changes are received from the user data stream,
+/// and reconciled against the REST API every `reconcile_interval` seconds, in case stream messages were lost.
+/// Orders are reported as executed once they are filled, canceled, rejected or expired.
+/// The balances are reported after every fill, so the economy doesn't estimate them.
+/// If the streams end or go quiet, they are subscribed again with a new listen key.
+pub struct Live {
+    binance: Binance,
+    socket: BinanceWebsocket,
+    listings: Listings,
+    listen_key: String,
+    events: VecDeque<Event>,
+    orders: HashMap<u64, QueryOrder>,
+    balances: HashMap<String, Monetary>,
+    timestamp: i64,
+    checked: i64,
+    kept_alive: i64,
+    reconciled: i64,
+    reconcile_interval: i64,
+    /// When the last message was received.
+    received: i64,
+    /// Seconds to wait before connecting the streams again.
+    backoff: u64,
+    lag: Option<f64>,
+    clock: Box<dyn Clock>,
+    bars: Bars,
+    tickers: Tickers,
+    pool: Option<PgPool>,
+}
+
+impl Live {
+    /// The websocket is only connected during initialization, so it may be connected to a different server than the client.
+    pub fn new<U>(selector: U, binance: Binance, socket: BinanceWebsocket) -> Live
+    where
+        U: UniverseSelector + Send + Sync + 'static,
+    {
+        Live {
+            binance,
+            socket,
+            listings: Listings::new(selector),
+            listen_key: String::new(),
+            events: VecDeque::new(),
+            orders: HashMap::new(),
+            balances: HashMap::new(),
+            timestamp: 0,
+            checked: SystemClock.now(),
+            kept_alive: SystemClock.now(),
+            reconciled: SystemClock.now(),
+            reconcile_interval: 60,
+            received: SystemClock.now(),
+            backoff: 0,
+            lag: None,
+            clock: Box::new(SystemClock),
+            bars: Bars::new(),
+            tickers: Tickers::new(),
+            pool: None,
+        }
+    }
+
+    /// Replaces the system clock, e.g. to speed up the intervals in tests.
+    pub fn set_clock(&mut self, clock: Box<dyn Clock>) {
+        let now = clock.now();
+        self.checked = now;
+        self.kept_alive = now;
+        self.reconciled = now;
+        self.received = now;
+        self.clock = clock;
+    }
+
+    /// Only evaluates once a multiple of `interval` seconds passed, with the bars of the tickers since the previous evaluation.
+    pub fn set_evaluation_interval(&mut self, interval: i64) {
+        self.bars.set_interval(interval);
+    }
+
+    pub fn set_reconcile_interval(&mut self, reconcile_interval: i64) {
+        self.reconcile_interval = reconcile_interval;
+    }
+
+    /// Stores alerts and balances in the `messages` and `balances` tables.
+    pub fn set_pool(&mut self, pool: PgPool) {
+        self.pool = Some(pool);
+    }
+
+    /// Subscribes to the tickers and the user data stream with a new listen key, replacing earlier subscriptions.
+    async fn subscribe(&mut self) -> Result<(), failure::Error> {
+        self.socket.unsubscribe(&Subscription::UserData(self.listen_key.clone()));
+        self.socket.unsubscribe(&Subscription::TickerAll);
+        self.listen_key = self.binance.user_stream_start()?.await?.listen_key;
+        self.kept_alive = self.clock.now();
+        self.socket.subscribe(Subscription::UserData(self.listen_key.clone())).await?;
+        self.socket.subscribe(Subscription::TickerAll).await?;
+        self.received = self.clock.now();
+        Ok(())
+    }
+
+    /// Connects the streams again after they ended, waiting longer after each attempt that didn't bring messages.
+    async fn reconnect(&mut self) {
+        tokio::time::delay_for(Duration::from_secs(self.backoff)).await;
+        self.backoff = (self.backoff * 2).max(1).min(MAX_BACKOFF);
+        match self.subscribe().await {
+            // Updates may have been missed in the meantime.
+            Ok(()) => self.reconcile().await,
+            Err(error) => tracing::warn!(%error, backoff = self.backoff, "connecting the streams failed"),
+        }
+    }
+
+    /// Queues balance changes of the account. If `all` is set, all balances are queued,
+    /// e.g. to replace the balances of the economy with the actual ones.
+    async fn fetch_balances(&mut self, all: bool) -> Result<(), ()> {
+        let account = self.binance.get_account().map_err(|_| ())?.await.map_err(|error| {
+            tracing::warn!(%error, "fetching the account failed");
+        })?;
+        for balance in account.balances {
+            let total = balance.free.parse::<Monetary>().unwrap_or(0.0) + balance.locked.parse::<Monetary>().unwrap_or(0.0);
+            let known = self.balances.insert(balance.asset.clone(), total);
+            if all || known != Some(total) {
+                self.events.push_back(Event::SetAssetBalance(balance.asset, total));
+            }
+        }
+        Ok(())
+    }
+
+    /// Queries the status of all open orders, in case their updates were missed on the user data stream.
+    async fn reconcile(&mut self) {
+        let mut finished = false;
+        let orders = self.orders
+            .values()
+            .map(|order| (order.symbol.clone(), order.order_id))
+            .collect::<Vec<(String, u64)>>();
+        for (symbol, order_id) in orders {
+            let status = match self.binance.order_status(&symbol, order_id) {
+                Ok(request) => request.await,
+                Err(error) => Err(error),
+            };
+            let status = match status {
+                Ok(status) => status,
+                Err(error) => {
+                    tracing::warn!(%error, %symbol, order_id, "querying order failed");
+                    continue;
+                },
+            };
+            let open = match status.status.as_str() {
+                "FILLED" | "CANCELED" | "REJECTED" | "EXPIRED" => false,
+                _ => true,
+            };
+            if !open {
+                tracing::warn!(%symbol, order_id, status = %status.status, "missed order update");
+                let order = self.orders.get_mut(&order_id).unwrap();
+                order.executed_qty = status.executed_qty;
+                order.status = status.status;
+                self.finish(order_id);
+                finished = true;
+            }
+        }
+        self.fetch_balances(finished).await.ok();
+    }
+
+    fn update_order(&mut self, update: UserOrderUpdate) {
+        if let Some(order) = self.orders.get_mut(&update.order_id) {
+            order.executed_qty = update.accumulated_qty_filled_trades;
+            if update.accumulated_qty_filled_trades > 0.0 {
+                order.price = update.cumulative_quote_asset_transacted_qty / update.accumulated_qty_filled_trades;
+            }
+            order.status = String::from(match update.order_status {
+                OrderStatus::New => "NEW",
+                OrderStatus::PartiallyFilled => "PARTIALLY_FILLED",
+                OrderStatus::Filled => "FILLED",
+                OrderStatus::Canceled => "CANCELED",
+                OrderStatus::PendingCancel => "PENDING_CANCEL",
+                OrderStatus::Rejected => "REJECTED",
+                OrderStatus::Expired => "EXPIRED",
+            });
+            match update.order_status {
+                OrderStatus::Filled | OrderStatus::Canceled | OrderStatus::Rejected | OrderStatus::Expired => {
+                    self.finish(update.order_id);
+                },
+                _ => {},
+            }
+        }
+    }
+
+    fn finish(&mut self, order_id: u64) {
+        if let Some(order) = self.orders.remove(&order_id) {
+            let executed = order.executed_qty;
+            self.events.push_back(Event::ExecutedOrder(order, executed, None));
+        }
+    }
+}
+
+#[async_trait]
+impl Environment for Live {
+    async fn initialize(&mut self) -> Result<Vec<MarketData>, ()> {
+        let markets = self.universe().await?;
+        self.fetch_balances(true).await?;
+
+        self.subscribe().await.map_err(|error| {
+            tracing::warn!(%error, "connecting the streams failed");
+        })?;
+
+        Ok(markets)
+    }
+
+    async fn universe(&mut self) -> Result<Vec<MarketData>, ()> {
+        self.listings.select(&self.binance).await
+    }
+
+    async fn poll(&mut self) -> Event {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return event;
+            }
+
+            let now = self.clock.now();
+            if now - self.kept_alive >= KEEP_ALIVE {
+                self.kept_alive = now;
+                let kept_alive = match self.binance.user_stream_keep_alive(&self.listen_key) {
+                    Ok(request) => request.await.map(|_| ()),
+                    Err(error) => Err(error),
+                };
+                // Retried with the next interval, the listen key only expires after two.
+                if let Err(error) = kept_alive {
+                    tracing::warn!(%error, "keeping the user data stream alive failed");
+                }
+                continue;
+            }
+            if now - self.checked >= 60 {
+                self.checked = now;
+                let events = self.listings.check(&self.binance).await;
+                self.events.extend(events);
+                continue;
+            }
+            if now - self.reconciled >= self.reconcile_interval {
+                self.reconciled = now;
+                self.reconcile().await;
+                continue;
+            }
+            if now - self.received >= STALE {
+                tracing::warn!(seconds = now - self.received, "no messages on the streams");
+                self.reconnect().await;
+                continue;
+            }
+
+            // Tickers arrive every second, the timeout only matters if the connection stalls.
+            let message = match tokio::time::timeout(Duration::from_secs(1), self.socket.try_next()).await {
+                Ok(Ok(Some(message))) => message,
+                Err(_) => continue,
+                Ok(Ok(None)) => {
+                    tracing::warn!("the streams ended");
+                    self.reconnect().await;
+                    continue;
+                },
+                Ok(Err(error)) => {
+                    if let Some(BinanceError::NoStreamSubscribed) = error.downcast_ref::<BinanceError>() {
+                        tracing::warn!("the streams ended");
+                        self.reconnect().await;
+                    } else {
+                        tracing::warn!(%error, "unexpected websocket message");
+                    }
+                    continue;
+                },
+            };
+            self.received = self.clock.now();
+            self.backoff = 0;
+            match message {
+                BinanceWebsocketMessage::TickerAll(tickers) => {
+                    let received = self.clock.now_millis();
+                    if let Some(sent) = tickers.iter().map(|ticker| ticker.event_time).max() {
+                        self.lag = Some((received - sent as i64) as f64 / 1000.0);
+                    }
+                    for ticker in tickers {
+                        let (events, traded) = self.tickers.update(&ticker);
+                        self.bars.update(&ticker.symbol, ticker.current_close, traded);
+                        self.events.push_back(Event::SetMarketValue(ticker.symbol, ticker.current_close));
+                        self.events.extend(events);
+                    }
+                    let timestamp = received.div_euclid(1000);
+                    if timestamp > self.timestamp {
+                        if self.bars.closes_since(self.timestamp, timestamp) {
+                            let bars = self.bars.close();
+                            self.events.extend(bars);
+                            self.events.push_back(Event::Evaluate(timestamp));
+                        }
+                        self.timestamp = timestamp;
+                    }
+                },
+                BinanceWebsocketMessage::UserOrderUpdate(update) => {
+                    self.update_order(update);
+                },
+                BinanceWebsocketMessage::UserAccountUpdate(update) => {
+                    for balance in update.balance {
+                        let total = balance.free + balance.locked;
+                        if self.balances.insert(balance.asset.clone(), total) != Some(total) {
+                            self.events.push_back(Event::SetAssetBalance(balance.asset, total));
+                        }
+                    }
+                },
+                _ => {},
+            }
+        }
+    }
+
+    async fn order(&mut self, symbol: &str, order: Order) -> Result<OrderId, OrderError> {
+        let (side, quantity, price) = match order {
+            Order::Limit(Action::Buy, quantity, price) => (Side::Buy, quantity, price),
+            Order::Limit(Action::Sell, quantity, price) => (Side::Sell, quantity, price),
+            Order::Market(_, _) => return Err(OrderError::Unsupported),
+        };
+
+        let request = OrderRequest {
+            symbol: String::from(symbol),
+            qty: quantity,
+            price,
+            order_side: side.clone(),
+            order_type: String::from("LIMIT"),
+            time_in_force: String::from("GTC"),
+        };
+        let transaction = self.binance.place_order(request).map_err(order_error)?.await.map_err(|error| {
+            let error = order_error(error);
+            tracing::warn!(%error, %symbol, "placing order failed");
+            error
+        })?;
+        let order_id = transaction.order_id;
+
+        self.orders.insert(transaction.order_id, QueryOrder {
+            symbol: transaction.symbol,
+            order_id: transaction.order_id,
+            client_order_id: transaction.client_order_id,
+            price,
+            orig_qty: quantity,
+            executed_qty: 0.0,
+            status: String::from("NEW"),
+            time_in_force: String::from("GTC"),
+            type_name: String::from("LIMIT"),
+            side,
+            stop_price: 0.0,
+            iceberg_qty: String::new(),
+            time: transaction.transact_time,
+        });
+        Ok(order_id)
+    }
+
+    async fn cancel(&mut self, symbol: &str, order_id: OrderId) -> Result<(), OrderError> {
+        if !self.orders.get(&order_id).map_or(false, |order| order.symbol == symbol) {
+            return Err(OrderError::Unknown(order_id));
+        }
+        // The update of the cancel, or of a fill that came first, is still received.
+        self.binance.cancel_order(symbol, order_id).map_err(order_error)?.await.map_err(|error| {
+            match error.downcast_ref::<BinanceError>() {
+                Some(BinanceError::BinanceError { code: UNKNOWN_ORDER, .. }) => OrderError::Unknown(order_id),
+                _ => order_error(error),
+            }
+        })?;
+        Ok(())
+    }
+
+    async fn cancel_all(&mut self, symbol: &str) -> Result<(), OrderError> {
+        let orders = self.orders
+            .values()
+            .filter(|order| order.symbol == symbol)
+            .map(|order| order.order_id)
+            .collect::<Vec<OrderId>>();
+        for order_id in orders {
+            // Fails if the order was filled in the meantime, its update is still received.
+            if let Err(error) = self.cancel(symbol, order_id).await {
+                tracing::warn!(%error, %symbol, order_id, "canceling order failed");
+            }
+        }
+        Ok(())
+    }
+
+    async fn open_orders(&mut self, symbol: &str) -> Result<Vec<QueryOrder>, OrderError> {
+        Ok(self.orders.values().filter(|order| order.symbol == symbol).cloned().collect())
+    }
+
+    async fn update_balances(&self, balances: Vec<(&AssetSymbol, Monetary)>) {
+        if let Some(pool) = &self.pool {
+            if let Err(error) = store_balances(pool, &balances).await {
+                tracing::warn!(%error, "storing balances failed");
+            }
+        }
+    }
+
+    async fn alert(&self, alert: &Alert) {
+        if let Some(pool) = &self.pool {
+            if let Err(error) = store_alert(pool, alert).await {
+                tracing::warn!(%error, "storing alert failed");
+            }
+        }
+    }
+
+    fn reports_balances(&self) -> bool {
+        true
+    }
+
+    async fn history(&mut self, symbol: &str, duration: i64) -> Vec<(i64, Monetary)> {
+        let to = self.clock.now();
+        let from = to - duration;
+        fill_seconds(&klines(&self.binance, symbol, from, to).await, from, to)
+    }
+
+    fn report(&self, metrics: &Metrics) {
+        metrics.set_queue_depth(self.events.len());
+        metrics.set_api_weight(self.binance.transport.used_weight());
+        if let Some(lag) = self.lag {
+            metrics.set_websocket_lag(lag);
+        }
+    }
+
+    fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
+    }
+}
+
+#[tokio::test]
+async fn test_live() {
+    use super::mock::MockExchange;
+    use crate::universe::Trading;
+
+    let market = MarketData {
+        symbol: String::from("BTCUSDT"),
+        status: String::from("TRADING"),
+        base_asset: String::from("BTC"),
+        base_asset_precision: 8,
+        quote_asset: String::from("USDT"),
+        quote_precision: 8,
+        order_types: vec![String::from("LIMIT")],
+        iceberg_allowed: false,
+        filters: Vec::new(),
+    };
+    let exchange = MockExchange::start(vec![market], vec![("USDT", 1000.0)]);
+    let mut live = Live::new(Trading, exchange.binance(), exchange.websocket());
+    assert_eq!(live.initialize().await.unwrap().len(), 1);
+
+    match live.poll().await {
+        Event::SetAssetBalance(asset, balance) => assert_eq!((asset.as_str(), balance), ("USDT", 1000.0)),
+        event => panic!("unexpected event {:?}", event),
+    }
+
+    exchange.tick("BTCUSDT", 100.0);
+    match live.poll().await {
+        Event::SetMarketValue(symbol, value) => assert_eq!((symbol.as_str(), value), ("BTCUSDT", 100.0)),
+        event => panic!("unexpected event {:?}", event),
+    }
+    match live.poll().await {
+        Event::SetMarketBookTicker(_, book_ticker) => assert_eq!((book_ticker.bid, book_ticker.ask), (100.0, 100.0)),
+        event => panic!("unexpected event {:?}", event),
+    }
+    assert!(matches!(live.poll().await, Event::MarketTrade(_, _)));
+    assert!(matches!(live.poll().await, Event::SetMarketStats(_, _)));
+    assert!(matches!(live.poll().await, Event::Evaluate(_)));
+
+    live.order("BTCUSDT", Order::Limit(Action::Buy, 2.0, 100.0)).await.unwrap();
+    exchange.fill_all();
+    match live.poll().await {
+        Event::ExecutedOrder(order, executed, _) => {
+            assert_eq!((order.status.as_str(), executed, order.price), ("FILLED", 2.0, 100.0));
+        },
+        event => panic!("unexpected event {:?}", event),
+    }
+    let mut balances = HashMap::new();
+    for _ in 0..2 {
+        match live.poll().await {
+            Event::SetAssetBalance(asset, balance) => balances.insert(asset, balance),
+            event => panic!("unexpected event {:?}", event),
+        };
+    }
+    assert_eq!(balances["BTC"], 2.0);
+    assert_eq!(balances["USDT"], 800.0);
+
+    let order_id = live.order("BTCUSDT", Order::Limit(Action::Sell, 1.0, 110.0)).await.unwrap();
+    assert_eq!(live.open_orders("BTCUSDT").await.unwrap().len(), 1);
+    assert!(matches!(live.cancel("ETHUSDT", order_id).await, Err(OrderError::Unknown(_))));
+    live.cancel_all("BTCUSDT").await.unwrap();
+    match live.poll().await {
+        Event::ExecutedOrder(order, executed, _) => assert_eq!((order.status.as_str(), executed), ("CANCELED", 0.0)),
+        event => panic!("unexpected event {:?}", event),
+    }
+
+    // Streams that end are subscribed again, and receive what was published in the meantime.
+    exchange.disconnect();
+    exchange.tick("BTCUSDT", 110.0);
+    match live.poll().await {
+        Event::SetMarketValue(symbol, value) => assert_eq!((symbol.as_str(), value), ("BTCUSDT", 110.0)),
+        event => panic!("unexpected event {:?}", event),
+    }
+}
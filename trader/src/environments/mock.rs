@@ -0,0 +1,270 @@
+use super::MarketData;
+use binance_async::{Binance, BinanceWebsocket};
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use warp::{ws::{Message, WebSocket, Ws}, Filter};
+
+const LISTEN_KEY: &str = "mock";
+
+#[derive(Default)]
+struct State {
+    markets: Vec<MarketData>,
+    balances: HashMap<String, f64>,
+    /// Orders in the format of `/api/v3/order`.
+    orders: Vec<Value>,
+    /// Messages that were published before anyone subscribed, and the subscribers of each stream.
+    streams: HashMap<String, (Vec<String>, Vec<mpsc::UnboundedSender<String>>)>,
+    /// ID of the last trade published on the ticker stream.
+    trades: u64,
+}
+
+impl State {
+    fn publish(&mut self, stream: &str, message: Value) {
+        let (backlog, subscribers) = self.streams.entry(String::from(stream)).or_default();
+        subscribers.retain(|subscriber| subscriber.send(message.to_string()).is_ok());
+        if subscribers.is_empty() {
+            backlog.push(message.to_string());
+        }
+    }
+
+    /// Ends an open order, reports it on the user data stream and updates the balances.
+    fn close(&mut self, index: usize, status: &str) {
+        let order = &mut self.orders[index];
+        let symbol = order["symbol"].as_str().unwrap().to_string();
+        let side = order["side"].as_str().unwrap().to_string();
+        let price = order["price"].as_str().unwrap().parse::<f64>().unwrap();
+        let quantity = order["origQty"].as_str().unwrap().parse::<f64>().unwrap();
+        let executed = if status == "FILLED" { quantity } else { 0.0 };
+        order["status"] = json!(status);
+        order["executedQty"] = json!(executed.to_string());
+        let order_id = order["orderId"].clone();
+
+        let market = self.markets.iter().find(|market| market.symbol == symbol).unwrap();
+        let (base, quote) = (market.base_asset.clone(), market.quote_asset.clone());
+        let sign = if side == "BUY" { 1.0 } else { -1.0 };
+        *self.balances.entry(base.clone()).or_default() += sign * executed;
+        *self.balances.entry(quote.clone()).or_default() -= sign * executed * price;
+
+        self.publish(LISTEN_KEY, json!({
+            "e": "executionReport", "E": 0, "s": symbol, "c": "", "S": side, "o": "LIMIT", "f": "GTC",
+            "q": quantity.to_string(), "p": price.to_string(), "P": "0", "F": "0", "g": -1, "C": "",
+            "x": if status == "FILLED" { "TRADE" } else { status }, "X": status, "r": "NONE", "i": order_id,
+            "l": executed.to_string(), "z": executed.to_string(), "L": price.to_string(), "n": "0", "N": null,
+            "T": 0, "t": -1, "I": 0, "w": false, "m": false, "M": false, "O": 0, "Z": (executed * price).to_string(),
+        }));
+        let balances = vec![base, quote]
+            .into_iter()
+            .map(|asset| json!({ "a": asset, "f": self.balances[&asset].to_string(), "l": "0" }))
+            .collect::<Vec<Value>>();
+        self.publish(LISTEN_KEY, json!({ "e": "outboundAccountPosition", "E": 0, "u": 0, "B": balances }));
+    }
+}
+
+/// A minimal Binance on localhost to test `Live` against.
+///
+/// Limit orders stay open until `fill_all` is called, fills are free of fees.
+pub struct MockExchange {
+    address: SocketAddr,
+    state: Arc<Mutex<State>>,
+}
+
+impl MockExchange {
+    pub fn start(markets: Vec<MarketData>, balances: Vec<(&str, f64)>) -> MockExchange {
+        let state = Arc::new(Mutex::new(State {
+            markets,
+            balances: balances.into_iter().map(|(asset, balance)| (String::from(asset), balance)).collect(),
+            ..State::default()
+        }));
+        let with_state = {
+            let state = state.clone();
+            warp::any().map(move || state.clone())
+        };
+
+        let exchange_info = warp::get()
+            .and(warp::path!("api" / "v3" / "exchangeInfo"))
+            .and(with_state.clone())
+            .map(|state: Arc<Mutex<State>>| {
+                let state = state.lock().unwrap();
+                warp::reply::json(&json!({
+                    "timezone": "UTC",
+                    "serverTime": 0,
+                    "rateLimits": [],
+                    "exchangeFilters": [],
+                    "symbols": state.markets,
+                }))
+            });
+        let stats = warp::get()
+            .and(warp::path!("api" / "v1" / "ticker" / "24hr"))
+            .map(|| warp::reply::json(&json!([])));
+        let account = warp::get()
+            .and(warp::path!("api" / "v3" / "account"))
+            .and(with_state.clone())
+            .map(|state: Arc<Mutex<State>>| {
+                let state = state.lock().unwrap();
+                let balances = state.balances
+                    .iter()
+                    .map(|(asset, balance)| json!({ "asset": asset, "free": balance.to_string(), "locked": "0" }))
+                    .collect::<Vec<Value>>();
+                warp::reply::json(&json!({
+                    "makerCommission": 0,
+                    "takerCommission": 0,
+                    "buyerCommission": 0,
+                    "sellerCommission": 0,
+                    "canTrade": true,
+                    "canWithdraw": true,
+                    "canDeposit": true,
+                    "balances": balances,
+                }))
+            });
+        let user_stream = warp::path!("api" / "v1" / "userDataStream")
+            .and(warp::post().or(warp::put()).unify())
+            .map(|| warp::reply::json(&json!({ "listenKey": LISTEN_KEY })));
+        let place = warp::post()
+            .and(warp::path!("api" / "v3" / "order"))
+            .and(warp::body::form())
+            .and(with_state.clone())
+            .map(|params: HashMap<String, String>, state: Arc<Mutex<State>>| {
+                let mut state = state.lock().unwrap();
+                let order_id = state.orders.len() as u64 + 1;
+                state.orders.push(json!({
+                    "symbol": params["symbol"],
+                    "orderId": order_id,
+                    "clientOrderId": "",
+                    "price": params["price"],
+                    "origQty": params["quantity"],
+                    "executedQty": "0",
+                    "status": "NEW",
+                    "timeInForce": params["timeInForce"],
+                    "type": params["type"],
+                    "side": params["side"],
+                    "stopPrice": "0",
+                    "icebergQty": "0",
+                    "time": 0,
+                }));
+                warp::reply::json(&json!({
+                    "symbol": params["symbol"],
+                    "orderId": order_id,
+                    "clientOrderId": "",
+                    "transactTime": 0,
+                }))
+            });
+        let query = warp::get()
+            .and(warp::path!("api" / "v3" / "order"))
+            .and(warp::query())
+            .and(with_state.clone())
+            .map(|params: HashMap<String, String>, state: Arc<Mutex<State>>| {
+                let state = state.lock().unwrap();
+                let index = params["orderId"].parse::<usize>().unwrap() - 1;
+                warp::reply::json(&state.orders[index])
+            });
+        let cancel = warp::delete()
+            .and(warp::path!("api" / "v3" / "order"))
+            .and(warp::query())
+            .and(with_state.clone())
+            .map(|params: HashMap<String, String>, state: Arc<Mutex<State>>| {
+                let mut state = state.lock().unwrap();
+                let index = params["orderId"].parse::<usize>().unwrap() - 1;
+                if state.orders[index]["status"] == "NEW" {
+                    state.close(index, "CANCELED");
+                    warp::reply::json(&json!({
+                        "symbol": params["symbol"],
+                        "origClientOrderId": "",
+                        "orderId": index + 1,
+                        "clientOrderId": "",
+                    }))
+                } else {
+                    warp::reply::json(&json!({ "code": -2011, "msg": "Unknown order sent." }))
+                }
+            });
+        let streams = warp::path!("ws" / String)
+            .and(warp::ws())
+            .and(with_state)
+            .map(|stream: String, ws: Ws, state: Arc<Mutex<State>>| {
+                ws.on_upgrade(move |socket| subscribe(state, stream, socket))
+            });
+
+        let routes = exchange_info
+            .or(stats)
+            .or(account)
+            .or(user_stream)
+            .or(place)
+            .or(query)
+            .or(cancel)
+            .or(streams);
+        let (address, server) = warp::serve(routes).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        MockExchange {
+            address,
+            state,
+        }
+    }
+
+    pub fn binance(&self) -> Binance {
+        let mut binance = Binance::with_credential("key", "secret");
+        binance.transport.set_base(&format!("http://{}", self.address));
+        binance
+    }
+
+    pub fn websocket(&self) -> BinanceWebsocket {
+        BinanceWebsocket::with_base(&format!("ws://{}/ws", self.address))
+    }
+
+    /// Publishes the value of a market on the ticker stream, with the best bid and ask at the value.
+    pub fn tick(&self, symbol: &str, value: f64) {
+        let mut state = self.state.lock().unwrap();
+        state.trades += 1;
+        let trades = state.trades;
+        state.publish("!ticker@arr", json!([{
+            "e": "24hrTicker", "E": 0, "s": symbol, "c": value.to_string(), "Q": "1",
+            "p": "0", "P": "0", "w": "0", "x": "0",
+            "b": value.to_string(), "B": "1", "a": value.to_string(), "A": "1",
+            "o": "0", "l": "0", "h": "0", "v": "0", "q": "0",
+            "O": 0, "C": 0, "F": 0, "L": trades, "n": trades,
+        }]));
+    }
+
+    /// Replaces the markets the exchange lists, e.g. to list, halt or delist one.
+    pub fn set_markets(&self, markets: Vec<MarketData>) {
+        self.state.lock().unwrap().markets = markets;
+    }
+
+    /// Closes the connections of all streams, messages are kept until they are subscribed again.
+    pub fn disconnect(&self) {
+        for (_, subscribers) in self.state.lock().unwrap().streams.values_mut() {
+            subscribers.clear();
+        }
+    }
+
+    pub fn fill_all(&self) {
+        let mut state = self.state.lock().unwrap();
+        for index in 0..state.orders.len() {
+            if state.orders[index]["status"] == "NEW" {
+                state.close(index, "FILLED");
+            }
+        }
+    }
+}
+
+async fn subscribe(state: Arc<Mutex<State>>, stream: String, socket: WebSocket) {
+    let (mut sink, _) = socket.split();
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    {
+        let mut state = state.lock().unwrap();
+        let (backlog, subscribers) = state.streams.entry(stream).or_default();
+        for message in backlog.drain(..) {
+            sender.send(message).ok();
+        }
+        subscribers.push(sender);
+    }
+    while let Some(message) = receiver.recv().await {
+        if sink.send(Message::text(message)).await.is_err() {
+            return;
+        }
+    }
+    sink.close().await.ok();
+}
@@ -1,22 +1,94 @@
-//mod historical;
+mod backtest;
+mod bars;
+mod book;
+mod clock;
+mod config;
+mod data;
+mod exchange;
+mod fills;
+mod live;
+#[cfg(test)]
+mod mock;
+mod rolling;
 mod simulated;
+mod store;
+mod tickers;
 
-//pub use historical::Historical;
+pub use backtest::Backtest;
+pub use book::Book;
+pub use clock::{replay_clock, Clock, ScaledClock, SimulatedClock, SystemClock};
+pub use config::SimulationConfig;
+pub use data::{DataSource, FileSource, MarketValueChange, PostgresSource};
+pub use fills::{FillConfig, FillModel, Queue, Slippage};
+pub use live::Live;
 pub use simulated::Simulated;
 
-use crate::economy::{Market, Monetary, AssetSymbol};
+use bars::Bars;
+use exchange::{klines, Listings};
+use rolling::RollingStats;
+use store::{store_alert, store_balances};
+use tickers::Tickers;
+use crate::economy::{Alert, Bar, BookTicker, Market, Monetary, AssetSymbol, Stats, Trade};
+use crate::metrics::Metrics;
 use crate::traders::{Order, Action};
 use async_trait::async_trait;
 use binance_async::model::{Order as QueryOrder, Symbol};
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
 use std::time::Duration;
 
+/// Identifies an order placed through an environment, the order ID of the exchange.
+pub type OrderId = u64;
+
+/// Why an environment didn't place, cancel or replace an order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrderError {
+    /// The environment can't place this kind of order, e.g. market orders when trading live.
+    Unsupported,
+    /// The order isn't open, because it was never placed or already finished.
+    Unknown(OrderId),
+    /// The exchange refused the request, with its reason.
+    Rejected(String),
+    /// The request didn't reach the exchange, or its response couldn't be read.
+    Connection(String),
+}
+
+impl fmt::Display for OrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderError::Unsupported => write!(f, "order type not supported"),
+            OrderError::Unknown(id) => write!(f, "unknown order {}", id),
+            OrderError::Rejected(reason) => write!(f, "order rejected: {}", reason),
+            OrderError::Connection(error) => write!(f, "order request failed: {}", error),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum Event {
     SetMarketValue(String, Monetary),
+    /// The bar of a market that closes with the following evaluation.
+    /// Markets without a bar didn't change since the previous evaluation, or bars aren't aggregated.
+    SetMarketBar(String, Bar),
+    /// The best bid or ask of a market changed.
+    SetMarketBookTicker(String, BookTicker),
+    /// A trade on a market. Environments that only see tickers report the last trade of each ticker.
+    MarketTrade(String, Trade),
+    /// The statistics of a market over the last 24 hours.
+    SetMarketStats(String, Stats),
     SetAssetBalance(String, Monetary),
-    ExecutedOrder(QueryOrder, Monetary),
+    /// An order was filled, canceled or expired, with its executed quantity and the average fee rate of its fills.
+    /// Without a fee rate, the market's fee is charged.
+    ExecutedOrder(QueryOrder, Monetary, Option<Monetary>),
     Evaluate(i64),
+    /// A market was listed, or is listed again.
+    MarketAdded(MarketData),
+    /// Trading on the market is suspended, e.g. its status changed to `BREAK`.
+    MarketHalted(String),
+    MarketResumed(String),
+    /// The market was delisted.
+    MarketRemoved(String),
+    /// The environment has no more data, e.g. a backtest reached its end.
+    Finished,
 }
 
 pub type MarketData = Symbol;
@@ -24,7 +96,77 @@ pub type MarketData = Symbol;
 #[async_trait]
 pub trait Environment {
     async fn initialize(&mut self) -> Result<Vec<MarketData>, ()>;
+
+    /// Selects the markets that should be traded from now on.
+    /// Returns an error if the environment can't change its markets after initialization.
+    async fn universe(&mut self) -> Result<Vec<MarketData>, ()> {
+        Err(())
+    }
+
     async fn poll(&mut self) -> Event;
-    async fn order(&mut self, symbol: &str, order: Order) -> Result<(), ()>;
+    /// Places an order and returns its ID, which the executed order is reported with.
+    async fn order(&mut self, symbol: &str, order: Order) -> Result<OrderId, OrderError>;
+    /// Cancels an open order. Canceled orders are reported as executed orders with their filled quantity.
+    async fn cancel(&mut self, symbol: &str, id: OrderId) -> Result<(), OrderError>;
+    /// Cancels all open orders of the market.
+    async fn cancel_all(&mut self, symbol: &str) -> Result<(), OrderError>;
+    /// Orders of the market that are placed but not yet reported as executed.
+    async fn open_orders(&mut self, symbol: &str) -> Result<Vec<QueryOrder>, OrderError>;
+
+    /// Cancels an open order and places another one instead. What the canceled order filled is still reported.
+    async fn replace(&mut self, symbol: &str, id: OrderId, order: Order) -> Result<OrderId, OrderError> {
+        self.cancel(symbol, id).await?;
+        self.order(symbol, order).await
+    }
+
     async fn update_balances(&self, balances: Vec<(&AssetSymbol, Monetary)>);
+    async fn alert(&self, alert: &Alert);
+
+    /// Whether the balances of assets are reported after every fill, instead of being estimated from executed orders.
+    fn reports_balances(&self) -> bool {
+        false
+    }
+
+    /// Returns the values of a market with their timestamps, one per second, for the `duration` seconds
+    /// before the environment's current time. Used to warm up indicators before trading starts.
+    async fn history(&mut self, _symbol: &str, _duration: i64) -> Vec<(i64, Monetary)> {
+        Vec::new()
+    }
+
+    /// Reports the environment's own metrics, e.g. its connection to the exchange.
+    fn report(&self, _metrics: &Metrics) {}
+
+    /// The clock all time of the environment is measured with.
+    fn clock(&self) -> &dyn Clock {
+        &SystemClock
+    }
+}
+
+/// Turns sparse value changes, ordered by timestamp, into one value per second in `[from, to)`.
+/// Seconds before the first change are skipped, later gaps are filled with the previous value.
+pub fn fill_seconds(changes: &[(i64, Monetary)], from: i64, to: i64) -> Vec<(i64, Monetary)> {
+    let mut values = Vec::new();
+    let mut changes = changes.iter().peekable();
+    let mut current = None;
+
+    for timestamp in from..to {
+        while let Some((change, value)) = changes.peek() {
+            if *change > timestamp {
+                break;
+            }
+            current = Some(*value);
+            changes.next();
+        }
+        if let Some(value) = current {
+            values.push((timestamp, value));
+        }
+    }
+
+    values
+}
+
+#[tokio::test]
+async fn test_fill_seconds() {
+    let values = fill_seconds(&[(2, 1.0), (4, 2.0), (5, 3.0)], 0, 8);
+    assert_eq!(values, vec![(2, 1.0), (3, 1.0), (4, 2.0), (5, 3.0), (6, 3.0), (7, 3.0)]);
 }
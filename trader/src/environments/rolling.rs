@@ -0,0 +1,137 @@
+use super::Event;
+use crate::economy::{Monetary, Stats};
+use std::collections::{BTreeMap, VecDeque};
+
+/// Seconds the statistics of a market cover.
+const WINDOW: i64 = 3600 * 24;
+
+#[derive(Default)]
+struct Window {
+    values: VecDeque<(i64, Monetary, Monetary)>,
+    /// Values that may still become the high or the low once older values expire, in the order they were replayed.
+    highs: VecDeque<(i64, Monetary)>,
+    lows: VecDeque<(i64, Monetary)>,
+    volume: Monetary,
+    /// Last value replayed, which the statistics keep once every value expired.
+    last: Monetary,
+    changed: bool,
+}
+
+impl Window {
+    fn push(&mut self, timestamp: i64, value: Monetary, volume: Monetary) {
+        self.values.push_back((timestamp, value, volume));
+        self.volume += volume;
+        while self.highs.back().map_or(false, |(_, high)| *high <= value) {
+            self.highs.pop_back();
+        }
+        self.highs.push_back((timestamp, value));
+        while self.lows.back().map_or(false, |(_, low)| *low >= value) {
+            self.lows.pop_back();
+        }
+        self.lows.push_back((timestamp, value));
+        self.last = value;
+        self.changed = true;
+    }
+
+    /// Drops the values at or before `timestamp`.
+    fn expire(&mut self, timestamp: i64) {
+        while self.values.front().map_or(false, |(time, _, _)| *time <= timestamp) {
+            let (_, _, volume) = self.values.pop_front().unwrap();
+            self.volume -= volume;
+            self.changed = true;
+        }
+        while self.highs.front().map_or(false, |(time, _)| *time <= timestamp) {
+            self.highs.pop_front();
+        }
+        while self.lows.front().map_or(false, |(time, _)| *time <= timestamp) {
+            self.lows.pop_front();
+        }
+    }
+}
+
+/// Rolls the 24 hour statistics of markets over replayed values, for environments that don't receive them.
+///
+/// Each value counts as one trade, and values without a volume add none.
+/// Until a day was replayed, the statistics only cover the values replayed so far.
+/// Markets without values in the last day keep their last value, without volume or trades.
+pub struct RollingStats {
+    windows: BTreeMap<String, Window>,
+}
+
+impl RollingStats {
+    pub fn new() -> RollingStats {
+        RollingStats {
+            windows: BTreeMap::new(),
+        }
+    }
+
+    pub fn update(&mut self, symbol: &str, timestamp: i64, value: Monetary, volume: Option<Monetary>) {
+        self.windows
+            .entry(String::from(symbol))
+            .or_insert_with(Window::default)
+            .push(timestamp, value, volume.unwrap_or(0.0));
+    }
+
+    /// Returns the statistics at `timestamp` of the markets whose values changed or expired since the previous call, ordered by symbol.
+    pub fn close(&mut self, timestamp: i64) -> Vec<Event> {
+        let mut events = Vec::new();
+        for (symbol, window) in &mut self.windows {
+            window.expire(timestamp - WINDOW);
+            if !window.changed {
+                continue;
+            }
+            window.changed = false;
+            let stats = match (window.values.front(), window.highs.front(), window.lows.front()) {
+                (Some(first), Some(high), Some(low)) => Stats {
+                    open: first.1,
+                    high: high.1,
+                    low: low.1,
+                    close: window.last,
+                    volume: window.volume.max(0.0),
+                    trades: window.values.len() as u64,
+                },
+                _ => Stats {
+                    open: window.last,
+                    high: window.last,
+                    low: window.last,
+                    close: window.last,
+                    volume: 0.0,
+                    trades: 0,
+                },
+            };
+            events.push(Event::SetMarketStats(symbol.clone(), stats));
+        }
+        events
+    }
+}
+
+#[tokio::test]
+async fn test_rolling_stats() {
+    let mut rolling = RollingStats::new();
+    rolling.update("ETHUSDT", 60, 10.0, Some(2.0));
+    rolling.update("BTCUSDT", 0, 100.0, Some(1.0));
+    rolling.update("BTCUSDT", 60, 120.0, None);
+    rolling.update("BTCUSDT", WINDOW, 90.0, Some(0.5));
+
+    let events = rolling.close(WINDOW);
+    match &events[..] {
+        [Event::SetMarketStats(first, stats), Event::SetMarketStats(second, _)] => {
+            assert_eq!((first.as_str(), second.as_str()), ("BTCUSDT", "ETHUSDT"));
+            // The value at 0 is more than a day old.
+            assert_eq!(*stats, Stats { open: 120.0, high: 120.0, low: 90.0, close: 90.0, volume: 0.5, trades: 2 });
+        },
+        events => panic!("unexpected events {:?}", events),
+    }
+    assert!(rolling.close(WINDOW + 1).is_empty());
+
+    // Values expire without new ones being replayed.
+    match &rolling.close(WINDOW + 60)[..] {
+        [Event::SetMarketStats(first, btc), Event::SetMarketStats(second, eth)] => {
+            assert_eq!((first.as_str(), second.as_str()), ("BTCUSDT", "ETHUSDT"));
+            assert_eq!(*btc, Stats { open: 90.0, high: 90.0, low: 90.0, close: 90.0, volume: 0.5, trades: 1 });
+            assert_eq!(*eth, Stats { open: 10.0, high: 10.0, low: 10.0, close: 10.0, volume: 0.0, trades: 0 });
+        },
+        events => panic!("unexpected events {:?}", events),
+    }
+    assert!(rolling.close(WINDOW + 61).is_empty());
+}
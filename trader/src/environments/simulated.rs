@@ -1,21 +1,17 @@
-use super::{Environment, Event, MarketData};
-use crate::economy::{Market, AssetSymbol, Monetary};
-use crate::traders::{Action, Order};
+use super::{fill_seconds, klines, replay_clock, store_alert, store_balances, Bars, Clock, Environment, Event, FillModel, Listings, MarketData, OrderError, OrderId, SystemClock, Tickers};
+use crate::economy::{Alert, Market, AssetSymbol, Monetary};
+use crate::metrics::Metrics;
+use crate::traders::Order;
+use crate::universe::UniverseSelector;
 use async_trait::async_trait;
 use binance_async::{
-    model::{
-        Side,
-        OrderType,
-        TimeInForce,
-        Order as QueryOrder,
-        OrderRequest,
-        websocket::{Subscription, BinanceWebsocketMessage}
-    },
+    model::{websocket::{Subscription, BinanceWebsocketMessage}, Order as QueryOrder},
     Binance,
     BinanceWebsocket
 };
 use sqlx::PgPool;
-use std::time::{Duration, SystemTime};
+use std::collections::HashMap;
+use std::time::Duration;
 use tokio::stream::StreamExt;
 
 struct MarketValueChange {
@@ -27,16 +23,29 @@ struct MarketValueChange {
 pub struct Simulated {
     timestamp: i64,
     realtime: bool,
+    switchover: bool,
+    bars: Bars,
     buffer: Vec<MarketValueChange>,
     pool: PgPool,
     binance: Binance,
     socket: BinanceWebsocket,
     events: Vec<Event>,
-    orders: Vec<(Order, QueryOrder, SystemTime, bool)>,
+    fills: FillModel,
+    tickers: Tickers,
+    /// Volume traded since the value of each market was last set.
+    traded: HashMap<String, Monetary>,
+    listings: Listings,
+    checked: i64,
+    speed: Option<f64>,
+    clock: Box<dyn Clock>,
+    lag: Option<f64>,
 }
 
 impl Simulated {
-    pub async fn new() -> Simulated {
+    pub async fn new<U>(selector: U) -> Simulated
+    where
+        U: UniverseSelector + Send + Sync + 'static,
+    {
         dotenv::dotenv().ok();
 
         let pool = PgPool::new(&std::env::var("DATABASE_URL").unwrap())
@@ -59,57 +68,64 @@ impl Simulated {
         Simulated {
             timestamp,
             realtime: false,
+            switchover: true,
+            bars: Bars::new(),
             buffer: Vec::new(),
             pool,
             binance,
-            events: vec![Event::SetAssetBalance(String::from("USDT"), 200.0)],
+            events: Vec::new(),
             socket: BinanceWebsocket::default(),
-            orders: Vec::new()
+            fills: FillModel::new(),
+            tickers: Tickers::new(),
+            traded: HashMap::new(),
+            listings: Listings::new(selector),
+            checked: timestamp,
+            speed: None,
+            clock: replay_clock(timestamp, None),
+            lag: None,
         }
     }
-}
 
-#[async_trait]
-impl Environment for Simulated {
-    async fn initialize(&mut self) -> Result<Vec<MarketData>, ()> {
-        let stats = self
-            .binance
-            .get_24h_price_stats_all()
-            .unwrap()
-            .await
-            .unwrap();
-        let mut symbols = Vec::new();
-        for stat in stats {
-            if stat.count >= 24 * 60 {
-                symbols.push(stat.symbol);
-            }
-        }
+    pub fn set_fill_model(&mut self, fills: FillModel) {
+        self.fills = fills;
+    }
 
+    /// Starts replaying the recorded tickers at `start` instead of the first one.
+    pub fn set_start(&mut self, start: i64) {
+        self.timestamp = start;
+    }
 
-        let exchange_info = self.binance.get_exchange_info().unwrap().await.unwrap();
+    /// Sets the balance the simulation starts with.
+    pub fn set_balance(&mut self, asset: &str, balance: Monetary) {
+        self.events.push(Event::SetAssetBalance(String::from(asset), balance));
+    }
 
-        let mut to_usdt = vec![String::from("USDT")];
-        for symbol in &exchange_info.symbols {
-            if symbols.contains(&symbol.symbol) {
-                if symbol.base_asset == "USDT" && !to_usdt.contains(&symbol.quote_asset) {
-                    to_usdt.push(symbol.quote_asset.clone());
-                } else
-                if symbol.quote_asset == "USDT" && !to_usdt.contains(&symbol.base_asset) {
-                    to_usdt.push(symbol.base_asset.clone());
-                }
-            }
-        }
+    /// Only evaluates seconds that are a multiple of `interval`, with the bars of the values since the previous evaluation.
+    pub fn set_evaluation_interval(&mut self, interval: i64) {
+        self.bars.set_interval(interval);
+    }
 
-        let mut markets = Vec::new();
-        for symbol in exchange_info.symbols {
-            if symbols.contains(&symbol.symbol) {
-                if to_usdt.contains(&symbol.base_asset) && to_usdt.contains(&symbol.quote_asset) {
-                    markets.push(symbol);
-                }
-            }
-        }
+    /// Replays the recorded tickers at `speed` seconds per second of real time instead of as fast as possible.
+    pub fn set_speed(&mut self, speed: Option<f64>) {
+        self.speed = speed;
+    }
 
-        Ok(markets)
+    /// Whether to switch to the live tickers once the recorded ones are caught up with, which is the default.
+    /// Otherwise, the simulation waits for the fetcher to record new tickers.
+    pub fn set_switchover(&mut self, switchover: bool) {
+        self.switchover = switchover;
+    }
+}
+
+#[async_trait]
+impl Environment for Simulated {
+    async fn initialize(&mut self) -> Result<Vec<MarketData>, ()> {
+        self.clock = replay_clock(self.timestamp, self.speed);
+        self.universe().await
+    }
+
+    async fn universe(&mut self) -> Result<Vec<MarketData>, ()> {
+        self.listings.select(&self.binance).await
     }
 
     async fn poll(&mut self) -> Event {
@@ -118,33 +134,41 @@ impl Environment for Simulated {
                 return event;
             }
 
-            for (order, order_status, timestamp, open) in &mut self.orders {
-                if *open {
-                    //*order_status = self.binance.order_status(&order_status.symbol, order_status.order_id).unwrap().await.unwrap();
-                    *open = match order_status.status.as_str() {
-                        "FILLED" | "CANCELED" | "REJECTED" | "EXPIRED" => false,
-                        _ => true,
-                    };
-                    if !*open {
-                        return Event::ExecutedOrder(order_status.clone(), order_status.executed_qty);
-                    }
-                    if SystemTime::now().duration_since(*timestamp).unwrap().as_secs() > 10 {
-                        //self.binance.cancel_order(&order_status.symbol, order_status.order_id).unwrap().await.unwrap();
-                    }
-                }
+            if let Some((order, fee)) = self.fills.next() {
+                let executed = order.executed_qty;
+                return Event::ExecutedOrder(order, executed, Some(fee));
+            }
+
+            // Market statuses are only known for the present, so they are not checked while replaying history.
+            if self.realtime && self.clock.now() - self.checked >= 60 {
+                self.checked = self.clock.now();
+                let events = self.listings.check(&self.binance).await;
+                self.events.extend(events);
+                continue;
             }
 
-            let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs() as i64 - 1;
+            // Recorded tickers only reach up to the present, whatever the replay's clock says.
+            let now = SystemClock.now() - 1;
 
             if self.buffer.is_empty() {
                 if self.realtime {
                     //println!("fetching realtime data ... at {}, fetching {}", self.timestamp, now);
                     if let Some(message) = self.socket.try_next().await.unwrap() {
-                        if let BinanceWebsocketMessage::MiniTickerAll(tickers) = message {
+                        if let BinanceWebsocketMessage::TickerAll(tickers) = message {
+                            let received = SystemClock.now_millis();
+                            if let Some(sent) = tickers.iter().map(|ticker| ticker.event_time).max() {
+                                self.lag = Some((received - sent as i64) as f64 / 1000.0);
+                            }
                             for ticker in tickers {
+                                let (events, traded) = self.tickers.update(&ticker);
+                                if let Some(traded) = traded {
+                                    *self.traded.entry(ticker.symbol.clone()).or_default() += traded;
+                                }
+                                // The other channels only update the markets, so they can be reported before the values.
+                                self.events.extend(events);
                                 self.buffer.push(MarketValueChange {
                                     symbol: ticker.symbol,
-                                    value: ticker.close,
+                                    value: ticker.current_close,
                                     timestamp: now,
                                 });
                             }
@@ -153,15 +177,22 @@ impl Environment for Simulated {
                 } else {
                     self.timestamp += 1;
                     // TODO: Find a proper switchover criterion.
+                    if self.timestamp + 10 >= now && !self.switchover {
+                        self.timestamp -= 1;
+                        tokio::time::delay_for(Duration::from_secs(1)).await;
+                        continue;
+                    }
                     if self.timestamp + 10 >= now {
-                        println!("switching over to realtime data ...");
+                        tracing::info!("switching over to realtime data");
                         self.realtime = true;
-                        self.socket.subscribe(Subscription::MiniTickerAll).await.unwrap();
+                        self.clock = Box::new(SystemClock);
+                        self.checked = self.clock.now();
+                        self.socket.subscribe(Subscription::TickerAll).await.unwrap();
                         continue;
                     }
 
                     let to = std::cmp::min(3600 * 24, now - self.timestamp);
-                    println!("fetching historical data ...  at {}, fetching [{}-{}]", self.timestamp, self.timestamp, self.timestamp + to - 1);
+                    tracing::info!(from = self.timestamp, to = self.timestamp + to - 1, "fetching historical data");
                     self.buffer = sqlx::query_as!(
                         MarketValueChange,
                         "
@@ -182,121 +213,104 @@ impl Environment for Simulated {
 
             if let Some(next) = self.buffer.pop() {
                 if next.timestamp == self.timestamp {
+                    let volume = self.traded.remove(&next.symbol);
+                    self.fills.trade(&next.symbol, next.value, volume, self.timestamp);
+                    self.bars.update(&next.symbol, next.value, volume);
                     return Event::SetMarketValue(next.symbol, next.value);
                 } else {
                     self.timestamp += 1;
-                    return Event::Evaluate(self.timestamp - 1);
+                    self.clock.wait_until(self.timestamp).await;
+                    self.fills.advance(self.timestamp);
+                    if self.bars.closes(self.timestamp - 1) {
+                        // Events are popped from the back, so the bars come before the evaluation.
+                        self.events.push(Event::Evaluate(self.timestamp - 1));
+                        let bars = self.bars.close();
+                        self.events.extend(bars);
+                    }
                 }
             }
             
         }
     }
 
-    async fn order(&mut self, symbol: &str, order: Order) -> Result<(), ()> {
-        
-        /*sqlx::query!(
-            "
-                INSERT INTO trades (id, symbol, base, quote, price, quantity, side, type, time_in_force) VALUES
-                ($1, $2, $3, $4, $5)
-            ",
-            ""
-        )
-        .execute(&self.pool)
-        .await
-        .unwrap();*/
-        
-        let order_request = match order {
-            Order::Limit(Action::Buy, quantity, value) => {
-                Ok(OrderRequest {
-                    symbol: String::from(symbol),
-                    qty: quantity,
-                    price: value,
-                    order_side: Side::Buy,
-                    order_type: String::from("LIMIT"),
-                    time_in_force: String::from("GTC"),
-                })
-            },
-            Order::Limit(Action::Sell, quantity, value) => {
-                Ok(OrderRequest {
-                    symbol: String::from(symbol),
-                    qty: quantity,
-                    price: value,
-                    order_side: Side::Sell,
-                    order_type: String::from("LIMIT"),
-                    time_in_force: String::from("GTC"),
-                })
-            },
-            /*
-            Order::Market(Action::Buy, quantity) => {
-                Ok(OrderRequest {
-                    symbol: String::from(symbol),
-                    qty: quantity,
-                    price: 0.0,
-                    order_side: Side::Buy,
-                    order_type: String::from("MARKET"),
-                    time_in_force: String::from("GTC"),
-                })
-            },
-            Order::Market(Action::Sell, quantity) => {
-                Ok(OrderRequest {
-                    symbol: String::from(symbol),
-                    qty: quantity,
-                    price: 0.0,
-                    order_side: Side::Sell,
-                    order_type: String::from("MARKET"),
-                    time_in_force: String::from("GTC"),
-                })
-            }
-            */
-            _ => { Err(()) }
-        };
-
-        if let Ok(order_request) = order_request {
-            let query_order = QueryOrder {
-                symbol: String::from(symbol),
-                order_id: 0,
-                client_order_id: String::new(),
-                price: order_request.price,
-                orig_qty: order_request.qty,
-                executed_qty: order_request.qty,
-                status: String::from("FILLED"),
-                time_in_force: order_request.time_in_force,
-                type_name: order_request.order_type,
-                side: order_request.order_side,
-                stop_price: 0.0,
-                iceberg_qty: String::new(),
-                time: 0,
-            };
-
-            self.orders.push((order, query_order, SystemTime::now(), true));
+    async fn order(&mut self, symbol: &str, order: Order) -> Result<OrderId, OrderError> {
+        Ok(self.fills.place(symbol, order, self.timestamp))
+    }
 
+    async fn cancel(&mut self, _symbol: &str, id: OrderId) -> Result<(), OrderError> {
+        if self.fills.cancel(id) {
             Ok(())
         } else {
-            // TODO: Proper error handling.
-            Ok(())
+            Err(OrderError::Unknown(id))
         }
     }
 
+    async fn cancel_all(&mut self, symbol: &str) -> Result<(), OrderError> {
+        self.fills.cancel_all(symbol);
+        Ok(())
+    }
+
+    async fn open_orders(&mut self, symbol: &str) -> Result<Vec<QueryOrder>, OrderError> {
+        Ok(self.fills.open_orders(symbol))
+    }
+
     async fn update_balances(&self, balances: Vec<(&AssetSymbol, Monetary)>) {
-        // Build query.
-        let mut sql = String::new();
-        sql.push_str("INSERT INTO balances (symbol, balance) VALUES");
-        for i in 0..balances.len() {
-            sql.push_str(&format!(
-                " (${}, ${}),",
-                i * 2 + 1,
-                i * 2 + 2
-            ));
+        if let Err(error) = store_balances(&self.pool, &balances).await {
+            tracing::warn!(%error, "storing balances failed");
         }
-        sql.pop();
-        sql.push_str(" ON CONFLICT (symbol) DO UPDATE SET balance = EXCLUDED.balance");
-
-        // Bind values to query.
-        let mut query = sqlx::query(&sql);
-        for balance in &balances {
-            query = query.bind(balance.0.as_str());
-            query = query.bind(balance.1);
+    }
+
+    async fn history(&mut self, symbol: &str, duration: i64) -> Vec<(i64, Monetary)> {
+        let to = self.timestamp;
+        let from = to - duration;
+
+        let mut changes = sqlx::query_as!(
+                MarketValueChange,
+                "
+                    SELECT symbol, value, timestamp
+                    FROM tickers
+                    WHERE symbol = $1
+                    AND timestamp >= $2::BIGINT
+                    AND timestamp < $3::BIGINT
+                    ORDER BY timestamp ASC
+                ",
+                symbol,
+                from,
+                to
+            )
+            .fetch_all(&self.pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|change| (change.timestamp, change.value))
+            .collect::<Vec<(i64, Monetary)>>();
+
+        // Fill in from one minute klines where the database doesn't reach back far enough.
+        let recorded = changes.first().map(|(timestamp, _)| *timestamp).unwrap_or(to);
+        if recorded > from {
+            let mut earlier = klines(&self.binance, symbol, from, recorded).await;
+            earlier.append(&mut changes);
+            changes = earlier;
+        }
+
+        fill_seconds(&changes, from, to)
+    }
+
+    async fn alert(&self, alert: &Alert) {
+        if let Err(error) = store_alert(&self.pool, alert).await {
+            tracing::warn!(%error, "storing alert failed");
+        }
+    }
+
+    fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
+    }
+
+    fn report(&self, metrics: &Metrics) {
+        metrics.set_queue_depth(self.events.len() + self.buffer.len());
+        metrics.set_api_weight(self.binance.transport.used_weight());
+        if let Some(lag) = self.lag {
+            metrics.set_websocket_lag(lag);
         }
-        query.execute(&self.pool).await.unwrap();
     }
 }
@@ -0,0 +1,40 @@
+use crate::economy::{Alert, AssetSymbol, Monetary};
+use sqlx::PgPool;
+
+/// Replaces the stored balances of the assets in the `balances` table.
+pub async fn store_balances(pool: &PgPool, balances: &[(&AssetSymbol, Monetary)]) -> Result<(), sqlx::Error> {
+    if balances.is_empty() {
+        return Ok(());
+    }
+
+    // Build query.
+    let mut sql = String::new();
+    sql.push_str("INSERT INTO balances (symbol, balance) VALUES");
+    for i in 0..balances.len() {
+        sql.push_str(&format!(
+            " (${}, ${}),",
+            i * 2 + 1,
+            i * 2 + 2
+        ));
+    }
+    sql.pop();
+    sql.push_str(" ON CONFLICT (symbol) DO UPDATE SET balance = EXCLUDED.balance");
+
+    // Bind values to query.
+    let mut query = sqlx::query(&sql);
+    for balance in balances {
+        query = query.bind(balance.0.as_str());
+        query = query.bind(balance.1);
+    }
+    query.execute(pool).await?;
+    Ok(())
+}
+
+/// Adds an alert to the `messages` table.
+pub async fn store_alert(pool: &PgPool, alert: &Alert) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO messages (message) VALUES ($1)")
+        .bind(alert.to_string())
+        .execute(pool)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use super::Event;
+use crate::economy::{BookTicker, Monetary, Stats, Trade};
+use binance_async::model::websocket::Ticker;
+use std::collections::HashMap;
+
+/// Turns the 24 hour tickers of the websocket into market events, only reporting what changed since the previous ticker.
+pub struct Tickers {
+    volumes: HashMap<String, Monetary>,
+    book_tickers: HashMap<String, BookTicker>,
+    trades: HashMap<String, u64>,
+}
+
+impl Tickers {
+    pub fn new() -> Tickers {
+        Tickers {
+            volumes: HashMap::new(),
+            book_tickers: HashMap::new(),
+            trades: HashMap::new(),
+        }
+    }
+
+    /// Returns the events of a ticker besides its value, and the volume traded since the previous ticker of the market.
+    pub fn update(&mut self, ticker: &Ticker) -> (Vec<Event>, Option<Monetary>) {
+        let symbol = &ticker.symbol;
+        let mut events = Vec::new();
+
+        let book_ticker = BookTicker {
+            bid: ticker.best_bid,
+            bid_quantity: ticker.best_bid_qty,
+            ask: ticker.best_ask,
+            ask_quantity: ticker.best_ask_qty,
+        };
+        if self.book_tickers.insert(symbol.clone(), book_ticker) != Some(book_ticker) {
+            events.push(Event::SetMarketBookTicker(symbol.clone(), book_ticker));
+        }
+        // Tickers only carry the last trade since the previous one.
+        if self.trades.insert(symbol.clone(), ticker.last_trade_id) != Some(ticker.last_trade_id) {
+            events.push(Event::MarketTrade(symbol.clone(), Trade {
+                price: ticker.current_close,
+                quantity: ticker.current_close_qty,
+                timestamp: (ticker.event_time / 1000) as i64,
+            }));
+        }
+        events.push(Event::SetMarketStats(symbol.clone(), Stats {
+            open: ticker.open,
+            high: ticker.high,
+            low: ticker.low,
+            close: ticker.current_close,
+            volume: ticker.volume,
+            trades: ticker.num_trades,
+        }));
+
+        // The difference of the rolling 24 hour volume approximates the volume traded since the last ticker.
+        let traded = self.volumes
+            .insert(symbol.clone(), ticker.volume)
+            .map(|previous| (ticker.volume - previous).max(0.0));
+        (events, traded)
+    }
+}
+
+#[tokio::test]
+async fn test_tickers() {
+    let ticker = |bid: f64, trade: u64, volume: f64| -> Ticker {
+        serde_json::from_value(serde_json::json!({
+            "e": "24hrTicker", "E": 1600000000000u64, "s": "BTCUSDT",
+            "p": "0", "P": "0", "w": "0", "x": "0", "c": "100", "Q": "0.5",
+            "b": bid.to_string(), "B": "1", "a": "101", "A": "1",
+            "o": "90", "h": "110", "l": "80", "v": volume.to_string(), "q": "0",
+            "O": 0, "C": 0, "F": 0, "L": trade, "n": trade,
+        })).unwrap()
+    };
+
+    let mut tickers = Tickers::new();
+    let (events, traded) = tickers.update(&ticker(99.0, 1, 10.0));
+    assert_eq!(events.len(), 3);
+    assert_eq!(traded, None);
+
+    let (events, traded) = tickers.update(&ticker(99.0, 1, 12.0));
+    assert!(matches!(events[..], [Event::SetMarketStats(_, _)]));
+    assert_eq!(traded, Some(2.0));
+
+    let (events, _) = tickers.update(&ticker(99.5, 2, 12.0));
+    match &events[..] {
+        [Event::SetMarketBookTicker(_, book_ticker), Event::MarketTrade(_, trade), Event::SetMarketStats(_, stats)] => {
+            assert_eq!(book_ticker.bid, 99.5);
+            assert_eq!((trade.price, trade.quantity, trade.timestamp), (100.0, 0.5, 1600000000));
+            assert_eq!(stats.trades, 2);
+        },
+        events => panic!("unexpected events {:?}", events),
+    }
+}
@@ -0,0 +1,46 @@
+use super::{Indicator, SMMA};
+use crate::economy::{Bar, Monetary};
+use serde::{Serialize, Deserialize};
+
+/// Average true range, the smoothed range of each bar including the gap to the previous close.
+#[derive(Serialize, Deserialize)]
+pub struct ATR<const PERIOD: usize> {
+    range: SMMA<PERIOD>,
+    close: Monetary,
+}
+
+impl<const PERIOD: usize> Indicator for ATR<PERIOD> {
+    type Output = Option<Monetary>;
+
+    fn initialize(value: Monetary) -> Self {
+        ATR {
+            range: SMMA::initialize(0.0),
+            close: value,
+        }
+    }
+
+    fn evaluate(&mut self, value: Monetary) -> Self::Output {
+        self.evaluate_bar(&Bar::flat(value))
+    }
+
+    fn initialize_bar(bar: &Bar) -> Self {
+        ATR {
+            range: SMMA::initialize(bar.high - bar.low),
+            close: bar.close,
+        }
+    }
+
+    fn evaluate_bar(&mut self, bar: &Bar) -> Self::Output {
+        let range = bar.high.max(self.close) - bar.low.min(self.close);
+        self.close = bar.close;
+        self.range.evaluate(range)
+    }
+}
+
+#[tokio::test]
+async fn test_atr() {
+    let mut atr = ATR::<2>::initialize_bar(&Bar { open: 10.0, high: 11.0, low: 9.0, close: 10.0, volume: 0.0, bid: None, ask: None });
+    assert_eq!(atr.evaluate_bar(&Bar { open: 10.0, high: 12.0, low: 10.0, close: 12.0, volume: 0.0, bid: None, ask: None }), None);
+    // The gap from the previous close of 12 counts towards the range.
+    assert_eq!(atr.evaluate_bar(&Bar { open: 14.0, high: 15.0, low: 14.0, close: 15.0, volume: 0.0, bid: None, ask: None }), Some(2.5));
+}
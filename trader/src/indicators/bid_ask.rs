@@ -0,0 +1,32 @@
+use super::Indicator;
+use crate::economy::{Bar, Monetary};
+use serde::{Serialize, Deserialize};
+
+/// The best bid and ask at the close of the current bar, to price limit orders at the touch.
+/// Without a book ticker, both are the close.
+#[derive(Serialize, Deserialize)]
+pub struct BidAsk;
+
+impl Indicator for BidAsk {
+    type Output = (Monetary, Monetary);
+
+    fn initialize(_value: Monetary) -> Self {
+        BidAsk
+    }
+
+    fn evaluate(&mut self, value: Monetary) -> Self::Output {
+        (value, value)
+    }
+
+    fn evaluate_bar(&mut self, bar: &Bar) -> Self::Output {
+        (bar.bid.unwrap_or(bar.close), bar.ask.unwrap_or(bar.close))
+    }
+}
+
+#[tokio::test]
+async fn test_bid_ask() {
+    let mut bid_ask = BidAsk::initialize(100.0);
+    assert_eq!(bid_ask.evaluate_bar(&Bar::flat(100.0)), (100.0, 100.0));
+    let bar = Bar { bid: Some(99.0), ask: Some(101.0), ..Bar::flat(100.0) };
+    assert_eq!(bid_ask.evaluate_bar(&bar), (99.0, 101.0));
+}
@@ -0,0 +1,23 @@
+use super::Indicator;
+use crate::economy::{Bar, Monetary};
+use serde::{Serialize, Deserialize};
+
+/// The current bar. Single values are seen as flat bars.
+#[derive(Serialize, Deserialize)]
+pub struct Candle;
+
+impl Indicator for Candle {
+    type Output = Bar;
+
+    fn initialize(_value: Monetary) -> Self {
+        Candle
+    }
+
+    fn evaluate(&mut self, value: Monetary) -> Self::Output {
+        Bar::flat(value)
+    }
+
+    fn evaluate_bar(&mut self, bar: &Bar) -> Self::Output {
+        *bar
+    }
+}
@@ -0,0 +1,54 @@
+use super::{ema, Average, Convergence, ConvergenceHistogram, StretchedRSI};
+use crate::economy::Monetary;
+use serde::{Serialize, Deserialize};
+
+/// Exponential moving average with a period chosen at runtime, behaves like `EMA<PERIOD>`.
+#[derive(Serialize, Deserialize)]
+pub struct DynamicEMA {
+    period: usize,
+    ema: Monetary,
+    count: usize,
+}
+
+impl DynamicEMA {
+    pub fn new(period: usize, value: Monetary) -> DynamicEMA {
+        DynamicEMA {
+            period,
+            ema: value,
+            count: 0,
+        }
+    }
+}
+
+impl Average for DynamicEMA {
+    fn average(&mut self, value: Monetary) -> Option<Monetary> {
+        ema::smooth(&mut self.ema, &mut self.count, self.period, value)
+    }
+}
+
+/// Behaves like `StretchedRSI<EMA<PERIOD>, EMA<SCALE>>`.
+pub type DynamicStretchedRSI = StretchedRSI<DynamicEMA, DynamicEMA>;
+
+impl DynamicStretchedRSI {
+    pub fn new(period: usize, scale: usize, value: Monetary) -> DynamicStretchedRSI {
+        StretchedRSI::with(DynamicEMA::new(period, value), DynamicEMA::new(period, value), DynamicEMA::new(scale, 0.0), value)
+    }
+
+    pub fn evaluate(&mut self, value: Monetary) -> Option<Monetary> {
+        self.update(value)
+    }
+}
+
+/// Behaves like `MACDHistogram<SHORT, LONG, PERIOD>`.
+pub type DynamicMACDHistogram = ConvergenceHistogram<DynamicEMA, DynamicEMA, DynamicEMA>;
+
+impl DynamicMACDHistogram {
+    pub fn new([short, long, period]: [usize; 3], value: Monetary) -> DynamicMACDHistogram {
+        let macd = Convergence::with(DynamicEMA::new(short, value), DynamicEMA::new(long, value));
+        ConvergenceHistogram::with(macd, DynamicEMA::new(period, 0.0))
+    }
+
+    pub fn evaluate(&mut self, value: Monetary) -> Option<(Monetary, Monetary)> {
+        self.update(value)
+    }
+}
@@ -1,6 +1,8 @@
 use super::{Indicator, MovingAverage};
 use crate::economy::Monetary;
+use serde::{Serialize, Deserialize};
 
+#[derive(Serialize, Deserialize)]
 pub struct EMA<const PERIOD: usize> {
     ema: Monetary,
     count: usize,
@@ -17,22 +19,27 @@ impl<const PERIOD: usize> Indicator for EMA<PERIOD> {
     }
 
     fn evaluate(&mut self, value: Monetary) -> Self::Output {
-        self.count += 1;
-
-        let alpha = 2.0 / (1.0 + PERIOD as f64);
-        self.ema *= 1.0 - alpha;
-        self.ema += value * alpha;
-
-        if self.count >= PERIOD {
-            Some(self.ema)
-        } else {
-            None
-        }
+        smooth(&mut self.ema, &mut self.count, PERIOD, value)
     }
 }
 
 impl<const PERIOD: usize> MovingAverage for EMA<PERIOD> {}
 
+/// Adds a value to an exponential moving average of `period` values, shared with `DynamicEMA`.
+pub(super) fn smooth(ema: &mut Monetary, count: &mut usize, period: usize, value: Monetary) -> Option<Monetary> {
+    *count += 1;
+
+    let alpha = 2.0 / (1.0 + period as f64);
+    *ema *= 1.0 - alpha;
+    *ema += value * alpha;
+
+    if *count >= period {
+        Some(*ema)
+    } else {
+        None
+    }
+}
+
 #[tokio::test]
 async fn test_ema() {
     let mut ema = EMA::<9>::initialize(0.0);
@@ -1,25 +1,32 @@
-use super::{Indicator, EMA};
+use super::{Average, Indicator, MovingAverage, EMA};
 use crate::economy::Monetary;
+use serde::{Serialize, Deserialize};
 
-pub struct MACD<const SHORT: usize, const LONG: usize> {
-    ema_short: EMA<SHORT>,
-    ema_long: EMA<LONG>,
+/// Difference of a short and a long moving average, whose periods may be chosen at runtime.
+#[derive(Serialize, Deserialize)]
+pub struct Convergence<S, L> {
+    ema_short: S,
+    ema_long: L,
 }
 
-impl<const SHORT: usize, const LONG: usize> Indicator for MACD<SHORT, LONG> {
-    type Output = Option<Monetary>;
+pub type MACD<const SHORT: usize, const LONG: usize> = Convergence<EMA<SHORT>, EMA<LONG>>;
 
-    fn initialize(value: Monetary) -> Self {
-        MACD {
-            ema_short: EMA::initialize(value),
-            ema_long: EMA::initialize(value),
+impl<S, L> Convergence<S, L>
+where
+    S: Average,
+    L: Average,
+{
+    pub(super) fn with(ema_short: S, ema_long: L) -> Self {
+        Convergence {
+            ema_short,
+            ema_long,
         }
     }
 
-    fn evaluate(&mut self, value: Monetary) -> Self::Output {
+    pub(super) fn update(&mut self, value: Monetary) -> Option<Monetary> {
         if let (Some(short), Some(long)) = (
-            self.ema_short.evaluate(value),
-            self.ema_long.evaluate(value),
+            self.ema_short.average(value),
+            self.ema_long.average(value),
         ) {
             Some(short - long)
         } else {
@@ -27,3 +34,19 @@ impl<const SHORT: usize, const LONG: usize> Indicator for MACD<SHORT, LONG> {
         }
     }
 }
+
+impl<S, L> Indicator for Convergence<S, L>
+where
+    S: MovingAverage<Output = Option<Monetary>>,
+    L: MovingAverage<Output = Option<Monetary>>,
+{
+    type Output = Option<Monetary>;
+
+    fn initialize(value: Monetary) -> Self {
+        Convergence::with(S::initialize(value), L::initialize(value))
+    }
+
+    fn evaluate(&mut self, value: Monetary) -> Self::Output {
+        self.update(value)
+    }
+}
@@ -1,28 +1,34 @@
-use super::{Indicator, EMA, MACD};
+use super::{Average, Convergence, Indicator, MovingAverage, EMA};
 use crate::economy::Monetary;
+use serde::{Serialize, Deserialize};
 
-pub struct MACDHistogram<const SHORT: usize, const LONG: usize, const PERIOD: usize> {
+/// MACD and its distance to its signal line, with the periods of the averages possibly chosen at runtime.
+#[derive(Serialize, Deserialize)]
+pub struct ConvergenceHistogram<S, L, P> {
     histogram: Monetary,
-    macd: MACD<SHORT, LONG>,
-    signal: EMA<PERIOD>,
+    macd: Convergence<S, L>,
+    signal: P,
 }
 
-impl<const SHORT: usize, const LONG: usize, const PERIOD: usize> Indicator
-    for MACDHistogram<SHORT, LONG, PERIOD>
-{
-    type Output = Option<(Monetary, Monetary)>;
+pub type MACDHistogram<const SHORT: usize, const LONG: usize, const PERIOD: usize> = ConvergenceHistogram<EMA<SHORT>, EMA<LONG>, EMA<PERIOD>>;
 
-    fn initialize(value: Monetary) -> Self {
-        MACDHistogram {
+impl<S, L, P> ConvergenceHistogram<S, L, P>
+where
+    S: Average,
+    L: Average,
+    P: Average,
+{
+    pub(super) fn with(macd: Convergence<S, L>, signal: P) -> Self {
+        ConvergenceHistogram {
             histogram: 0.0,
-            macd: MACD::initialize(value),
-            signal: EMA::initialize(0.0),
+            macd,
+            signal,
         }
     }
 
-    fn evaluate(&mut self, value: Monetary) -> Self::Output {
-        if let Some(macd) = self.macd.evaluate(value) {
-            if let Some(signal) = self.signal.evaluate(macd) {
+    pub(super) fn update(&mut self, value: Monetary) -> Option<(Monetary, Monetary)> {
+        if let Some(macd) = self.macd.update(value) {
+            if let Some(signal) = self.signal.average(macd) {
                 self.histogram = macd - signal;
                 return Some((macd, self.histogram));
             }
@@ -30,3 +36,20 @@ impl<const SHORT: usize, const LONG: usize, const PERIOD: usize> Indicator
         None
     }
 }
+
+impl<S, L, P> Indicator for ConvergenceHistogram<S, L, P>
+where
+    S: MovingAverage<Output = Option<Monetary>>,
+    L: MovingAverage<Output = Option<Monetary>>,
+    P: MovingAverage<Output = Option<Monetary>>,
+{
+    type Output = Option<(Monetary, Monetary)>;
+
+    fn initialize(value: Monetary) -> Self {
+        ConvergenceHistogram::with(Convergence::initialize(value), P::initialize(0.0))
+    }
+
+    fn evaluate(&mut self, value: Monetary) -> Self::Output {
+        self.update(value)
+    }
+}
@@ -1,3 +1,5 @@
+mod atr;
+mod candle;
 mod ema;
 mod macd;
 mod macd_histogram;
@@ -6,27 +8,62 @@ mod sma;
 mod value;
 mod stretched_rsi;
 mod smma;
+mod dynamic;
+mod bid_ask;
+mod relative_volume;
 
+pub use atr::ATR;
+pub use candle::Candle;
 pub use ema::EMA;
-pub use macd::MACD;
-pub use macd_histogram::MACDHistogram;
+pub use macd::{Convergence, MACD};
+pub use macd_histogram::{ConvergenceHistogram, MACDHistogram};
 pub use rsi::RSI;
 pub use sma::SMA;
 pub use value::Value;
 pub use stretched_rsi::StretchedRSI;
 pub use smma::SMMA;
+pub use dynamic::{DynamicEMA, DynamicStretchedRSI, DynamicMACDHistogram};
+pub use bid_ask::BidAsk;
+pub use relative_volume::RelativeVolume;
 
-use crate::economy::Monetary;
+use crate::economy::{Bar, Monetary};
 
 pub trait Indicator {
     type Output;
 
     fn initialize(value: Monetary) -> Self;
     fn evaluate(&mut self, value: Monetary) -> Self::Output;
+
+    /// Indicators of single values are initialized with the close of the first bar.
+    fn initialize_bar(bar: &Bar) -> Self
+    where
+        Self: Sized,
+    {
+        Self::initialize(bar.close)
+    }
+
+    /// Indicators of single values only see the close of each bar.
+    fn evaluate_bar(&mut self, bar: &Bar) -> Self::Output {
+        self.evaluate(bar.close)
+    }
 }
 
 pub trait MovingAverage: Indicator {}
 
+/// A moving average, whether its period is fixed by its type or chosen at runtime.
+pub trait Average {
+    fn average(&mut self, value: Monetary) -> Option<Monetary>;
+}
+
+impl<MA> Average for MA
+where
+    MA: MovingAverage<Output = Option<Monetary>>,
+{
+    fn average(&mut self, value: Monetary) -> Option<Monetary> {
+        self.evaluate(value)
+    }
+}
+
 macro_rules! peel {
     ( $name:ident, $($other:ident,)* ) => (tuple! { $($other,)* })
 }
@@ -46,6 +83,16 @@ macro_rules! tuple {
                 let ($($name,)+) = self;
                 ($($name.evaluate(value),)+)
             }
+
+            fn initialize_bar(bar: &Bar) -> Self {
+                ($($name::initialize_bar(bar),)+)
+            }
+
+            #[allow(non_snake_case)]
+            fn evaluate_bar(&mut self, bar: &Bar) -> Self::Output {
+                let ($($name,)+) = self;
+                ($($name.evaluate_bar(bar),)+)
+            }
         }
         peel! { $($name,)+ }
     };
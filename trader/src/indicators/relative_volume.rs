@@ -0,0 +1,48 @@
+use super::{Indicator, SMA};
+use crate::economy::{Bar, Monetary};
+use serde::{Serialize, Deserialize};
+
+/// Volume of the current bar relative to the average volume of the last `PERIOD` bars.
+/// Single values count as bars without volume.
+#[derive(Serialize, Deserialize)]
+pub struct RelativeVolume<const PERIOD: usize> {
+    volume: SMA<PERIOD>,
+}
+
+impl<const PERIOD: usize> Indicator for RelativeVolume<PERIOD> {
+    type Output = Option<Monetary>;
+
+    fn initialize(_value: Monetary) -> Self {
+        RelativeVolume {
+            volume: SMA::initialize(0.0),
+        }
+    }
+
+    fn evaluate(&mut self, value: Monetary) -> Self::Output {
+        self.evaluate_bar(&Bar::flat(value))
+    }
+
+    fn initialize_bar(bar: &Bar) -> Self {
+        RelativeVolume {
+            volume: SMA::initialize(bar.volume),
+        }
+    }
+
+    fn evaluate_bar(&mut self, bar: &Bar) -> Self::Output {
+        let average = self.volume.evaluate(bar.volume)?;
+        if average > 0.0 {
+            Some(bar.volume / average)
+        } else {
+            None
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_relative_volume() {
+    let bar = |volume| Bar { volume, ..Bar::flat(100.0) };
+    let mut relative = RelativeVolume::<2>::initialize_bar(&bar(1.0));
+    assert_eq!(relative.evaluate_bar(&bar(1.0)), None);
+    assert_eq!(relative.evaluate_bar(&bar(3.0)), Some(1.5));
+    assert_eq!(relative.evaluate(100.0), Some(0.0));
+}
@@ -1,6 +1,8 @@
 use super::{Indicator, MovingAverage};
 use crate::economy::Monetary;
+use serde::{Serialize, Deserialize};
 
+#[derive(Serialize, Deserialize)]
 pub struct RSI<MA>
 where
     MA: MovingAverage<Output = Option<Monetary>>,
@@ -1,5 +1,6 @@
 use super::{Indicator, MovingAverage};
 use crate::economy::Monetary;
+use serde::{de, Serialize, Serializer, Deserialize, Deserializer};
 
 pub struct SMA<const PERIOD: usize> {
     sma: Monetary,
@@ -39,6 +40,29 @@ impl<const PERIOD: usize> Indicator for SMA<PERIOD> {
 
 impl<const PERIOD: usize> MovingAverage for SMA<PERIOD> {}
 
+// Serde can't derive implementations for arrays of arbitrary length, so the values are stored as a sequence.
+impl<const PERIOD: usize> Serialize for SMA<PERIOD> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.sma, self.count, &self.values[..]).serialize(serializer)
+    }
+}
+
+impl<'de, const PERIOD: usize> Deserialize<'de> for SMA<PERIOD> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (sma, count, stored): (Monetary, usize, Vec<Monetary>) = Deserialize::deserialize(deserializer)?;
+        if stored.len() != PERIOD {
+            return Err(de::Error::invalid_length(stored.len(), &"one value per period"));
+        }
+        let mut values = [0.0; PERIOD];
+        values.copy_from_slice(&stored);
+        Ok(SMA {
+            sma,
+            count,
+            values,
+        })
+    }
+}
+
 #[tokio::test]
 async fn test_sma() {
     let mut sma = SMA::<9>::initialize(0.0);
@@ -47,3 +71,17 @@ async fn test_sma() {
     }
     assert_eq!(sma.evaluate(8.0), Some(4.0));
 }
+
+#[tokio::test]
+async fn test_sma_serde() {
+    let mut sma = SMA::<3>::initialize(0.0);
+    sma.evaluate(1.0);
+    sma.evaluate(2.0);
+
+    let mut restored: SMA<3> = serde_json::from_value(serde_json::to_value(&sma).unwrap()).unwrap();
+    assert_eq!(restored.evaluate(3.0), sma.evaluate(3.0));
+    assert_eq!(restored.evaluate(4.0), Some(3.0));
+
+    // The stored values must match the period.
+    assert!(serde_json::from_value::<SMA<4>>(serde_json::to_value(&sma).unwrap()).is_err());
+}
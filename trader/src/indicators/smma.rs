@@ -1,6 +1,8 @@
 use super::{Indicator, MovingAverage};
 use crate::economy::Monetary;
+use serde::{Serialize, Deserialize};
 
+#[derive(Serialize, Deserialize)]
 pub struct SMMA<const PERIOD: usize> {
     smma: Monetary,
     count: usize,
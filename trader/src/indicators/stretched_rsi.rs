@@ -1,10 +1,12 @@
-use super::{Indicator, MovingAverage, SMA};
+use super::{Average, Indicator, MovingAverage};
 use crate::economy::Monetary;
+use serde::{Serialize, Deserialize};
 
+#[derive(Serialize, Deserialize)]
 pub struct StretchedRSI<MA, STRETCH>
 where
-    MA: MovingAverage<Output = Option<Monetary>>,
-    STRETCH: MovingAverage<Output = Option<Monetary>>,
+    MA: Average,
+    STRETCH: Average,
 {
     up: MA,
     down: MA,
@@ -12,28 +14,27 @@ where
     change: STRETCH
 }
 
-impl<MA, STRETCH> Indicator for StretchedRSI<MA, STRETCH>
+impl<MA, STRETCH> StretchedRSI<MA, STRETCH>
 where
-    MA: MovingAverage<Output = Option<Monetary>>,
-    STRETCH: MovingAverage<Output = Option<Monetary>>,
+    MA: Average,
+    STRETCH: Average,
 {
-    type Output = Option<Monetary>;
-
-    fn initialize(value: Monetary) -> Self {
+    /// Starts from the given averages, whose periods may be chosen at runtime.
+    pub(super) fn with(up: MA, down: MA, change: STRETCH, value: Monetary) -> Self {
         StretchedRSI {
-            up: MA::initialize(value),
-            down: MA::initialize(value),
+            up,
+            down,
             previous_value: value,
-            change: STRETCH::initialize(0.0)
+            change,
         }
     }
 
-    fn evaluate(&mut self, value: Monetary) -> Self::Output {
-        if let Some(change) = self.change.evaluate(value - self.previous_value) {
+    pub(super) fn update(&mut self, value: Monetary) -> Option<Monetary> {
+        if let Some(change) = self.change.average(value - self.previous_value) {
             self.previous_value = value;
             if let (Some(up), Some(down)) = (
-                self.up.evaluate(if change > 0.0 { change } else { 0.0 }),
-                self.down.evaluate(if change < 0.0 { -change } else { 0.0 }),
+                self.up.average(if change > 0.0 { change } else { 0.0 }),
+                self.down.average(if change < 0.0 { -change } else { 0.0 }),
             ) {
                 let rs = up / down;
                 Some(100.0 - 100.0 / (1.0 + rs))
@@ -45,3 +46,19 @@ where
         }
     }
 }
+
+impl<MA, STRETCH> Indicator for StretchedRSI<MA, STRETCH>
+where
+    MA: MovingAverage<Output = Option<Monetary>>,
+    STRETCH: MovingAverage<Output = Option<Monetary>>,
+{
+    type Output = Option<Monetary>;
+
+    fn initialize(value: Monetary) -> Self {
+        StretchedRSI::with(MA::initialize(value), MA::initialize(value), STRETCH::initialize(0.0), value)
+    }
+
+    fn evaluate(&mut self, value: Monetary) -> Self::Output {
+        self.update(value)
+    }
+}
@@ -1,6 +1,8 @@
 use super::Indicator;
 use crate::economy::Monetary;
+use serde::{Serialize, Deserialize};
 
+#[derive(Serialize, Deserialize)]
 pub struct Value;
 
 impl Indicator for Value {
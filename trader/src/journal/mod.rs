@@ -0,0 +1,129 @@
+mod sinks;
+
+pub use sinks::{StdoutSink, FileSink, PostgresSink, DecisionSink};
+
+use crate::economy::Monetary;
+use crate::traders::{Action, Note, Order};
+use async_trait::async_trait;
+use binance_async::model::Side;
+use serde::Serialize;
+use serde_json::Value as State;
+
+/// What happened, the typed part of a record.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Entry {
+    /// A strategy wants to trade, before sizing and filters.
+    Signal { action: Action, fraction: Monetary, price: Monetary },
+    Submitted { action: Action, quantity: Monetary, price: Monetary },
+    /// An order was dropped by the market's filters or the risk limits.
+    Rejected { action: Action, quantity: Monetary, price: Monetary, reason: String },
+    Fill { side: Side, quantity: Monetary, price: Monetary, fee: Monetary },
+    Balance { asset: String, balance: Monetary },
+    /// Value of the whole portfolio, in the reference asset.
+    Valuation { total: Monetary },
+    Holding { asset: String, balance: Monetary, value: Monetary },
+    /// Value and profit of a sleeve, in the reference asset.
+    Equity { equity: Monetary, pnl: Monetary },
+    /// Summary of a finished run, in the reference asset.
+    Report { initial: Monetary, total: Monetary, profit: Monetary, fills: usize },
+    /// Settings the run was started with, so it can be reproduced.
+    Settings { settings: State },
+    Alert { message: String },
+    Notice { message: String },
+    /// Audit of a strategy's decision: what its indicators said, what its wrappers did,
+    /// and what became of the order after sizing, filters and risk checks.
+    Decision {
+        indicators: State,
+        notes: Vec<Note>,
+        signal: Option<Order>,
+        order: Option<Order>,
+        rejection: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Record {
+    pub timestamp: i64,
+    pub market: Option<String>,
+    /// Name of the sleeve the record belongs to.
+    pub strategy: Option<String>,
+    #[serde(flatten)]
+    pub entry: Entry,
+}
+
+impl Record {
+    pub fn new(timestamp: i64, market: Option<&str>, strategy: Option<&str>, entry: Entry) -> Record {
+        Record {
+            timestamp,
+            market: market.map(String::from),
+            strategy: strategy.map(String::from),
+            entry,
+        }
+    }
+}
+
+/// Destination for records, written to in batches.
+#[async_trait]
+pub trait Sink {
+    async fn write(&mut self, records: &[Record]);
+}
+
+/// Collects the records of an event and hands them to all sinks once the event is handled.
+///
+/// Every record is also emitted as a `tracing` event within the current span,
+/// so a subscriber can be installed instead of or in addition to the sinks.
+pub struct Journal {
+    sinks: Vec<Box<dyn Sink + Send>>,
+    buffer: Vec<Record>,
+}
+
+impl Journal {
+    pub fn new() -> Journal {
+        Journal {
+            sinks: Vec::new(),
+            buffer: Vec::new(),
+        }
+    }
+
+    pub fn add_sink<K>(&mut self, sink: K)
+    where
+        K: Sink + Send + 'static,
+    {
+        self.sinks.push(Box::new(sink));
+    }
+
+    pub fn record(&mut self, record: Record) {
+        tracing::info!(
+            timestamp = record.timestamp,
+            market = record.market.as_deref().unwrap_or(""),
+            strategy = record.strategy.as_deref().unwrap_or(""),
+            "{}",
+            serde_json::to_string(&record.entry).unwrap()
+        );
+        self.buffer.push(record);
+    }
+
+    pub async fn flush(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        for sink in &mut self.sinks {
+            sink.write(&self.buffer).await;
+        }
+        self.buffer.clear();
+    }
+}
+
+#[tokio::test]
+async fn test_record() {
+    let record = Record::new(60, Some("BTCUSDT"), Some("rsi"), Entry::Submitted {
+        action: Action::Buy,
+        quantity: 0.5,
+        price: 10000.0,
+    });
+    assert_eq!(
+        serde_json::to_string(&record).unwrap(),
+        r#"{"timestamp":60,"market":"BTCUSDT","strategy":"rsi","type":"submitted","action":"Buy","quantity":0.5,"price":10000.0}"#
+    );
+}
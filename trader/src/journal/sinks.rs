@@ -0,0 +1,110 @@
+use super::{Entry, Record, Sink};
+use async_trait::async_trait;
+use sqlx::PgPool;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Prints one JSON object per line.
+pub struct StdoutSink;
+
+#[async_trait]
+impl Sink for StdoutSink {
+    async fn write(&mut self, records: &[Record]) {
+        for record in records {
+            println!("{}", serde_json::to_string(record).unwrap());
+        }
+    }
+}
+
+/// Appends one JSON object per line to a file.
+pub struct FileSink {
+    file: File,
+}
+
+impl FileSink {
+    pub fn new(path: &Path) -> io::Result<FileSink> {
+        Ok(FileSink {
+            file: OpenOptions::new().create(true).append(true).open(path)?,
+        })
+    }
+}
+
+#[async_trait]
+impl Sink for FileSink {
+    async fn write(&mut self, records: &[Record]) {
+        for record in records {
+            if let Err(error) = writeln!(self.file, "{}", serde_json::to_string(record).unwrap()) {
+                tracing::warn!(%error, "failed to write record");
+            }
+        }
+    }
+}
+
+/// Inserts each record as JSON into the `messages` table.
+pub struct PostgresSink {
+    pool: PgPool,
+}
+
+impl PostgresSink {
+    pub async fn new(url: &str) -> PostgresSink {
+        PostgresSink {
+            pool: PgPool::new(url).await.unwrap(),
+        }
+    }
+}
+
+/// Inserts the audited decisions into the `decisions` table and ignores all other records.
+pub struct DecisionSink {
+    pool: PgPool,
+}
+
+impl DecisionSink {
+    pub async fn new(url: &str) -> DecisionSink {
+        DecisionSink {
+            pool: PgPool::new(url).await.unwrap(),
+        }
+    }
+}
+
+#[async_trait]
+impl Sink for DecisionSink {
+    async fn write(&mut self, records: &[Record]) {
+        for record in records {
+            if let Entry::Decision { indicators, notes, signal, order, rejection } = &record.entry {
+                let inserted = sqlx::query("
+                        INSERT INTO decisions (timestamp, market, strategy, indicators, notes, signal, filtered, rejection)
+                        VALUES ($1, $2, $3, $4::JSONB, $5::JSONB, $6::JSONB, $7::JSONB, $8)
+                    ")
+                    .bind(record.timestamp)
+                    .bind(record.market.clone().unwrap_or_default())
+                    .bind(record.strategy.clone().unwrap_or_default())
+                    .bind(indicators.to_string())
+                    .bind(serde_json::to_string(notes).unwrap())
+                    .bind(serde_json::to_string(signal).unwrap())
+                    .bind(serde_json::to_string(order).unwrap())
+                    .bind(rejection.clone())
+                    .execute(&self.pool)
+                    .await;
+                if let Err(error) = inserted {
+                    tracing::warn!(%error, "failed to insert decision");
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Sink for PostgresSink {
+    async fn write(&mut self, records: &[Record]) {
+        for record in records {
+            let inserted = sqlx::query("INSERT INTO messages (message) VALUES ($1)")
+                .bind(serde_json::to_string(record).unwrap())
+                .execute(&self.pool)
+                .await;
+            if let Err(error) = inserted {
+                tracing::warn!(%error, "failed to insert record");
+            }
+        }
+    }
+}
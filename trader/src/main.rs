@@ -1,17 +1,32 @@
 #![allow(incomplete_features)]
 #![feature(const_generics)]
+#![feature(total_cmp)]
 
 mod economy;
 mod environments;
 mod indicators;
+mod journal;
+mod metrics;
+mod portfolio;
+mod sizers;
 mod traders;
+mod universe;
 
-use economy::{Economy, Monetary};
-use environments::Simulated;
-use traders::{Trader, RSITrader, MACDTrader, Order, Action, Backoff, StopLoss, FullStop, GobbleBadLongterm, And, SafeAnd};
+use economy::{Economy, Monetary, RiskLimits, Sleeve};
+use environments::{Environment, Backtest, Live, Simulated, SimulationConfig, DataSource, FileSource, PostgresSource, FillModel, Queue, Slippage};
+use binance_async::{Binance, BinanceWebsocket};
+use traders::{Trader, RSITrader, MACDTrader, Order, Action, Backoff, StopLoss, FullStop, GobbleBadLongterm, And, SafeAnd, StrategyConfig};
+use journal::{StdoutSink, FileSink, PostgresSink, DecisionSink};
+use metrics::Metrics;
 use indicators::{Indicator, Value, StretchedRSI, SMA, SMMA, EMA, MACD, MACDHistogram};
+use sizers::{FixedFractional, VolatilityTarget, FixedRisk, Kelly};
+use universe::{Trading, MinTrades, MinQuoteVolume, MaxSpread, Whitelist, Blacklist, QuoteAssets, OrderTypes, TopByVolume, ConnectedTo};
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use std::path::PathBuf;
 
 
+#[derive(Serialize, Deserialize)]
 pub struct CustomTrader {
     previous_rsi: Monetary,
 }
@@ -52,11 +67,236 @@ impl Trader for CustomTrader {
 
 //type MyTrader = StopLoss<Backoff<GobbleBadLongterm<RSITrader<4200, 300, 30.0, 70.0, 0.05>, "USDT">, 60>, "USDT", 0.97, 300>;
 type MyTrader = StopLoss<FullStop<Backoff<RSITrader<4200, 300, 20.0, 80.0, 0.05>, 60>, "USDT">, "USDT", 0.95, 300>;
+type MySizer = FixedFractional;
 
 #[tokio::main]
 async fn main() -> Result<(), ()> {
-    let environment = Simulated::new().await;
-    let mut economy = Economy::<_, MyTrader>::new(environment);
+    dotenv::dotenv().ok();
+    tracing::subscriber::set_global_default(tracing_subscriber::FmtSubscriber::new()).unwrap();
+    let reference = std::env::var("REFERENCE_ASSET").unwrap_or_else(|_| String::from("USDT"));
+
+    let selector = (
+        Trading,
+        OrderTypes(vec![String::from("LIMIT")]),
+        MinTrades(24 * 60),
+        ConnectedTo(reference.clone()),
+    );
+    let config = simulation_config(&reference)?;
+    if let (Some(start), Some(end)) = (config.start, config.end) {
+        let exchange_info = PathBuf::from(std::env::var("EXCHANGE_INFO").unwrap());
+        let source: Box<dyn DataSource + Send> = if let Ok(path) = std::env::var("BACKTEST_DATA") {
+            let source = FileSource::load(&PathBuf::from(path)).unwrap();
+            if let Ok(path) = std::env::var("BACKTEST_CONVERT") {
+                source.save(&PathBuf::from(path)).unwrap();
+            }
+            Box::new(source)
+        } else {
+            Box::new(PostgresSource::new(&std::env::var("DATABASE_URL").unwrap()).await)
+        };
+        let mut environment = match Backtest::new(selector, source, start, end, &exchange_info) {
+            Ok(environment) => environment,
+            Err(error) => {
+                tracing::error!(%error, "invalid exchange info");
+                return Err(());
+            }
+        };
+        if let Ok(path) = std::env::var("PRICE_STATS") {
+            if let Err(error) = environment.set_stats(&PathBuf::from(path)) {
+                tracing::error!(%error, "invalid price stats");
+                return Err(());
+            }
+        }
+        for (asset, balance) in &config.balances {
+            environment.set_balance(asset, *balance);
+        }
+        environment.set_evaluation_interval(config.evaluation_interval);
+        environment.set_speed(config.speed);
+        environment.set_fill_model(FillModel::with_config(config.fills.clone()));
+        trade(environment, &reference, config.evaluation_interval, Some(&config)).await
+    } else if std::env::var("LIVE_TRADING").map(|live| live == "true").unwrap_or(false) {
+        let mut binance = Binance::with_credential(
+            &std::env::var("BINANCE_API_KEY").unwrap(),
+            &std::env::var("BINANCE_SECRET_KEY").unwrap(),
+        );
+        if let Ok(url) = std::env::var("BINANCE_API_URL") {
+            binance.transport.set_base(&url);
+        }
+        let socket = match std::env::var("BINANCE_STREAM_URL") {
+            Ok(url) => BinanceWebsocket::with_base(&url),
+            Err(_) => BinanceWebsocket::default(),
+        };
+        let mut environment = Live::new(selector, binance, socket);
+        if let Ok(interval) = std::env::var("RECONCILE_INTERVAL") {
+            environment.set_reconcile_interval(interval.parse().unwrap());
+        }
+        if let Ok(url) = std::env::var("DATABASE_URL") {
+            environment.set_pool(sqlx::PgPool::new(&url).await.unwrap());
+        }
+        environment.set_evaluation_interval(config.evaluation_interval);
+        trade(environment, &reference, config.evaluation_interval, None).await
+    } else {
+        let mut environment = Simulated::new(selector).await;
+        if let Some(start) = config.start {
+            environment.set_start(start);
+        }
+        for (asset, balance) in &config.balances {
+            environment.set_balance(asset, *balance);
+        }
+        environment.set_evaluation_interval(config.evaluation_interval);
+        environment.set_switchover(config.realtime);
+        environment.set_speed(config.speed);
+        environment.set_fill_model(FillModel::with_config(config.fills.clone()));
+        trade(environment, &reference, config.evaluation_interval, Some(&config)).await
+    }
+}
+
+/// Reads the simulation config file, if there is one, and overrides its settings with the environment variables.
+/// Without initial balances, simulations start with 200 of the reference asset.
+fn simulation_config(reference: &str) -> Result<SimulationConfig, ()> {
+    let mut config = match std::env::var("SIMULATION_CONFIG") {
+        Ok(path) => match SimulationConfig::load(&PathBuf::from(path)) {
+            Ok(config) => config,
+            Err(error) => {
+                tracing::error!(%error, "invalid config");
+                return Err(());
+            }
+        },
+        Err(_) => SimulationConfig::default(),
+    };
+    if config.balances.is_empty() {
+        config.balances.insert(String::from(reference), 200.0);
+    }
+    if let Ok(start) = std::env::var("BACKTEST_START") {
+        config.start = Some(start.parse().unwrap());
+    }
+    if let Ok(end) = std::env::var("BACKTEST_END") {
+        config.end = Some(end.parse().unwrap());
+    }
+    if let Ok(interval) = std::env::var("EVALUATION_INTERVAL") {
+        config.evaluation_interval = interval.parse().unwrap();
+    }
+    if let Ok(speed) = std::env::var("REPLAY_SPEED") {
+        config.speed = if speed == "max" { None } else { Some(speed.parse().unwrap()) };
+    }
+
+    let fills = &mut config.fills;
+    if let Ok(latency) = std::env::var("FILL_LATENCY") {
+        fills.latency = latency.parse().unwrap();
+    }
+    if let Ok(queue) = std::env::var("FILL_QUEUE") {
+        fills.queue = match queue.as_str() {
+            "front" => Queue::Front,
+            "back" => Queue::Back,
+            volume => Queue::Behind(volume.parse().unwrap()),
+        };
+    }
+    if let Ok(participation) = std::env::var("FILL_PARTICIPATION") {
+        fills.participation = participation.parse().unwrap();
+    }
+    if let Ok(expiry) = std::env::var("ORDER_EXPIRY") {
+        fills.expiry = Some(expiry.parse().unwrap());
+    }
+    if let (Ok(maker), Ok(taker)) = (std::env::var("MAKER_FEE"), std::env::var("TAKER_FEE")) {
+        fills.maker_fee = maker.parse().unwrap();
+        fills.taker_fee = taker.parse().unwrap();
+    }
+    if let Ok(fixed) = std::env::var("SLIPPAGE") {
+        fills.slippage = Slippage {
+            fixed: fixed.parse().unwrap(),
+            impact: std::env::var("SLIPPAGE_IMPACT").map(|impact| impact.parse().unwrap()).unwrap_or(0.0),
+        };
+    }
+
+    if let Err(error) = config.validate() {
+        tracing::error!(%error, "invalid simulation config");
+        return Err(());
+    }
+    Ok(config)
+}
+
+async fn trade<E>(environment: E, reference: &str, interval: i64, config: Option<&SimulationConfig>) -> Result<(), ()>
+where
+    E: Environment,
+{
+    let mut economy = Economy::<_, MySizer>::new(environment, reference);
+    let mut strategy = None;
+    if let Ok(path) = std::env::var("STRATEGY_CONFIG") {
+        let config = match StrategyConfig::load(&PathBuf::from(path)) {
+            Ok(config) => config,
+            Err(error) => {
+                tracing::error!(%error, "invalid config");
+                return Err(());
+            }
+        };
+        for sleeve in &config.sleeves {
+            economy.add_sleeve(sleeve.build());
+        }
+        strategy = Some(config);
+    } else {
+        economy.add_sleeve(Sleeve::new::<MyTrader>("rsi", 1.0, &[]));
+    }
+    economy.set_bar_interval(interval);
+    if let Some(config) = config {
+        economy.set_settings(json!({
+            "reference": reference,
+            "simulation": config,
+            "strategy": strategy,
+        }));
+    }
+    economy.set_risk_limits(RiskLimits {
+        max_drawdown: Some(0.25),
+        max_orders_per_minute: Some(60),
+        ..RiskLimits::default()
+    });
+    if let Ok(path) = std::env::var("CHECKPOINT_PATH") {
+        let interval = std::env::var("CHECKPOINT_INTERVAL")
+            .map(|interval| interval.parse().unwrap())
+            .unwrap_or(3600);
+        economy.set_checkpoint(PathBuf::from(path), interval);
+    }
+    if std::env::var("RESUME_TRADING").map_or(false, |resume| resume == "true") {
+        economy.resume_trading();
+    }
+    if let Ok(interval) = std::env::var("UNIVERSE_INTERVAL") {
+        economy.set_universe_interval(interval.parse().unwrap());
+    }
+    if let Ok(margin) = std::env::var("ARBITRAGE_MARGIN") {
+        let fraction = std::env::var("ARBITRAGE_FRACTION")
+            .ok()
+            .map(|fraction| fraction.parse().unwrap());
+        economy.set_arbitrage(margin.parse().unwrap(), fraction);
+        if let Ok(timeout) = std::env::var("ARBITRAGE_TIMEOUT") {
+            economy.set_arbitrage_timeout(timeout.parse().unwrap());
+        }
+    }
+    if let Ok(duration) = std::env::var("WARM_UP") {
+        economy.set_warm_up(duration.parse().unwrap());
+    }
+    let sinks = std::env::var("LOG_SINKS").unwrap_or_else(|_| String::from("stdout"));
+    for sink in sinks.split(',').map(str::trim) {
+        if sink == "stdout" {
+            economy.add_sink(StdoutSink);
+        } else if sink == "postgres" {
+            economy.add_sink(PostgresSink::new(&std::env::var("DATABASE_URL").unwrap()).await);
+        } else if let Some(path) = sink.strip_prefix("file:") {
+            economy.add_sink(FileSink::new(&PathBuf::from(path)).unwrap());
+        } else {
+            tracing::error!(sink, "unknown log sink");
+            return Err(());
+        }
+    }
+    if let Ok(interval) = std::env::var("VALUATION_INTERVAL") {
+        economy.set_valuation_interval(interval.parse().unwrap());
+    }
+    if std::env::var("AUDIT_DECISIONS").map_or(false, |audit| audit == "true") {
+        economy.enable_audit();
+        economy.add_sink(DecisionSink::new(&std::env::var("DATABASE_URL").unwrap()).await);
+    }
+    if let Ok(address) = std::env::var("METRICS_ADDRESS") {
+        let metrics = Metrics::new();
+        tokio::spawn(metrics::serve(metrics.clone(), address.parse().unwrap()));
+        economy.set_metrics(metrics);
+    }
     economy.run().await?;
 
     Ok(())
@@ -0,0 +1,13 @@
+use super::Metrics;
+use std::net::SocketAddr;
+use warp::Filter;
+
+/// Serves the metrics at `GET /metrics` until the process exits.
+pub async fn serve(metrics: Metrics, address: SocketAddr) {
+    let route = warp::get()
+        .and(warp::path("metrics"))
+        .and(warp::path::end())
+        .map(move || warp::reply::with_header(metrics.render(), "content-type", "text/plain; version=0.0.4"));
+
+    warp::serve(route).run(address).await;
+}
@@ -0,0 +1,191 @@
+mod exporter;
+
+pub use exporter::serve;
+
+use crate::economy::Monetary;
+use crate::journal::{Entry, Record, Sink};
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+/// Upper bounds of the evaluation latency buckets, in seconds.
+const LATENCY_BUCKETS: [f64; 8] = [0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
+
+#[derive(Default)]
+struct Registry {
+    portfolio_value: Option<Monetary>,
+    balances: BTreeMap<String, Monetary>,
+    submitted: BTreeMap<String, u64>,
+    filled: BTreeMap<String, u64>,
+    rejected: BTreeMap<(String, String), u64>,
+    latency_buckets: [u64; LATENCY_BUCKETS.len()],
+    latency_sum: f64,
+    latency_count: u64,
+    websocket_lag: Option<f64>,
+    api_weight: Option<u32>,
+    queue_depth: Option<usize>,
+}
+
+/// Runtime metrics of the trader, rendered in the Prometheus text format.
+///
+/// Order counts and balances are taken from the journal, so the metrics are added as one of its sinks.
+/// Clones share the same registry.
+#[derive(Clone, Default)]
+pub struct Metrics {
+    registry: Arc<Mutex<Registry>>,
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        Metrics::default()
+    }
+
+    pub fn set_portfolio_value(&self, value: Monetary) {
+        self.registry.lock().unwrap().portfolio_value = Some(value);
+    }
+
+    pub fn observe_evaluation(&self, seconds: f64) {
+        let mut registry = self.registry.lock().unwrap();
+        for (bucket, bound) in LATENCY_BUCKETS.iter().enumerate() {
+            if seconds <= *bound {
+                registry.latency_buckets[bucket] += 1;
+            }
+        }
+        registry.latency_sum += seconds;
+        registry.latency_count += 1;
+    }
+
+    /// Time between the exchange sending a websocket message and the trader receiving it.
+    pub fn set_websocket_lag(&self, seconds: f64) {
+        self.registry.lock().unwrap().websocket_lag = Some(seconds);
+    }
+
+    pub fn set_api_weight(&self, weight: u32) {
+        self.registry.lock().unwrap().api_weight = Some(weight);
+    }
+
+    /// Number of events the environment has buffered but not yet handed to the economy.
+    pub fn set_queue_depth(&self, depth: usize) {
+        self.registry.lock().unwrap().queue_depth = Some(depth);
+    }
+
+    pub fn render(&self) -> String {
+        let registry = self.registry.lock().unwrap();
+        let mut output = String::new();
+
+        header(&mut output, "trader_portfolio_value", "gauge", "Value of all balances in the reference asset.");
+        if let Some(value) = registry.portfolio_value {
+            writeln!(output, "trader_portfolio_value {}", value).unwrap();
+        }
+
+        header(&mut output, "trader_balance", "gauge", "Balance of each asset.");
+        for (asset, balance) in &registry.balances {
+            writeln!(output, "trader_balance{{asset=\"{}\"}} {}", escape(asset), balance).unwrap();
+        }
+
+        header(&mut output, "trader_orders_submitted_total", "counter", "Orders placed with the environment.");
+        for (market, count) in &registry.submitted {
+            writeln!(output, "trader_orders_submitted_total{{market=\"{}\"}} {}", escape(market), count).unwrap();
+        }
+
+        header(&mut output, "trader_orders_filled_total", "counter", "Orders that were filled, fully or partially.");
+        for (market, count) in &registry.filled {
+            writeln!(output, "trader_orders_filled_total{{market=\"{}\"}} {}", escape(market), count).unwrap();
+        }
+
+        header(&mut output, "trader_orders_rejected_total", "counter", "Orders dropped by the market's filters or the risk limits.");
+        for ((market, reason), count) in &registry.rejected {
+            writeln!(
+                output,
+                "trader_orders_rejected_total{{market=\"{}\",reason=\"{}\"}} {}",
+                escape(market),
+                escape(reason),
+                count
+            ).unwrap();
+        }
+
+        header(&mut output, "trader_evaluation_seconds", "histogram", "Duration of an evaluation of all strategies.");
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(registry.latency_buckets.iter()) {
+            writeln!(output, "trader_evaluation_seconds_bucket{{le=\"{}\"}} {}", bound, count).unwrap();
+        }
+        writeln!(output, "trader_evaluation_seconds_bucket{{le=\"+Inf\"}} {}", registry.latency_count).unwrap();
+        writeln!(output, "trader_evaluation_seconds_sum {}", registry.latency_sum).unwrap();
+        writeln!(output, "trader_evaluation_seconds_count {}", registry.latency_count).unwrap();
+
+        header(&mut output, "trader_websocket_lag_seconds", "gauge", "Delay of the last websocket message.");
+        if let Some(lag) = registry.websocket_lag {
+            writeln!(output, "trader_websocket_lag_seconds {}", lag).unwrap();
+        }
+
+        header(&mut output, "trader_api_weight", "gauge", "Request weight used in the current minute.");
+        if let Some(weight) = registry.api_weight {
+            writeln!(output, "trader_api_weight {}", weight).unwrap();
+        }
+
+        header(&mut output, "trader_event_queue_depth", "gauge", "Events buffered by the environment.");
+        if let Some(depth) = registry.queue_depth {
+            writeln!(output, "trader_event_queue_depth {}", depth).unwrap();
+        }
+
+        output
+    }
+}
+
+#[async_trait]
+impl Sink for Metrics {
+    async fn write(&mut self, records: &[Record]) {
+        let mut registry = self.registry.lock().unwrap();
+        for record in records {
+            let market = record.market.clone().unwrap_or_default();
+            match &record.entry {
+                Entry::Submitted { .. } => *registry.submitted.entry(market).or_insert(0) += 1,
+                Entry::Fill { .. } => *registry.filled.entry(market).or_insert(0) += 1,
+                Entry::Rejected { reason, .. } => {
+                    // Only the kind of rejection, so the limit values don't end up as labels.
+                    let reason = reason.split('(').next().unwrap_or_default().to_string();
+                    *registry.rejected.entry((market, reason)).or_insert(0) += 1;
+                },
+                Entry::Balance { asset, balance } => {
+                    registry.balances.insert(asset.clone(), *balance);
+                },
+                Entry::Valuation { total } => registry.portfolio_value = Some(*total),
+                _ => {},
+            }
+        }
+    }
+}
+
+fn header(output: &mut String, name: &str, kind: &str, help: &str) {
+    writeln!(output, "# HELP {} {}", name, help).unwrap();
+    writeln!(output, "# TYPE {} {}", name, kind).unwrap();
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[tokio::test]
+async fn test_render() {
+    use crate::traders::Action;
+
+    let mut metrics = Metrics::new();
+    metrics.write(&[
+        Record::new(0, Some("BTCUSDT"), Some("rsi"), Entry::Submitted { action: Action::Buy, quantity: 1.0, price: 1.0 }),
+        Record::new(0, Some("BTCUSDT"), Some("rsi"), Entry::Rejected {
+            action: Action::Buy,
+            quantity: 1.0,
+            price: 1.0,
+            reason: String::from("OrderRate(60)"),
+        }),
+        Record::new(0, None, None, Entry::Balance { asset: String::from("USDT"), balance: 100.0 }),
+    ]).await;
+    metrics.observe_evaluation(0.02);
+
+    let output = metrics.render();
+    assert!(output.contains("trader_orders_submitted_total{market=\"BTCUSDT\"} 1\n"));
+    assert!(output.contains("trader_orders_rejected_total{market=\"BTCUSDT\",reason=\"OrderRate\"} 1\n"));
+    assert!(output.contains("trader_balance{asset=\"USDT\"} 100\n"));
+    assert!(output.contains("trader_evaluation_seconds_bucket{le=\"0.01\"} 0\n"));
+    assert!(output.contains("trader_evaluation_seconds_bucket{le=\"0.05\"} 1\n"));
+}
@@ -0,0 +1,30 @@
+use super::{Decision, PortfolioStrategy, PortfolioView};
+use crate::economy::Monetary;
+
+/// Rebalances to constant weights every `interval` seconds, e.g. 40% BTC and 30% ETH with the rest in the reference asset.
+pub struct FixedWeights {
+    weights: Vec<(String, Monetary)>,
+    interval: i64,
+}
+
+impl FixedWeights {
+    pub fn new(weights: &[(&str, Monetary)], interval: i64) -> FixedWeights {
+        FixedWeights {
+            weights: weights
+                .iter()
+                .map(|(symbol, weight)| (String::from(*symbol), *weight))
+                .collect(),
+            interval,
+        }
+    }
+}
+
+impl PortfolioStrategy for FixedWeights {
+    fn evaluate(&mut self, view: &PortfolioView) -> Option<Decision> {
+        if view.timestamp % self.interval == 0 {
+            Some(Decision::Weights(self.weights.clone()))
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,96 @@
+mod fixed_weights;
+mod momentum_rotation;
+
+pub use fixed_weights::FixedWeights;
+pub use momentum_rotation::MomentumRotation;
+
+use crate::economy::Monetary;
+use crate::traders::Order;
+use serde_json::Value as State;
+
+/// A market as seen by a portfolio strategy.
+pub struct MarketView<'a> {
+    pub symbol: &'a str,
+    pub base: &'a str,
+    pub quote: &'a str,
+    pub value: Option<Monetary>,
+}
+
+/// An asset as seen by a portfolio strategy, with the balance held by the strategy's sleeve.
+pub struct AssetView<'a> {
+    pub symbol: &'a str,
+    pub balance: Monetary,
+    /// Value of one unit, in the reference asset.
+    pub value: Monetary,
+}
+
+/// Everything a portfolio strategy may base its decision on.
+pub struct PortfolioView<'a> {
+    pub timestamp: i64,
+    pub reference: &'a str,
+    pub markets: Vec<MarketView<'a>>,
+    pub assets: Vec<AssetView<'a>>,
+    /// Value of all balances of the sleeve, in the reference asset.
+    pub equity: Monetary,
+}
+
+impl<'a> PortfolioView<'a> {
+    pub fn get_market(&self, symbol: &str) -> Option<&MarketView<'a>> {
+        self.markets.iter().find(|market| market.symbol == symbol)
+    }
+
+    /// Fraction of the equity held in the given asset.
+    pub fn weight(&self, symbol: &str) -> Monetary {
+        if self.equity <= 0.0 {
+            return 0.0;
+        }
+        self.assets
+            .iter()
+            .find(|asset| asset.symbol == symbol)
+            .map_or(0.0, |asset| asset.balance * asset.value / self.equity)
+    }
+}
+
+pub enum Decision {
+    /// Orders by market symbol. Unlike the orders of traders, the quantity is in the base asset.
+    Orders(Vec<(String, Order)>),
+    /// Fractions of the equity by asset symbol, the rest is held in the reference asset.
+    Weights(Vec<(String, Monetary)>),
+}
+
+/// A strategy that trades across all markets at once, e.g. rebalancing or rotation strategies.
+/// It runs in its own sleeve and only sees that sleeve's balances.
+pub trait PortfolioStrategy {
+    fn evaluate(&mut self, view: &PortfolioView) -> Option<Decision>;
+
+    fn save(&self) -> Result<State, serde_json::Error> {
+        Ok(State::Null)
+    }
+
+    fn restore(&mut self, _state: State) -> Result<(), serde_json::Error> {
+        Ok(())
+    }
+}
+
+/// Change in value each asset needs to reach its target weight, in the reference asset.
+/// Deviations smaller than `tolerance` times the equity are left alone, so small drifts don't cause orders.
+pub fn rebalance(values: &[Monetary], weights: &[Monetary], equity: Monetary, tolerance: Monetary) -> Vec<Monetary> {
+    values
+        .iter()
+        .zip(weights)
+        .map(|(value, weight)| {
+            let change = weight * equity - value;
+            if change.abs() > tolerance * equity {
+                change
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_rebalance() {
+    let changes = rebalance(&[50.0, 30.0, 20.0], &[0.0, 0.5, 0.205], 100.0, 0.01);
+    assert_eq!(changes, vec![-50.0, 20.0, 0.0]);
+}
@@ -0,0 +1,73 @@
+use super::{Decision, PortfolioStrategy, PortfolioView};
+use crate::economy::Monetary;
+use serde::{Serialize, Deserialize};
+use serde_json::Value as State;
+use std::collections::{HashMap, VecDeque};
+
+/// Holds the `top` assets with the highest return over the last `lookback` intervals, in equal parts.
+/// Only markets quoted in the reference asset are considered, and assets with a negative return are never held.
+#[derive(Serialize, Deserialize)]
+pub struct MomentumRotation {
+    top: usize,
+    lookback: usize,
+    interval: i64,
+    history: HashMap<String, VecDeque<Monetary>>,
+}
+
+impl MomentumRotation {
+    pub fn new(top: usize, lookback: usize, interval: i64) -> MomentumRotation {
+        MomentumRotation {
+            top,
+            lookback,
+            interval,
+            history: HashMap::new(),
+        }
+    }
+}
+
+impl PortfolioStrategy for MomentumRotation {
+    fn evaluate(&mut self, view: &PortfolioView) -> Option<Decision> {
+        if view.timestamp % self.interval != 0 {
+            return None;
+        }
+
+        let mut returns = Vec::new();
+        for market in &view.markets {
+            let value = match market.value {
+                Some(value) if market.quote == view.reference => value,
+                _ => continue,
+            };
+            let history = self.history.entry(String::from(market.base)).or_insert_with(VecDeque::new);
+            history.push_back(value);
+            if history.len() > self.lookback + 1 {
+                history.pop_front();
+            }
+            if history.len() == self.lookback + 1 {
+                returns.push((market.base, value / history[0] - 1.0));
+            }
+        }
+        if returns.is_empty() {
+            return None;
+        }
+
+        returns.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let weight = 1.0 / self.top as Monetary;
+        Some(Decision::Weights(
+            returns
+                .into_iter()
+                .take(self.top)
+                .filter(|(_, change)| *change > 0.0)
+                .map(|(symbol, _)| (String::from(symbol), weight))
+                .collect()
+        ))
+    }
+
+    fn save(&self) -> Result<State, serde_json::Error> {
+        serde_json::to_value(self)
+    }
+
+    fn restore(&mut self, state: State) -> Result<(), serde_json::Error> {
+        *self = serde_json::from_value(state)?;
+        Ok(())
+    }
+}
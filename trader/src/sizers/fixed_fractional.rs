@@ -0,0 +1,32 @@
+use super::{PositionSizer, Sizing};
+use crate::economy::Monetary;
+use serde::{Serialize, Deserialize};
+
+/// Orders exactly the fraction of the portfolio requested by the trader.
+#[derive(Serialize, Deserialize)]
+pub struct FixedFractional;
+
+impl PositionSizer for FixedFractional {
+    fn initialize() -> FixedFractional {
+        FixedFractional
+    }
+
+    fn size(&self, sizing: &Sizing) -> Monetary {
+        sizing.quantity(sizing.fraction)
+    }
+}
+
+#[tokio::test]
+async fn test_fixed_fractional() {
+    use crate::traders::Action;
+
+    let sizing = Sizing {
+        action: Action::Sell,
+        fraction: 0.1,
+        price: 50.0,
+        total: 1000.0,
+        reference_to_base: 0.02,
+        stop: None,
+    };
+    assert!((FixedFractional::initialize().size(&sizing) - 2.0).abs() < 1e-9);
+}
@@ -0,0 +1,42 @@
+use super::{PositionSizer, Sizing};
+use crate::economy::Monetary;
+use serde::{Serialize, Deserialize};
+
+/// Risks the requested fraction of the portfolio until the strategy's stop, or a stop `STOP` (relative to the price)
+/// away if the strategy doesn't keep one.
+/// A trader asking for 1% with a 5% stop therefore buys a position worth 20% of the portfolio.
+#[derive(Serialize, Deserialize)]
+pub struct FixedRisk<const STOP: Monetary>;
+
+impl<const STOP: Monetary> PositionSizer for FixedRisk<STOP> {
+    fn initialize() -> FixedRisk<STOP> {
+        FixedRisk
+    }
+
+    fn size(&self, sizing: &Sizing) -> Monetary {
+        let stop = sizing.stop.filter(|stop| *stop > 0.0).unwrap_or(STOP);
+        if stop > 0.0 {
+            sizing.quantity(sizing.fraction / stop)
+        } else {
+            0.0
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_fixed_risk() {
+    use crate::traders::Action;
+
+    let sizer = FixedRisk::<0.05>::initialize();
+    let mut sizing = Sizing {
+        action: Action::Buy,
+        fraction: 0.01,
+        price: 1.0,
+        total: 100.0,
+        reference_to_base: 1.0,
+        stop: None,
+    };
+    assert!((sizer.size(&sizing) - 20.0).abs() < 1e-9);
+    sizing.stop = Some(0.1);
+    assert!((sizer.size(&sizing) - 10.0).abs() < 1e-9);
+}
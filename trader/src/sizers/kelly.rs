@@ -0,0 +1,39 @@
+use super::{PositionSizer, Sizing};
+use crate::economy::Monetary;
+use serde::{Serialize, Deserialize};
+
+/// Kelly criterion for a strategy winning with probability `WIN` and an average win to loss ratio of `PAYOFF`.
+/// The trader's requested fraction scales the Kelly fraction, e.g. to half Kelly with 0.5, and the result is capped at `CAP`.
+#[derive(Serialize, Deserialize)]
+pub struct Kelly<const WIN: Monetary, const PAYOFF: Monetary, const CAP: Monetary>;
+
+impl<const WIN: Monetary, const PAYOFF: Monetary, const CAP: Monetary> PositionSizer for Kelly<WIN, PAYOFF, CAP> {
+    fn initialize() -> Kelly<WIN, PAYOFF, CAP> {
+        Kelly
+    }
+
+    fn size(&self, sizing: &Sizing) -> Monetary {
+        let kelly = WIN - (1.0 - WIN) / PAYOFF;
+        sizing.quantity((kelly.max(0.0) * sizing.fraction).min(CAP))
+    }
+}
+
+#[tokio::test]
+async fn test_kelly() {
+    use crate::traders::Action;
+
+    let mut sizing = Sizing {
+        action: Action::Buy,
+        fraction: 1.0,
+        price: 1.0,
+        total: 100.0,
+        reference_to_base: 1.0,
+        stop: None,
+    };
+    // A Kelly fraction of 0.6 - 0.4 / 2 = 0.4.
+    assert!((Kelly::<0.6, 2.0, 1.0>::initialize().size(&sizing) - 40.0).abs() < 1e-9);
+    assert!((Kelly::<0.6, 2.0, 0.25>::initialize().size(&sizing) - 25.0).abs() < 1e-9);
+    sizing.fraction = 0.5;
+    assert!((Kelly::<0.6, 2.0, 1.0>::initialize().size(&sizing) - 20.0).abs() < 1e-9);
+    assert_eq!(Kelly::<0.3, 1.0, 1.0>::initialize().size(&sizing), 0.0);
+}
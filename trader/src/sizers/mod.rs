@@ -0,0 +1,48 @@
+mod fixed_fractional;
+mod volatility_target;
+mod fixed_risk;
+mod kelly;
+
+pub use fixed_fractional::FixedFractional;
+pub use volatility_target::VolatilityTarget;
+pub use fixed_risk::FixedRisk;
+pub use kelly::Kelly;
+
+use crate::economy::Monetary;
+use crate::traders::Action;
+
+/// Everything a position sizer may base its decision on.
+#[derive(Debug, Clone)]
+pub struct Sizing {
+    /// Direction of the signal.
+    pub action: Action,
+    /// Fraction of the portfolio the trader intends to move.
+    pub fraction: Monetary,
+    /// Limit price of the order, in the quote asset.
+    pub price: Monetary,
+    /// Total portfolio value, in the reference asset.
+    pub total: Monetary,
+    /// Amount of the base asset obtained for one unit of the reference asset.
+    pub reference_to_base: Monetary,
+    /// Distance from the price to the strategy's stop, relative to the price, if it keeps one.
+    pub stop: Option<Monetary>,
+}
+
+impl Sizing {
+    /// Converts a fraction of the portfolio into a quantity of the base asset.
+    pub fn quantity(&self, fraction: Monetary) -> Monetary {
+        fraction * self.total * self.reference_to_base
+    }
+}
+
+/// Turns a trader's signal into an order quantity.
+/// One sizer is created for each market, next to the market's trader.
+pub trait PositionSizer {
+    fn initialize() -> Self;
+
+    /// Called with the latest market value on every evaluation.
+    fn update(&mut self, _value: Monetary) {}
+
+    /// Returns the base asset quantity to order.
+    fn size(&self, sizing: &Sizing) -> Monetary;
+}
@@ -0,0 +1,68 @@
+use super::{PositionSizer, Sizing};
+use crate::economy::Monetary;
+use crate::indicators::{Indicator, EMA};
+use serde::{Serialize, Deserialize};
+
+/// Scales the requested fraction down if the market is more volatile than the target.
+/// Volatility is the standard deviation of returns over `PERIOD` evaluations.
+#[derive(Serialize, Deserialize)]
+pub struct VolatilityTarget<const PERIOD: usize, const TARGET: Monetary> {
+    variance: EMA<PERIOD>,
+    volatility: Option<Monetary>,
+    previous_value: Option<Monetary>,
+}
+
+impl<const PERIOD: usize, const TARGET: Monetary> PositionSizer for VolatilityTarget<PERIOD, TARGET> {
+    fn initialize() -> VolatilityTarget<PERIOD, TARGET> {
+        VolatilityTarget {
+            variance: EMA::initialize(0.0),
+            volatility: None,
+            previous_value: None,
+        }
+    }
+
+    fn update(&mut self, value: Monetary) {
+        if let Some(previous_value) = self.previous_value {
+            let change = value / previous_value - 1.0;
+            self.volatility = self
+                .variance
+                .evaluate(change * change)
+                .map(|variance| (variance * PERIOD as Monetary).sqrt());
+        }
+        self.previous_value = Some(value);
+    }
+
+    fn size(&self, sizing: &Sizing) -> Monetary {
+        match self.volatility {
+            Some(volatility) if volatility > 0.0 => {
+                sizing.quantity(sizing.fraction * (TARGET / volatility).min(1.0))
+            }
+            // Don't trade until the volatility estimate is warmed up.
+            _ => 0.0,
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_volatility_target() {
+    use crate::traders::Action;
+
+    let mut sizer = VolatilityTarget::<10, 0.01>::initialize();
+    let sizing = Sizing {
+        action: Action::Buy,
+        fraction: 0.5,
+        price: 1.0,
+        total: 100.0,
+        reference_to_base: 1.0,
+        stop: None,
+    };
+    for i in 0..20 {
+        sizer.update(if i % 2 == 0 { 100.0 } else { 101.0 });
+    }
+    let calm = sizer.size(&sizing);
+    for i in 0..20 {
+        sizer.update(if i % 2 == 0 { 100.0 } else { 110.0 });
+    }
+    let volatile = sizer.size(&sizing);
+    assert!(volatile > 0.0 && volatile < calm && calm <= 50.0);
+}
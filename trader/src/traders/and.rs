@@ -1,7 +1,9 @@
-use super::{Trader, Order, Action};
+use super::{safe_and, Trader, Order, Safe};
 use crate::indicators::{Value, Indicator};
 use crate::economy::Monetary;
+use serde::{Serialize, Deserialize};
 
+#[derive(Serialize, Deserialize)]
 pub struct And<T1, T2>
     where
         T1: Trader,
@@ -25,17 +27,12 @@ impl<T1, T2> Trader for And<T1, T2>
         }
     }
 
+    fn stop(&self) -> Option<Monetary> {
+        self.trader1.stop().or_else(|| self.trader2.stop())
+    }
+
     fn evaluate(&mut self, (output1, output2): <Self::Indicators as Indicator>::Output) -> Option<Order> {
-        match (self.trader1.evaluate(output1), self.trader2.evaluate(output2)) {
-            (Some(Order::Limit(Action::Buy, quantity1, value1)), Some(Order::Limit(Action::Buy, quantity2, value2))) => {
-                Some(Order::Limit(Action::Buy, (quantity1 + quantity2) / 2.0, (value1 + value2) / 2.0))
-            },
-            (Some(Order::Limit(Action::Sell, quantity1, value1)), Some(Order::Limit(Action::Sell, quantity2, value2))) => {
-                Some(Order::Limit(Action::Sell, (quantity1 + quantity2) / 2.0, (value1 + value2) / 2.0))
-            },
-            (_, _) => {
-                None
-            }
-        }
+        let (order1, order2) = (self.trader1.evaluate(output1), self.trader2.evaluate(output2));
+        safe_and::combine("And", &Safe::None, order1, order2)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,125 @@
+use super::Order;
+use serde::Serialize;
+use serde_json::Value as State;
+use std::cell::RefCell;
+
+/// What a wrapper did instead of passing on its inner trader's order.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "verdict", rename_all = "snake_case")]
+pub enum Verdict {
+    /// The inner trader wasn't asked, e.g. during a backoff.
+    Suppressed,
+    /// The inner trader's order was dropped.
+    Vetoed { order: Order },
+    /// The inner trader's order was changed.
+    Replaced { from: Order, to: Order },
+    /// The wrapper placed an order of its own, e.g. when a stop loss triggers.
+    Triggered { order: Order },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Note {
+    pub trader: &'static str,
+    #[serde(flatten)]
+    pub verdict: Verdict,
+}
+
+/// Why a strategy placed an order, or why one of its traders' orders was dropped.
+#[derive(Debug, Clone)]
+pub struct Audit {
+    /// The full indicator output the traders decided on.
+    pub indicators: State,
+    pub notes: Vec<Note>,
+    pub order: Option<Order>,
+}
+
+impl Audit {
+    /// Returns nothing if no trader of the chain wanted to trade.
+    pub fn new(indicators: State, notes: Vec<Note>, order: Option<Order>) -> Option<Audit> {
+        let relevant = order.is_some() || notes.iter().any(|note| match note.verdict {
+            Verdict::Suppressed => false,
+            _ => true,
+        });
+        if relevant {
+            Some(Audit { indicators, notes, order })
+        } else {
+            None
+        }
+    }
+}
+
+thread_local! {
+    static NOTES: RefCell<Option<Vec<Note>>> = RefCell::new(None);
+}
+
+/// Notes a wrapper's decision, if the current evaluation is audited.
+pub fn note(trader: &'static str, verdict: Verdict) {
+    NOTES.with(|notes| {
+        if let Some(notes) = &mut *notes.borrow_mut() {
+            notes.push(Note { trader, verdict });
+        }
+    });
+}
+
+/// Notes every order a wrapper dropped.
+pub fn veto(trader: &'static str, orders: Vec<Option<Order>>) {
+    for order in orders.into_iter().flatten() {
+        note(trader, Verdict::Vetoed { order });
+    }
+}
+
+/// Runs an evaluation and collects the notes made during it.
+pub fn collect<R>(evaluate: impl FnOnce() -> R) -> (R, Vec<Note>) {
+    NOTES.with(|notes| *notes.borrow_mut() = Some(Vec::new()));
+    let result = evaluate();
+    let notes = NOTES.with(|notes| notes.borrow_mut().take().unwrap_or_default());
+    (result, notes)
+}
+
+#[tokio::test]
+async fn test_collect() {
+    use super::{Action, And, Trader};
+    use crate::economy::Monetary;
+    use crate::indicators::Value;
+
+    struct Buy;
+
+    impl Trader for Buy {
+        type Indicators = Value;
+
+        fn initialize(_base: &str, _quote: &str) -> Buy {
+            Buy
+        }
+
+        fn evaluate(&mut self, value: Monetary) -> Option<Order> {
+            Some(Order::Limit(Action::Buy, 1.0, value))
+        }
+    }
+
+    struct Hold;
+
+    impl Trader for Hold {
+        type Indicators = Value;
+
+        fn initialize(_base: &str, _quote: &str) -> Hold {
+            Hold
+        }
+
+        fn evaluate(&mut self, _value: Monetary) -> Option<Order> {
+            None
+        }
+    }
+
+    let mut trader = And::<Buy, Hold>::initialize("BTC", "USDT");
+    let (order, notes) = collect(|| trader.evaluate((100.0, 100.0)));
+    assert!(order.is_none());
+    assert_eq!(
+        serde_json::to_value(&notes).unwrap(),
+        serde_json::json!([{ "trader": "And", "verdict": "vetoed", "order": { "Limit": ["Buy", 1.0, 100.0] } }])
+    );
+
+    // Outside of an audit, notes are dropped.
+    trader.evaluate((100.0, 100.0));
+    let (_, notes) = collect(|| None::<Order>);
+    assert!(notes.is_empty());
+}
@@ -1,6 +1,9 @@
-use super::{Trader, Order, Action};
+use super::{audit, Trader, Order, Action, Verdict};
 use crate::indicators::Indicator;
+use crate::economy::Monetary;
+use serde::{Serialize, Deserialize};
 
+#[derive(Serialize, Deserialize)]
 pub struct Backoff<T, const BACKOFF: usize>
     where
         T: Trader
@@ -22,19 +25,33 @@ impl<T, const BACKOFF: usize> Trader for Backoff<T, BACKOFF>
         }
     }
 
+    fn stop(&self) -> Option<Monetary> {
+        self.trader.stop()
+    }
+
     fn evaluate(&mut self, output: <Self::Indicators as Indicator>::Output) -> Option<Order> {
-        if self.backoff == 0 {
-            let order = self.trader.evaluate(output);
-            match order {
-                Some(Order::Limit(Action::Buy, _, _)) | Some(Order::Limit(Action::Sell, _, _)) => {
-                    self.backoff = BACKOFF;
-                },
-                Some(_) | None => {}
-            }
-            order
-        } else {
-            self.backoff -= 1;
-            None
+        let trader = &mut self.trader;
+        decide(&mut self.backoff, BACKOFF, || trader.evaluate(output))
+    }
+}
+
+/// Skips the inner trader for `period` evaluations after each of its orders, shared with the dynamic trader.
+pub(super) fn decide<F>(backoff: &mut usize, period: usize, decide: F) -> Option<Order>
+where
+    F: FnOnce() -> Option<Order>,
+{
+    if *backoff == 0 {
+        let order = decide();
+        match order {
+            Some(Order::Limit(Action::Buy, _, _)) | Some(Order::Limit(Action::Sell, _, _)) => {
+                *backoff = period;
+            },
+            Some(_) | None => {}
         }
+        order
+    } else {
+        *backoff -= 1;
+        audit::note("Backoff", Verdict::Suppressed);
+        None
     }
-}
\ No newline at end of file
+}
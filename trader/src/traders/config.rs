@@ -0,0 +1,270 @@
+use super::DynamicTrader;
+use crate::economy::{Monetary, Sleeve};
+use crate::sizers::PositionSizer;
+use serde::{Serialize, Deserialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Strategy tree that is built into a `DynamicTrader` at runtime.
+///
+/// Each variant mirrors the trader of the same name, with the const generic parameters as fields
+/// and the wrapped traders as nested configs. The variant is selected by the `type` field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", deny_unknown_fields)]
+pub enum TraderConfig {
+    RSITrader {
+        period: usize,
+        scale: usize,
+        buy: Monetary,
+        sell: Monetary,
+        fraction: Monetary,
+    },
+    MACDTrader {
+        fraction: Monetary,
+        #[serde(default = "default_fast")]
+        fast: [usize; 3],
+        #[serde(default = "default_slow")]
+        slow: [usize; 3],
+    },
+    Backoff {
+        backoff: usize,
+        trader: Box<TraderConfig>,
+    },
+    StopLoss {
+        safe: String,
+        stop: Monetary,
+        backoff: usize,
+        trader: Box<TraderConfig>,
+    },
+    FullStop {
+        safe: String,
+        trader: Box<TraderConfig>,
+    },
+    GobbleBadLongterm {
+        safe: String,
+        #[serde(default = "default_longterm")]
+        macd: [usize; 3],
+        trader: Box<TraderConfig>,
+    },
+    And {
+        first: Box<TraderConfig>,
+        second: Box<TraderConfig>,
+    },
+    SafeAnd {
+        safe: String,
+        first: Box<TraderConfig>,
+        second: Box<TraderConfig>,
+    },
+}
+
+fn default_fast() -> [usize; 3] {
+    [720, 1560, 540]
+}
+
+fn default_slow() -> [usize; 3] {
+    [5760, 12480, 4320]
+}
+
+fn default_longterm() -> [usize; 3] {
+    [21600, 46800, 16200]
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(String),
+    /// Path to the offending entry and the reason it was rejected.
+    Invalid(String, String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(error) => write!(f, "could not read config: {}", error),
+            ConfigError::Parse(error) => write!(f, "could not parse config: {}", error),
+            ConfigError::Invalid(path, reason) => write!(f, "invalid config at {}: {}", path, reason),
+        }
+    }
+}
+
+fn invalid(path: &str, reason: &str) -> Result<(), ConfigError> {
+    Err(ConfigError::Invalid(String::from(path), String::from(reason)))
+}
+
+fn validate_fraction(path: &str, fraction: Monetary) -> Result<(), ConfigError> {
+    if fraction > 0.0 && fraction <= 1.0 {
+        Ok(())
+    } else {
+        invalid(path, "fraction must be in (0, 1]")
+    }
+}
+
+fn validate_safe(path: &str, safe: &str) -> Result<(), ConfigError> {
+    if safe.is_empty() {
+        invalid(path, "safe asset must not be empty")
+    } else {
+        Ok(())
+    }
+}
+
+fn validate_macd(path: &str, [short, long, period]: [usize; 3]) -> Result<(), ConfigError> {
+    if short == 0 || period == 0 {
+        invalid(path, "macd periods must be positive")
+    } else if short >= long {
+        invalid(path, "short macd period must be below the long one")
+    } else {
+        Ok(())
+    }
+}
+
+impl TraderConfig {
+    /// Checks the parameters of this node and all nodes below it.
+    pub fn validate(&self, path: &str) -> Result<(), ConfigError> {
+        match self {
+            TraderConfig::RSITrader { period, scale, buy, sell, fraction } => {
+                if *period == 0 || *scale == 0 {
+                    return invalid(path, "periods must be positive");
+                }
+                if !(*buy > 0.0 && buy < sell && *sell < 100.0) {
+                    return invalid(path, "thresholds must satisfy 0 < buy < sell < 100");
+                }
+                validate_fraction(path, *fraction)
+            },
+            TraderConfig::MACDTrader { fraction, fast, slow } => {
+                validate_fraction(path, *fraction)?;
+                validate_macd(&format!("{}.fast", path), *fast)?;
+                validate_macd(&format!("{}.slow", path), *slow)
+            },
+            TraderConfig::Backoff { trader, .. } => {
+                trader.validate(&format!("{}.trader", path))
+            },
+            TraderConfig::StopLoss { safe, stop, trader, .. } => {
+                validate_safe(path, safe)?;
+                if !(*stop > 0.0 && *stop < 1.0) {
+                    return invalid(path, "stop must be in (0, 1)");
+                }
+                trader.validate(&format!("{}.trader", path))
+            },
+            TraderConfig::FullStop { safe, trader } => {
+                validate_safe(path, safe)?;
+                trader.validate(&format!("{}.trader", path))
+            },
+            TraderConfig::GobbleBadLongterm { safe, macd, trader } => {
+                validate_safe(path, safe)?;
+                validate_macd(&format!("{}.macd", path), *macd)?;
+                trader.validate(&format!("{}.trader", path))
+            },
+            TraderConfig::And { first, second } => {
+                first.validate(&format!("{}.first", path))?;
+                second.validate(&format!("{}.second", path))
+            },
+            TraderConfig::SafeAnd { safe, first, second } => {
+                validate_safe(path, safe)?;
+                first.validate(&format!("{}.first", path))?;
+                second.validate(&format!("{}.second", path))
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SleeveConfig {
+    pub name: String,
+    pub allocation: Monetary,
+    /// Markets the sleeve trades on, all markets if empty.
+    #[serde(default)]
+    pub symbols: Vec<String>,
+    pub trader: TraderConfig,
+}
+
+impl SleeveConfig {
+    pub fn build<S: PositionSizer>(&self) -> Sleeve<S> {
+        let trader = self.trader.clone();
+        let symbols = self.symbols
+            .iter()
+            .map(|symbol| symbol.as_str())
+            .collect::<Vec<&str>>();
+        Sleeve::with_factory(
+            &self.name,
+            self.allocation,
+            &symbols,
+            Box::new(move |base, quote| DynamicTrader::boxed(trader.clone(), base, quote)),
+        )
+    }
+}
+
+/// Content of a strategy config file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StrategyConfig {
+    pub sleeves: Vec<SleeveConfig>,
+}
+
+impl StrategyConfig {
+    /// Reads a TOML file if the path ends in `.toml`, and JSON otherwise.
+    pub fn load(path: &Path) -> Result<StrategyConfig, ConfigError> {
+        let content = fs::read_to_string(path).map_err(ConfigError::Io)?;
+        let config = if path.extension().map_or(false, |extension| extension == "toml") {
+            toml::from_str::<StrategyConfig>(&content).map_err(|error| ConfigError::Parse(error.to_string()))?
+        } else {
+            serde_json::from_str::<StrategyConfig>(&content).map_err(|error| ConfigError::Parse(error.to_string()))?
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.sleeves.is_empty() {
+            return invalid("sleeves", "at least one sleeve is required");
+        }
+
+        let mut names = HashSet::new();
+        let mut allocation = 0.0;
+        for sleeve in &self.sleeves {
+            let path = format!("sleeves.{}", sleeve.name);
+            if sleeve.name.is_empty() || !names.insert(&sleeve.name) {
+                return invalid(&path, "sleeve names must be unique and not empty");
+            }
+            if !(sleeve.allocation > 0.0 && sleeve.allocation <= 1.0) {
+                return invalid(&path, "allocation must be in (0, 1]");
+            }
+            allocation += sleeve.allocation;
+            sleeve.trader.validate(&format!("{}.trader", path))?;
+        }
+
+        if allocation > 1.0 + 1e-9 {
+            return invalid("sleeves", "allocations must not add up to more than 1");
+        }
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_validate() {
+    let config: StrategyConfig = toml::from_str(r#"
+        [[sleeves]]
+        name = "rsi"
+        allocation = 1.0
+
+        [sleeves.trader]
+        type = "Backoff"
+        backoff = 60
+
+        [sleeves.trader.trader]
+        type = "RSITrader"
+        period = 4200
+        scale = 300
+        buy = 80.0
+        sell = 20.0
+        fraction = 0.05
+    "#).unwrap();
+
+    match config.validate() {
+        Err(ConfigError::Invalid(path, _)) => assert_eq!(path, "sleeves.rsi.trader.trader"),
+        _ => panic!("expected validation error"),
+    }
+}
@@ -0,0 +1,323 @@
+use super::{audit, backoff, full_stop, gobble_bad_longterm, macd_trader, rsi_trader, safe_and, stop_loss, Audit, Order, Safe, Strategy, TraderConfig};
+use crate::economy::{Bar, Monetary};
+use crate::indicators::{DynamicStretchedRSI, DynamicMACDHistogram};
+use serde::{Serialize, Deserialize};
+use serde_json::Value as State;
+
+/// Runtime counterpart of the traders, each node owns the indicators its trader would request.
+///
+/// Like with the static traders, all indicators are evaluated on every value
+/// before any node decides, so wrappers that skip their inner trader do not stall its indicators.
+#[derive(Serialize, Deserialize)]
+enum Node {
+    RSITrader {
+        rsi: DynamicStretchedRSI,
+        output: Option<Monetary>,
+        previous_rsi: Monetary,
+        buy: Monetary,
+        sell: Monetary,
+        fraction: Monetary,
+    },
+    MACDTrader {
+        fast: DynamicMACDHistogram,
+        slow: DynamicMACDHistogram,
+        output: (Option<(Monetary, Monetary)>, Option<(Monetary, Monetary)>),
+        previous_macdh: Monetary,
+        fraction: Monetary,
+    },
+    Backoff {
+        trader: Box<Node>,
+        period: usize,
+        backoff: usize,
+    },
+    StopLoss {
+        trader: Box<Node>,
+        safe: Safe,
+        factor: Monetary,
+        period: usize,
+        stop: Option<Monetary>,
+        backoff: usize,
+    },
+    FullStop {
+        trader: Box<Node>,
+        safe: Safe,
+    },
+    GobbleBadLongterm {
+        trader: Box<Node>,
+        safe: Safe,
+        macd: DynamicMACDHistogram,
+        output: Option<(Monetary, Monetary)>,
+    },
+    And {
+        first: Box<Node>,
+        second: Box<Node>,
+    },
+    SafeAnd {
+        first: Box<Node>,
+        second: Box<Node>,
+        safe: Safe,
+    },
+}
+
+impl Node {
+    fn build(config: &TraderConfig, base: &str, quote: &str, value: Monetary) -> Node {
+        let build = |config: &TraderConfig| Box::new(Node::build(config, base, quote, value));
+        match config {
+            TraderConfig::RSITrader { period, scale, buy, sell, fraction } => Node::RSITrader {
+                rsi: DynamicStretchedRSI::new(*period, *scale, value),
+                output: None,
+                previous_rsi: 50.0,
+                buy: *buy,
+                sell: *sell,
+                fraction: *fraction,
+            },
+            TraderConfig::MACDTrader { fraction, fast, slow } => Node::MACDTrader {
+                fast: DynamicMACDHistogram::new(*fast, value),
+                slow: DynamicMACDHistogram::new(*slow, value),
+                output: (None, None),
+                previous_macdh: 0.0,
+                fraction: *fraction,
+            },
+            TraderConfig::Backoff { backoff, trader } => Node::Backoff {
+                trader: build(trader),
+                period: *backoff,
+                backoff: 0,
+            },
+            TraderConfig::StopLoss { safe, stop, backoff, trader } => Node::StopLoss {
+                trader: build(trader),
+                safe: Safe::new(safe, base, quote),
+                factor: *stop,
+                period: *backoff,
+                stop: None,
+                backoff: 0,
+            },
+            TraderConfig::FullStop { safe, trader } => Node::FullStop {
+                trader: build(trader),
+                safe: Safe::new(safe, base, quote),
+            },
+            TraderConfig::GobbleBadLongterm { safe, macd, trader } => Node::GobbleBadLongterm {
+                trader: build(trader),
+                safe: Safe::new(safe, base, quote),
+                macd: DynamicMACDHistogram::new(*macd, value),
+                output: None,
+            },
+            TraderConfig::And { first, second } => Node::And {
+                first: build(first),
+                second: build(second),
+            },
+            TraderConfig::SafeAnd { safe, first, second } => Node::SafeAnd {
+                first: build(first),
+                second: build(second),
+                safe: Safe::new(safe, base, quote),
+            },
+        }
+    }
+
+    /// Evaluates all indicators of the tree.
+    fn observe(&mut self, value: Monetary) {
+        match self {
+            Node::RSITrader { rsi, output, .. } => {
+                *output = rsi.evaluate(value);
+            },
+            Node::MACDTrader { fast, slow, output, .. } => {
+                *output = (fast.evaluate(value), slow.evaluate(value));
+            },
+            Node::GobbleBadLongterm { trader, macd, output, .. } => {
+                *output = macd.evaluate(value);
+                trader.observe(value);
+            },
+            Node::Backoff { trader, .. } | Node::StopLoss { trader, .. } | Node::FullStop { trader, .. } => {
+                trader.observe(value);
+            },
+            Node::And { first, second } | Node::SafeAnd { first, second, .. } => {
+                first.observe(value);
+                second.observe(value);
+            },
+        }
+    }
+
+    /// Outputs of the tree's indicators, as of the last observation.
+    fn outputs(&self) -> State {
+        match self {
+            Node::RSITrader { output, .. } => serde_json::json!({ "rsi": output }),
+            Node::MACDTrader { output, .. } => serde_json::json!({ "fast": output.0, "slow": output.1 }),
+            Node::GobbleBadLongterm { trader, output, .. } => serde_json::json!({ "macd": output, "trader": trader.outputs() }),
+            Node::Backoff { trader, .. } | Node::StopLoss { trader, .. } | Node::FullStop { trader, .. } => trader.outputs(),
+            Node::And { first, second } | Node::SafeAnd { first, second, .. } => serde_json::json!([first.outputs(), second.outputs()]),
+        }
+    }
+
+    fn stop(&self) -> Option<Monetary> {
+        match self {
+            Node::RSITrader { .. } | Node::MACDTrader { .. } => None,
+            Node::StopLoss { trader, safe, factor, .. } => stop_loss::distance(safe, *factor, || trader.stop()),
+            Node::Backoff { trader, .. } | Node::FullStop { trader, .. } | Node::GobbleBadLongterm { trader, .. } => trader.stop(),
+            Node::And { first, second } | Node::SafeAnd { first, second, .. } => first.stop().or_else(|| second.stop()),
+        }
+    }
+
+    /// Decides like the trader of the same name, with the shared rules of the static traders.
+    fn decide(&mut self, value: Monetary) -> Option<Order> {
+        match self {
+            Node::RSITrader { output, previous_rsi, buy, sell, fraction, .. } => {
+                rsi_trader::decide(previous_rsi, value, *output, *buy, *sell, *fraction)
+            },
+            Node::MACDTrader { output, previous_macdh, fraction, .. } => {
+                macd_trader::decide(previous_macdh, value, output.0, output.1, *fraction)
+            },
+            Node::Backoff { trader, period, backoff } => {
+                backoff::decide(backoff, *period, || trader.decide(value))
+            },
+            Node::StopLoss { trader, safe, factor, period, stop, backoff } => {
+                stop_loss::decide(safe, *factor, *period, stop, backoff, value, || trader.decide(value))
+            },
+            Node::FullStop { trader, safe } => {
+                full_stop::widen(safe, trader.decide(value))
+            },
+            Node::GobbleBadLongterm { trader, safe, output, .. } => {
+                gobble_bad_longterm::veto(safe, *output, trader.decide(value))
+            },
+            Node::And { first, second } => {
+                safe_and::combine("And", &Safe::None, first.decide(value), second.decide(value))
+            },
+            Node::SafeAnd { first, second, safe } => {
+                safe_and::combine("SafeAnd", safe, first.decide(value), second.decide(value))
+            },
+        }
+    }
+}
+
+/// Trader built from a `TraderConfig` at runtime.
+pub struct DynamicTrader {
+    config: TraderConfig,
+    base: String,
+    quote: String,
+    node: Option<Node>,
+}
+
+impl DynamicTrader {
+    pub fn new(config: TraderConfig, base: &str, quote: &str) -> DynamicTrader {
+        DynamicTrader {
+            config,
+            base: String::from(base),
+            quote: String::from(quote),
+            node: None,
+        }
+    }
+
+    pub fn boxed(config: TraderConfig, base: &str, quote: &str) -> Box<dyn Strategy> {
+        Box::new(DynamicTrader::new(config, base, quote))
+    }
+}
+
+impl Strategy for DynamicTrader {
+    /// The indicators of the tree only see the close of each bar.
+    fn evaluate(&mut self, bar: &Bar) -> Option<Order> {
+        let value = bar.close;
+        if let Some(node) = &mut self.node {
+            node.observe(value);
+            node.decide(value)
+        } else {
+            self.node = Some(Node::build(&self.config, &self.base, &self.quote, value));
+            None
+        }
+    }
+
+    fn evaluate_audited(&mut self, bar: &Bar) -> (Option<Order>, Option<Audit>) {
+        if self.node.is_none() {
+            return (self.evaluate(bar), None);
+        }
+        let value = bar.close;
+        let node = self.node.as_mut().unwrap();
+        node.observe(value);
+        let outputs = node.outputs();
+        let (order, notes) = audit::collect(|| node.decide(value));
+        (order.clone(), Audit::new(outputs, notes, order))
+    }
+
+    fn warm_up(&mut self, bar: &Bar) {
+        if let Some(node) = &mut self.node {
+            node.observe(bar.close);
+        } else {
+            self.node = Some(Node::build(&self.config, &self.base, &self.quote, bar.close));
+        }
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.node.is_some()
+    }
+
+    fn stop(&self) -> Option<Monetary> {
+        self.node.as_ref().and_then(|node| node.stop())
+    }
+
+    fn save(&self) -> Result<State, serde_json::Error> {
+        serde_json::to_value((&self.config, &self.node))
+    }
+
+    /// Fails if the checkpoint was taken with a different config, the strategy then starts from scratch.
+    fn restore(&mut self, state: State) -> Result<(), serde_json::Error> {
+        let (config, node): (TraderConfig, Option<Node>) = serde_json::from_value(state)?;
+        if config != self.config {
+            return Err(serde::de::Error::custom("strategy config changed since the checkpoint"));
+        }
+        self.node = node;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_matches_static() {
+    use super::{And, Backoff, FullStop, GobbleBadLongterm, MACDTrader, RSITrader, SafeAnd, StopLoss, TraderStrategy};
+
+    fn compare(config: &str, mut fixed: Box<dyn Strategy>, values: usize, value: fn(f64) -> Monetary) {
+        let config: TraderConfig = serde_json::from_str(config).unwrap();
+        config.validate("trader").unwrap();
+
+        let mut dynamic = DynamicTrader::new(config, "BTC", "USDT");
+        let mut orders = 0;
+        for i in 0..values {
+            let bar = Bar::flat(value(i as f64));
+            let order = dynamic.evaluate(&bar);
+            if order.is_some() {
+                orders += 1;
+            }
+            assert_eq!(format!("{:?}", order), format!("{:?}", fixed.evaluate(&bar)));
+        }
+        assert!(orders > 0);
+    }
+
+    compare(r#"{
+        "type": "StopLoss", "safe": "USDT", "stop": 0.95, "backoff": 5,
+        "trader": {
+            "type": "Backoff", "backoff": 3,
+            "trader": { "type": "RSITrader", "period": 20, "scale": 5, "buy": 30.0, "sell": 70.0, "fraction": 0.1 }
+        }
+    }"#,
+        TraderStrategy::<StopLoss<Backoff<RSITrader<20, 5, 30.0, 70.0, 0.1>, 3>, "USDT", 0.95, 5>>::boxed("BTC", "USDT"),
+        2000,
+        |i| 100.0 + 10.0 * (i / 15.0).sin() + 3.0 * (i / 4.0).cos(),
+    );
+
+    // The long term averages only settle after tens of thousands of values.
+    compare(r#"{
+        "type": "SafeAnd", "safe": "USDT",
+        "first": { "type": "FullStop", "safe": "USDT", "trader": { "type": "MACDTrader", "fraction": 0.2 } },
+        "second": {
+            "type": "And",
+            "first": {
+                "type": "GobbleBadLongterm", "safe": "USDT",
+                "trader": { "type": "RSITrader", "period": 20, "scale": 5, "buy": 30.0, "sell": 70.0, "fraction": 0.1 }
+            },
+            "second": { "type": "RSITrader", "period": 20, "scale": 5, "buy": 30.0, "sell": 70.0, "fraction": 0.3 }
+        }
+    }"#,
+        TraderStrategy::<SafeAnd<
+            FullStop<MACDTrader<0.2>, "USDT">,
+            And<GobbleBadLongterm<RSITrader<20, 5, 30.0, 70.0, 0.1>, "USDT">, RSITrader<20, 5, 30.0, 70.0, 0.3>>,
+            "USDT",
+        >>::boxed("BTC", "USDT"),
+        70000,
+        |i| 100.0 + 20.0 * (i / 8000.0).sin() + 10.0 * (i / 15.0).sin() + 3.0 * (i / 4.0).cos(),
+    );
+}
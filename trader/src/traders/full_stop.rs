@@ -1,13 +1,9 @@
-use super::{Trader, Order, Action};
+use super::{audit, Trader, Order, Action, Safe, Verdict};
 use crate::indicators::{Value, Indicator};
 use crate::economy::Monetary;
+use serde::{Serialize, Deserialize};
 
-enum Safe {
-    Base,
-    Quote,
-    None
-}
-
+#[derive(Serialize, Deserialize)]
 pub struct FullStop<T, const SAFE: &'static str>
     where
         T: Trader
@@ -25,33 +21,27 @@ impl<T, const SAFE: &'static str> Trader for FullStop<T, SAFE>
     fn initialize(base: &str, quote: &str) -> FullStop<T, SAFE> {
         FullStop {
             trader: T::initialize(base, quote),
-            safe: if base == SAFE {
-                    Safe::Base
-                } else
-                if quote == SAFE {
-                    Safe::Quote
-                } else {
-                    Safe::None
-                }
+            safe: Safe::new(SAFE, base, quote)
         }
     }
 
+    fn stop(&self) -> Option<Monetary> {
+        self.trader.stop()
+    }
+
     fn evaluate(&mut self, output: <Self::Indicators as Indicator>::Output) -> Option<Order> {
-        if let Some(order) = self.trader.evaluate(output) {
-            Some(match (&self.safe, order) {
-                (Safe::Base, Order::Limit(Action::Buy, quantity, value)) => {
-                    Order::Limit(Action::Buy, 1.0, value)
-                },
-                (Safe::Quote, Order::Limit(Action::Sell, quantity, value)) => {
-                    Order::Limit(Action::Sell, 1.0, value)
-                },
-                (_, order) => {
-                    order
-                }
-            })
-        } else {
-            None
-        }
-        
+        widen(&self.safe, self.trader.evaluate(output))
     }
-}
\ No newline at end of file
+}
+
+/// Turns orders into the safe asset into orders of everything, shared with the dynamic trader.
+pub(super) fn widen(safe: &Safe, order: Option<Order>) -> Option<Order> {
+    let order = order?;
+    let full = match (safe, &order) {
+        (Safe::Base, Order::Limit(Action::Buy, _, value)) => Order::Limit(Action::Buy, 1.0, *value),
+        (Safe::Quote, Order::Limit(Action::Sell, _, value)) => Order::Limit(Action::Sell, 1.0, *value),
+        _ => return Some(order),
+    };
+    audit::note("FullStop", Verdict::Replaced { from: order, to: full.clone() });
+    Some(full)
+}
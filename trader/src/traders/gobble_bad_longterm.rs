@@ -1,13 +1,9 @@
-use super::{Trader, Order, Action};
+use super::{audit, Trader, Order, Action, Safe, Verdict};
 use crate::indicators::{MACDHistogram, Indicator};
 use crate::economy::Monetary;
+use serde::{Serialize, Deserialize};
 
-enum Safe {
-    Base,
-    Quote,
-    None
-}
-
+#[derive(Serialize, Deserialize)]
 pub struct GobbleBadLongterm<T, const SAFE: &'static str>
     where
         T: Trader
@@ -25,41 +21,33 @@ impl<T, const SAFE: &'static str> Trader for GobbleBadLongterm<T, SAFE>
     fn initialize(base: &str, quote: &str) -> GobbleBadLongterm<T, SAFE> {
         GobbleBadLongterm {
             trader: T::initialize(base, quote),
-            safe: if base == SAFE {
-                    Safe::Base
-                } else
-                if quote == SAFE {
-                    Safe::Quote
-                } else {
-                    Safe::None
-                }
+            safe: Safe::new(SAFE, base, quote)
         }
     }
 
+    fn stop(&self) -> Option<Monetary> {
+        self.trader.stop()
+    }
+
     fn evaluate(&mut self, (macd, output): <Self::Indicators as Indicator>::Output) -> Option<Order> {
-        if let (Some(order), Some((macd, macdh))) = (self.trader.evaluate(output), macd) {
-            match (&self.safe, order) {
-                (Safe::Quote, Order::Limit(Action::Buy, quantity, value)) => {
-                    if macdh > 0.0 {
-                        Some(Order::Limit(Action::Buy, quantity, value))
-                    } else {
-                        None
-                    }
-                },
-                (Safe::Base, Order::Limit(Action::Sell, quantity, value)) => {
-                    if macdh < 0.0 {
-                        Some(Order::Limit(Action::Sell, quantity, value))
-                    } else {
-                        None
-                    }
-                },
-                (_, order) => {
-                    Some(order)
-                }
-            }
-        } else {
-            None
-        }
-        
+        veto(&self.safe, macd, self.trader.evaluate(output))
     }
-}
\ No newline at end of file
+}
+
+/// Vetoes orders away from the safe asset against the long term trend, and all orders while it is unknown.
+/// Shared with the dynamic trader.
+pub(super) fn veto(safe: &Safe, macd: Option<(Monetary, Monetary)>, order: Option<Order>) -> Option<Order> {
+    let order = order?;
+    let vetoed = match (safe, &order, macd) {
+        (_, _, None) => true,
+        (Safe::Quote, Order::Limit(Action::Buy, _, _), Some((_, macdh))) => macdh <= 0.0,
+        (Safe::Base, Order::Limit(Action::Sell, _, _), Some((_, macdh))) => macdh >= 0.0,
+        _ => false,
+    };
+    if vetoed {
+        audit::note("GobbleBadLongterm", Verdict::Vetoed { order });
+        None
+    } else {
+        Some(order)
+    }
+}
@@ -1,7 +1,9 @@
 use super::{Order, Action, Trader};
 use crate::economy::Monetary;
 use crate::indicators::{Indicator, Value, StretchedRSI, SMA, MACDHistogram, MACD};
+use serde::{Serialize, Deserialize};
 
+#[derive(Serialize, Deserialize)]
 pub struct MACDTrader<const FRACTION: Monetary> {
     previous_macdh: Monetary,
 }
@@ -18,32 +20,43 @@ impl<const FRACTION: Monetary> Trader
     }
 
     fn evaluate(&mut self, (value, macd, lmacd): <Self::Indicators as Indicator>::Output) -> Option<Order> {
-        if let (Some((macd, macdh)), Some((lmacd, lmacdh))) = (macd, lmacd) {
-            let action = if
-                self.previous_macdh < 0.0 &&
-                macdh >= 0.0 &&
-                macd < 0.0 &&
-                lmacdh >= 0.0 &&
-                lmacd < 0.0
-            {
-                Some(Order::Limit(Action::Buy, FRACTION, value))
-            } else if
-                self.previous_macdh > 0.0 &&
-                macdh <= 0.0 &&
-                macd > 0.0 &&
-                lmacdh <= 0.0 &&
-                lmacd > 0.0
-            {
-                Some(Order::Limit(Action::Sell, FRACTION, value))
-            } else {
-                None
-            };
-
-            self.previous_macdh = macdh;
+        decide(&mut self.previous_macdh, value, macd, lmacd, FRACTION)
+    }
+}
 
-            action
+/// Trades when the short term histogram crosses zero in agreement with the long term one, shared with the dynamic trader.
+pub(super) fn decide(
+    previous_macdh: &mut Monetary,
+    value: Monetary,
+    macd: Option<(Monetary, Monetary)>,
+    lmacd: Option<(Monetary, Monetary)>,
+    fraction: Monetary,
+) -> Option<Order> {
+    if let (Some((macd, macdh)), Some((lmacd, lmacdh))) = (macd, lmacd) {
+        let action = if
+            *previous_macdh < 0.0 &&
+            macdh >= 0.0 &&
+            macd < 0.0 &&
+            lmacdh >= 0.0 &&
+            lmacd < 0.0
+        {
+            Some(Order::Limit(Action::Buy, fraction, value))
+        } else if
+            *previous_macdh > 0.0 &&
+            macdh <= 0.0 &&
+            macd > 0.0 &&
+            lmacdh <= 0.0 &&
+            lmacd > 0.0
+        {
+            Some(Order::Limit(Action::Sell, fraction, value))
         } else {
             None
-        }
+        };
+
+        *previous_macdh = macdh;
+
+        action
+    } else {
+        None
     }
 }
@@ -6,6 +6,10 @@ mod full_stop;
 mod gobble_bad_longterm;
 mod and;
 mod safe_and;
+mod strategy;
+mod config;
+mod dynamic;
+mod audit;
 
 pub use rsi_trader::RSITrader;
 pub use macd_trader::MACDTrader;
@@ -15,22 +19,47 @@ pub use full_stop::FullStop;
 pub use gobble_bad_longterm::GobbleBadLongterm;
 pub use and::And;
 pub use safe_and::SafeAnd;
+pub use strategy::{Strategy, TraderStrategy};
+pub use config::{TraderConfig, SleeveConfig, StrategyConfig, ConfigError};
+pub use dynamic::DynamicTrader;
+pub use audit::{Audit, Note, Verdict};
 
 use crate::economy::Monetary;
 use crate::indicators::Indicator;
+use serde::{Serialize, Deserialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Order {
     Market(Action, Monetary),
     Limit(Action, Monetary, Monetary),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Action {
     Buy,
     Sell
 }
 
+/// Which asset of a market a trader falls back to.
+#[derive(Serialize, Deserialize)]
+enum Safe {
+    Base,
+    Quote,
+    None
+}
+
+impl Safe {
+    fn new(safe: &str, base: &str, quote: &str) -> Safe {
+        if base == safe {
+            Safe::Base
+        } else if quote == safe {
+            Safe::Quote
+        } else {
+            Safe::None
+        }
+    }
+}
+
 pub trait Trader
 where
     Self::Indicators: Indicator,
@@ -40,4 +69,9 @@ where
     fn initialize(base: &str, quote: &str) -> Self;
 
     fn evaluate(&mut self, output: <Self::Indicators as Indicator>::Output) -> Option<Order>;
+
+    /// Distance from the price to the stop of a position, relative to the price, if the trader keeps a stop.
+    fn stop(&self) -> Option<Monetary> {
+        None
+    }
 }
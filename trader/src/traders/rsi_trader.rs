@@ -1,7 +1,9 @@
 use super::{Order, Action, Trader};
 use crate::economy::Monetary;
 use crate::indicators::{Indicator, Value, StretchedRSI, EMA};
+use serde::{Serialize, Deserialize};
 
+#[derive(Serialize, Deserialize)]
 pub struct RSITrader<const PERIOD: usize, const SCALE: usize, const BUY: Monetary, const SELL: Monetary, const FRACTION: Monetary> {
     previous_rsi: Monetary,
 }
@@ -18,20 +20,22 @@ impl<const PERIOD: usize, const SCALE: usize, const BUY: Monetary, const SELL: M
     }
 
     fn evaluate(&mut self, (value, rsi): <Self::Indicators as Indicator>::Output) -> Option<Order> {
-        if let Some(rsi) = rsi {
-            let action = if self.previous_rsi < BUY && rsi >= BUY {
-                Some(Order::Limit(Action::Buy, FRACTION, value))
-            } else if self.previous_rsi > SELL && rsi <= SELL {
-                Some(Order::Limit(Action::Sell, FRACTION, value))
-            } else {
-                None
-            };
+        decide(&mut self.previous_rsi, value, rsi, BUY, SELL, FRACTION)
+    }
+}
 
-            self.previous_rsi = rsi;
+/// Buys when the RSI rises through `buy` and sells when it falls through `sell`, shared with the dynamic trader.
+pub(super) fn decide(previous_rsi: &mut Monetary, value: Monetary, rsi: Option<Monetary>, buy: Monetary, sell: Monetary, fraction: Monetary) -> Option<Order> {
+    let rsi = rsi?;
+    let action = if *previous_rsi < buy && rsi >= buy {
+        Some(Order::Limit(Action::Buy, fraction, value))
+    } else if *previous_rsi > sell && rsi <= sell {
+        Some(Order::Limit(Action::Sell, fraction, value))
+    } else {
+        None
+    };
 
-            action
-        } else {
-            None
-        }
-    }
+    *previous_rsi = rsi;
+
+    action
 }
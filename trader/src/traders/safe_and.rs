@@ -1,13 +1,9 @@
-use super::{Trader, Order, Action};
+use super::{audit, Trader, Order, Action, Safe};
 use crate::indicators::{Value, Indicator};
 use crate::economy::Monetary;
+use serde::{Serialize, Deserialize};
 
-enum Safe {
-    Base,
-    Quote,
-    None
-}
-
+#[derive(Serialize, Deserialize)]
 pub struct SafeAnd<T1, T2, const SAFE: &'static str>
     where
         T1: Trader,
@@ -29,70 +25,41 @@ impl<T1, T2, const SAFE: &'static str> Trader for SafeAnd<T1, T2, SAFE>
         SafeAnd {
             trader1: T1::initialize(base, quote),
             trader2: T2::initialize(base, quote),
-            safe: if base == SAFE {
-                    Safe::Base
-                } else
-                if quote == SAFE {
-                    Safe::Quote
-                } else {
-                    Safe::None
-                }
+            safe: Safe::new(SAFE, base, quote)
         }
     }
 
+    fn stop(&self) -> Option<Monetary> {
+        self.trader1.stop().or_else(|| self.trader2.stop())
+    }
+
     fn evaluate(&mut self, (output1, output2): <Self::Indicators as Indicator>::Output) -> Option<Order> {
-        match self.safe {
-            Safe::Base => {
-                match (self.trader1.evaluate(output1), self.trader2.evaluate(output2)) {
-                    (Some(Order::Limit(Action::Buy, quantity1, value1)), Some(Order::Limit(Action::Buy, quantity2, value2))) => {
-                        Some(Order::Limit(Action::Buy, (quantity1 + quantity2) / 2.0, (value1 + value2) / 2.0))
-                    },
-                    (Some(Order::Limit(Action::Buy, quantity1, value1)), _) => {
-                        Some(Order::Limit(Action::Buy, quantity1, value1))
-                    },
-                    (_, Some(Order::Limit(Action::Buy, quantity2, value2))) => {
-                        Some(Order::Limit(Action::Buy, quantity2, value2))
-                    },
-                    (Some(Order::Limit(Action::Sell, quantity1, value1)), Some(Order::Limit(Action::Sell, quantity2, value2))) => {
-                        Some(Order::Limit(Action::Sell, (quantity1 + quantity2) / 2.0, (value1 + value2) / 2.0))
-                    },
-                    (_, _) => {
-                        None
-                    }
-                }
-            },
-            Safe::Quote => {
-                match (self.trader1.evaluate(output1), self.trader2.evaluate(output2)) {
-                    (Some(Order::Limit(Action::Buy, quantity1, value1)), Some(Order::Limit(Action::Buy, quantity2, value2))) => {
-                        Some(Order::Limit(Action::Buy, (quantity1 + quantity2) / 2.0, (value1 + value2) / 2.0))
-                    },
-                    (Some(Order::Limit(Action::Sell, quantity1, value1)), Some(Order::Limit(Action::Sell, quantity2, value2))) => {
-                        Some(Order::Limit(Action::Sell, (quantity1 + quantity2) / 2.0, (value1 + value2) / 2.0))
-                    },
-                    (Some(Order::Limit(Action::Sell, quantity1, value1)), _) => {
-                        Some(Order::Limit(Action::Sell, quantity1, value1))
-                    },
-                    (_, Some(Order::Limit(Action::Sell, quantity2, value2))) => {
-                        Some(Order::Limit(Action::Sell, quantity2, value2))
-                    },
-                    (_, _) => {
-                        None
-                    }
-                }
-            },
-            Safe::None => {
-                match (self.trader1.evaluate(output1), self.trader2.evaluate(output2)) {
-                    (Some(Order::Limit(Action::Buy, quantity1, value1)), Some(Order::Limit(Action::Buy, quantity2, value2))) => {
-                        Some(Order::Limit(Action::Buy, (quantity1 + quantity2) / 2.0, (value1 + value2) / 2.0))
-                    },
-                    (Some(Order::Limit(Action::Sell, quantity1, value1)), Some(Order::Limit(Action::Sell, quantity2, value2))) => {
-                        Some(Order::Limit(Action::Sell, (quantity1 + quantity2) / 2.0, (value1 + value2) / 2.0))
-                    },
-                    (_, _) => {
-                        None
-                    }
-                }
-            }
-        }
+        let (order1, order2) = (self.trader1.evaluate(output1), self.trader2.evaluate(output2));
+        combine("SafeAnd", &self.safe, order1, order2)
     }
-}
\ No newline at end of file
+}
+
+/// Agreeing orders are averaged. Towards the safe asset, a single order is enough.
+/// Shared with `And`, which has no safe asset, and the dynamic trader.
+pub(super) fn combine(trader: &'static str, safe: &Safe, first: Option<Order>, second: Option<Order>) -> Option<Order> {
+    match (safe, first.clone(), second.clone()) {
+        (_, Some(Order::Limit(Action::Buy, quantity1, value1)), Some(Order::Limit(Action::Buy, quantity2, value2))) => {
+            Some(Order::Limit(Action::Buy, (quantity1 + quantity2) / 2.0, (value1 + value2) / 2.0))
+        },
+        (_, Some(Order::Limit(Action::Sell, quantity1, value1)), Some(Order::Limit(Action::Sell, quantity2, value2))) => {
+            Some(Order::Limit(Action::Sell, (quantity1 + quantity2) / 2.0, (value1 + value2) / 2.0))
+        },
+        (Safe::Base, Some(Order::Limit(Action::Buy, quantity, value)), _) |
+        (Safe::Base, _, Some(Order::Limit(Action::Buy, quantity, value))) => {
+            Some(Order::Limit(Action::Buy, quantity, value))
+        },
+        (Safe::Quote, Some(Order::Limit(Action::Sell, quantity, value)), _) |
+        (Safe::Quote, _, Some(Order::Limit(Action::Sell, quantity, value))) => {
+            Some(Order::Limit(Action::Sell, quantity, value))
+        },
+        _ => {
+            audit::veto(trader, vec![first, second]);
+            None
+        },
+    }
+}
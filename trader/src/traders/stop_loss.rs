@@ -1,13 +1,9 @@
-use super::{Trader, Order, Action};
+use super::{audit, Trader, Order, Action, Safe, Verdict};
 use crate::indicators::{Value, Indicator};
 use crate::economy::Monetary;
+use serde::{Serialize, Deserialize};
 
-enum Safe {
-    Base,
-    Quote,
-    None
-}
-
+#[derive(Serialize, Deserialize)]
 pub struct StopLoss<T, const SAFE: &'static str, const STOP: Monetary, const BACKOFF: usize>
     where
         T: Trader
@@ -27,66 +23,78 @@ impl<T, const SAFE: &'static str, const STOP: Monetary, const BACKOFF: usize> Tr
     fn initialize(base: &str, quote: &str) -> StopLoss<T, SAFE, STOP, BACKOFF> {
         StopLoss {
             trader: T::initialize(base, quote),
-            safe: if base == SAFE {
-                    Safe::Base
-                } else
-                if quote == SAFE {
-                    Safe::Quote
-                } else {
-                    Safe::None
-                },
+            safe: Safe::new(SAFE, base, quote),
             stop: None,
             backoff: 0,
         }
     }
 
+    /// The distance of the stop to the price of the order that set it.
+    fn stop(&self) -> Option<Monetary> {
+        distance(&self.safe, STOP, || self.trader.stop())
+    }
+
     fn evaluate(&mut self, (value, output): <Self::Indicators as Indicator>::Output) -> Option<Order> {
-        if self.backoff == 0 {
-            if let Some(stop) = self.stop {
-                match self.safe {
-                    Safe::Base => {
-                        if value >= stop {
-                            self.stop = None;
-                            self.backoff = BACKOFF;
-                            return Some(Order::Limit(Action::Buy, 1.0, value));
-                        }
-                    },
-                    Safe::Quote => {
-                        if value <= stop {
-                            self.stop = None;
-                            self.backoff = BACKOFF;
-                            return Some(Order::Limit(Action::Sell, 1.0, value));
-                        }
-                    }
-                    Safe::None => {}
-                }
-            }
+        let trader = &mut self.trader;
+        decide(&self.safe, STOP, BACKOFF, &mut self.stop, &mut self.backoff, value, || trader.evaluate(output))
+    }
+}
 
-            let order = self.trader.evaluate(output);
-            if let Some(order) = &order {
-                match (&self.safe, order) {
-                    (Safe::Quote, Order::Limit(Action::Buy, _, value)) => {
-                        self.stop = Some(if let Some(stop) = self.stop {
-                            stop.min(*value * STOP)
-                        } else {
-                            *value * STOP
-                        });
-                    },
-                    (Safe::Base, Order::Limit(Action::Sell, _, value)) => {
-                        self.stop = Some(if let Some(stop) = self.stop {
-                            stop.max(*value / STOP)
-                        } else {
-                            *value / STOP
-                        });
-                    },
-                    _ => {}
-                }
-            }
-            order
+/// Distance of a stop at `factor` of the price, or that of the inner trader without a safe asset.
+pub(super) fn distance<F>(safe: &Safe, factor: Monetary, inner: F) -> Option<Monetary>
+where
+    F: FnOnce() -> Option<Monetary>,
+{
+    match safe {
+        Safe::Quote => Some(1.0 - factor),
+        Safe::Base => Some(1.0 / factor - 1.0),
+        Safe::None => inner(),
+    }
+}
+
+/// Moves everything into the safe asset once the value crosses the stop, and backs off for `period` evaluations.
+/// Orders of the inner trader away from the safe asset set the stop at `factor` of their price. Shared with the dynamic trader.
+pub(super) fn decide<F>(
+    safe: &Safe,
+    factor: Monetary,
+    period: usize,
+    stop: &mut Option<Monetary>,
+    backoff: &mut usize,
+    value: Monetary,
+    decide: F,
+) -> Option<Order>
+where
+    F: FnOnce() -> Option<Order>,
+{
+    if *backoff != 0 {
+        *backoff -= 1;
+        audit::note("StopLoss", Verdict::Suppressed);
+        return None;
+    }
 
-        } else {
-            self.backoff -= 1;
-            None
+    if let Some(limit) = *stop {
+        let triggered = match safe {
+            Safe::Base if value >= limit => Some(Order::Limit(Action::Buy, 1.0, value)),
+            Safe::Quote if value <= limit => Some(Order::Limit(Action::Sell, 1.0, value)),
+            _ => None,
+        };
+        if let Some(order) = triggered {
+            *stop = None;
+            *backoff = period;
+            audit::note("StopLoss", Verdict::Triggered { order: order.clone() });
+            return Some(order);
         }
     }
-}
\ No newline at end of file
+
+    let order = decide();
+    match (safe, &order) {
+        (Safe::Quote, Some(Order::Limit(Action::Buy, _, price))) => {
+            *stop = Some(stop.map_or(*price * factor, |stop| stop.min(*price * factor)));
+        },
+        (Safe::Base, Some(Order::Limit(Action::Sell, _, price))) => {
+            *stop = Some(stop.map_or(*price / factor, |stop| stop.max(*price / factor)));
+        },
+        _ => {}
+    }
+    order
+}
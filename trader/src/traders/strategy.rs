@@ -0,0 +1,149 @@
+use super::{audit, Audit, Order, Trader};
+use crate::economy::{Bar, Monetary};
+use crate::indicators::Indicator;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value as State;
+
+/// Object safe wrapper around a trader and its indicators, so different traders can run side by side.
+pub trait Strategy {
+    /// Evaluates the bar of a market since the previous evaluation.
+    fn evaluate(&mut self, bar: &Bar) -> Option<Order>;
+
+    /// Like `evaluate`, but also explains the decision if any trader of the chain wanted to trade.
+    fn evaluate_audited(&mut self, bar: &Bar) -> (Option<Order>, Option<Audit>) {
+        (self.evaluate(bar), None)
+    }
+
+    /// Feeds a bar of the history to the indicators only. The traders don't decide on it,
+    /// so wrappers don't keep stops or backoffs for orders that were never placed.
+    fn warm_up(&mut self, bar: &Bar);
+
+    /// Whether the indicators have seen at least one bar.
+    fn is_initialized(&self) -> bool;
+
+    /// Distance from the price to the stop of a position, relative to the price, if the strategy keeps a stop.
+    fn stop(&self) -> Option<Monetary> {
+        None
+    }
+
+    fn save(&self) -> Result<State, serde_json::Error>;
+    fn restore(&mut self, state: State) -> Result<(), serde_json::Error>;
+}
+
+pub struct TraderStrategy<T>
+where
+    T: Trader,
+{
+    trader: T,
+    indicators: Option<T::Indicators>,
+}
+
+impl<T> TraderStrategy<T>
+where
+    T: Trader + Serialize + DeserializeOwned + 'static,
+    T::Indicators: Serialize + DeserializeOwned,
+    <T::Indicators as Indicator>::Output: Serialize,
+{
+    pub fn new(base: &str, quote: &str) -> TraderStrategy<T> {
+        TraderStrategy {
+            trader: T::initialize(base, quote),
+            indicators: None,
+        }
+    }
+
+    pub fn boxed(base: &str, quote: &str) -> Box<dyn Strategy> {
+        Box::new(TraderStrategy::<T>::new(base, quote))
+    }
+}
+
+impl<T> Strategy for TraderStrategy<T>
+where
+    T: Trader + Serialize + DeserializeOwned,
+    T::Indicators: Serialize + DeserializeOwned,
+    <T::Indicators as Indicator>::Output: Serialize,
+{
+    fn evaluate(&mut self, bar: &Bar) -> Option<Order> {
+        if let Some(indicators) = &mut self.indicators {
+            self.trader.evaluate(indicators.evaluate_bar(bar))
+        } else {
+            self.indicators = Some(T::Indicators::initialize_bar(bar));
+            None
+        }
+    }
+
+    fn evaluate_audited(&mut self, bar: &Bar) -> (Option<Order>, Option<Audit>) {
+        if self.indicators.is_none() {
+            return (self.evaluate(bar), None);
+        }
+        let output = self.indicators.as_mut().unwrap().evaluate_bar(bar);
+        let serialized = serde_json::to_value(&output).unwrap_or(State::Null);
+        let trader = &mut self.trader;
+        let (order, notes) = audit::collect(|| trader.evaluate(output));
+        (order.clone(), Audit::new(serialized, notes, order))
+    }
+
+    fn warm_up(&mut self, bar: &Bar) {
+        if let Some(indicators) = &mut self.indicators {
+            indicators.evaluate_bar(bar);
+        } else {
+            self.indicators = Some(T::Indicators::initialize_bar(bar));
+        }
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.indicators.is_some()
+    }
+
+    fn stop(&self) -> Option<Monetary> {
+        self.trader.stop()
+    }
+
+    fn save(&self) -> Result<State, serde_json::Error> {
+        serde_json::to_value((&self.trader, &self.indicators))
+    }
+
+    fn restore(&mut self, state: State) -> Result<(), serde_json::Error> {
+        let (trader, indicators) = serde_json::from_value(state)?;
+        self.trader = trader;
+        self.indicators = indicators;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_warm_up() {
+    use super::{Backoff, DynamicTrader, RSITrader, StopLoss, TraderConfig};
+
+    type Static = StopLoss<Backoff<RSITrader<20, 5, 30.0, 70.0, 0.1>, 3>, "USDT", 0.95, 5>;
+    let config: TraderConfig = serde_json::from_str(r#"{
+        "type": "StopLoss", "safe": "USDT", "stop": 0.95, "backoff": 5,
+        "trader": {
+            "type": "Backoff", "backoff": 3,
+            "trader": { "type": "RSITrader", "period": 20, "scale": 5, "buy": 30.0, "sell": 70.0, "fraction": 0.1 }
+        }
+    }"#).unwrap();
+    let bars = (0..2000)
+        .map(|i| Bar::flat(100.0 + 10.0 * (i as f64 / 15.0).sin() + 3.0 * (i as f64 / 4.0).cos()))
+        .collect::<Vec<Bar>>();
+    let fresh = TraderStrategy::<Static>::new("BTC", "USDT").save().unwrap();
+
+    // Deciding on the same bars trades and changes the state of the traders.
+    let mut evaluated = TraderStrategy::<Static>::new("BTC", "USDT");
+    assert!(bars.iter().filter_map(|bar| evaluated.evaluate(bar)).count() > 0);
+    assert_ne!(evaluated.save().unwrap()[0], fresh[0]);
+
+    let mut fixed = TraderStrategy::<Static>::new("BTC", "USDT");
+    let mut dynamic = DynamicTrader::new(config, "BTC", "USDT");
+    for bar in &bars {
+        fixed.warm_up(bar);
+        dynamic.warm_up(bar);
+    }
+    assert!(fixed.is_initialized() && dynamic.is_initialized());
+
+    let warmed = fixed.save().unwrap();
+    assert_eq!(warmed[0], fresh[0]);
+    assert_ne!(warmed[1], fresh[1]);
+    let node = &dynamic.save().unwrap()[1]["StopLoss"];
+    assert_eq!(node["stop"], State::Null);
+    assert_eq!((&node["backoff"], &node["trader"]["Backoff"]["backoff"]), (&serde_json::json!(0), &serde_json::json!(0)));
+}
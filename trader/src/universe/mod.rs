@@ -0,0 +1,121 @@
+mod rules;
+
+pub use rules::{Trading, MinTrades, MinQuoteVolume, MaxSpread, Whitelist, Blacklist, QuoteAssets, OrderTypes, TopByVolume, ConnectedTo};
+
+use crate::economy::Monetary;
+use crate::environments::MarketData;
+use binance_async::model::PriceStats;
+
+/// A market that may be traded, together with its statistics of the last 24 hours.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub market: MarketData,
+    pub stats: Option<PriceStats>,
+}
+
+impl Candidate {
+    /// Traded volume of the last 24 hours, in the quote asset.
+    pub fn quote_volume(&self) -> Monetary {
+        self.stats
+            .as_ref()
+            .map_or(0.0, |stats| stats.volume * stats.weighted_avg_price)
+    }
+
+    /// Difference between the best ask and bid, relative to their mid price.
+    pub fn spread(&self) -> Option<Monetary> {
+        let stats = self.stats.as_ref()?;
+        let mid = (stats.ask_price + stats.bid_price) / 2.0;
+        if mid > 0.0 {
+            Some((stats.ask_price - stats.bid_price) / mid)
+        } else {
+            None
+        }
+    }
+}
+
+/// Decides which markets are traded.
+/// Selectors are composed with tuples, which apply their selectors from left to right.
+pub trait UniverseSelector {
+    fn select(&self, candidates: Vec<Candidate>) -> Vec<Candidate>;
+}
+
+macro_rules! peel {
+    ( $name:ident, $($other:ident,)* ) => (tuple! { $($other,)* })
+}
+
+macro_rules! tuple {
+    () => ();
+    ( $($name:ident,)+ ) => {
+        impl<$($name: UniverseSelector,)+> UniverseSelector for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn select(&self, candidates: Vec<Candidate>) -> Vec<Candidate> {
+                let ($($name,)+) = self;
+                $(let candidates = $name.select(candidates);)+
+                candidates
+            }
+        }
+        peel! { $($name,)+ }
+    };
+}
+
+tuple! {T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11,}
+
+#[tokio::test]
+async fn test_select() {
+    use binance_async::model::Symbol;
+
+    let candidate = |base: &str, quote: &str, volume: Monetary| Candidate {
+        market: Symbol {
+            symbol: format!("{}{}", base, quote),
+            status: String::from("TRADING"),
+            base_asset: String::from(base),
+            base_asset_precision: 8,
+            quote_asset: String::from(quote),
+            quote_precision: 8,
+            order_types: vec![String::from("LIMIT")],
+            iceberg_allowed: false,
+            filters: Vec::new(),
+        },
+        stats: Some(PriceStats {
+            symbol: format!("{}{}", base, quote),
+            price_change: 0.0,
+            price_change_percent: 0.0,
+            weighted_avg_price: 1.0,
+            prev_close_price: 1.0,
+            last_price: 1.0,
+            bid_price: 0.999,
+            ask_price: 1.001,
+            open_price: 1.0,
+            high_price: 1.0,
+            low_price: 1.0,
+            volume,
+            open_time: 0,
+            close_time: 0,
+            first_id: 0,
+            last_id: 0,
+            count: 10000,
+        }),
+    };
+
+    let selector = (
+        Trading,
+        Blacklist(vec![String::from("DOGE")]),
+        MinQuoteVolume(1000.0),
+        ConnectedTo(String::from("USDT")),
+        TopByVolume(2),
+    );
+    let selected = selector.select(vec![
+        candidate("BTC", "USDT", 5000.0),
+        candidate("ETH", "USDT", 4000.0),
+        candidate("ETH", "BTC", 3000.0),
+        candidate("DOGE", "USDT", 9000.0),
+        candidate("XYZ", "BTC", 2000.0),
+        candidate("ABC", "USDT", 10.0),
+    ]);
+
+    let symbols = selected
+        .iter()
+        .map(|candidate| candidate.market.symbol.as_str())
+        .collect::<Vec<&str>>();
+    assert_eq!(symbols, vec!["BTCUSDT", "ETHUSDT"]);
+}
@@ -0,0 +1,144 @@
+use super::{Candidate, UniverseSelector};
+use crate::economy::Monetary;
+use std::collections::HashSet;
+
+fn matches(candidate: &Candidate, names: &[String]) -> bool {
+    names.iter().any(|name|
+        *name == candidate.market.symbol ||
+        *name == candidate.market.base_asset ||
+        *name == candidate.market.quote_asset
+    )
+}
+
+/// Markets that are currently open for trading.
+pub struct Trading;
+
+impl UniverseSelector for Trading {
+    fn select(&self, candidates: Vec<Candidate>) -> Vec<Candidate> {
+        candidates
+            .into_iter()
+            .filter(|candidate| candidate.market.status == "TRADING")
+            .collect()
+    }
+}
+
+/// Markets with at least this many trades in the last 24 hours.
+pub struct MinTrades(pub u64);
+
+impl UniverseSelector for MinTrades {
+    fn select(&self, candidates: Vec<Candidate>) -> Vec<Candidate> {
+        candidates
+            .into_iter()
+            .filter(|candidate| candidate.stats.as_ref().map_or(false, |stats| stats.count >= self.0))
+            .collect()
+    }
+}
+
+/// Markets with at least this volume in the last 24 hours, in the quote asset.
+pub struct MinQuoteVolume(pub Monetary);
+
+impl UniverseSelector for MinQuoteVolume {
+    fn select(&self, candidates: Vec<Candidate>) -> Vec<Candidate> {
+        candidates
+            .into_iter()
+            .filter(|candidate| candidate.quote_volume() >= self.0)
+            .collect()
+    }
+}
+
+/// Markets whose spread, relative to the mid price, is at most this large.
+pub struct MaxSpread(pub Monetary);
+
+impl UniverseSelector for MaxSpread {
+    fn select(&self, candidates: Vec<Candidate>) -> Vec<Candidate> {
+        candidates
+            .into_iter()
+            .filter(|candidate| candidate.spread().map_or(false, |spread| spread <= self.0))
+            .collect()
+    }
+}
+
+/// Only markets whose symbol or one of whose assets is listed.
+pub struct Whitelist(pub Vec<String>);
+
+impl UniverseSelector for Whitelist {
+    fn select(&self, candidates: Vec<Candidate>) -> Vec<Candidate> {
+        candidates
+            .into_iter()
+            .filter(|candidate| matches(candidate, &self.0))
+            .collect()
+    }
+}
+
+/// No markets whose symbol or one of whose assets is listed.
+pub struct Blacklist(pub Vec<String>);
+
+impl UniverseSelector for Blacklist {
+    fn select(&self, candidates: Vec<Candidate>) -> Vec<Candidate> {
+        candidates
+            .into_iter()
+            .filter(|candidate| !matches(candidate, &self.0))
+            .collect()
+    }
+}
+
+/// Markets quoted in one of the listed assets.
+pub struct QuoteAssets(pub Vec<String>);
+
+impl UniverseSelector for QuoteAssets {
+    fn select(&self, candidates: Vec<Candidate>) -> Vec<Candidate> {
+        candidates
+            .into_iter()
+            .filter(|candidate| self.0.contains(&candidate.market.quote_asset))
+            .collect()
+    }
+}
+
+/// Markets that permit all of the listed order types, e.g. `LIMIT`.
+pub struct OrderTypes(pub Vec<String>);
+
+impl UniverseSelector for OrderTypes {
+    fn select(&self, candidates: Vec<Candidate>) -> Vec<Candidate> {
+        candidates
+            .into_iter()
+            .filter(|candidate| self.0.iter().all(|order_type| candidate.market.order_types.contains(order_type)))
+            .collect()
+    }
+}
+
+/// The markets with the highest quote volume.
+/// Volumes in different quote assets are compared as they are, so this is best combined with `QuoteAssets`.
+pub struct TopByVolume(pub usize);
+
+impl UniverseSelector for TopByVolume {
+    fn select(&self, mut candidates: Vec<Candidate>) -> Vec<Candidate> {
+        candidates.sort_by(|a, b| b.quote_volume().total_cmp(&a.quote_volume()));
+        candidates.truncate(self.0);
+        candidates
+    }
+}
+
+/// Markets whose assets are both either the given asset, or traded directly against it in one of the candidates.
+pub struct ConnectedTo(pub String);
+
+impl UniverseSelector for ConnectedTo {
+    fn select(&self, candidates: Vec<Candidate>) -> Vec<Candidate> {
+        let mut connected = HashSet::new();
+        connected.insert(self.0.clone());
+        for candidate in &candidates {
+            if candidate.market.base_asset == self.0 {
+                connected.insert(candidate.market.quote_asset.clone());
+            } else if candidate.market.quote_asset == self.0 {
+                connected.insert(candidate.market.base_asset.clone());
+            }
+        }
+
+        candidates
+            .into_iter()
+            .filter(|candidate|
+                connected.contains(&candidate.market.base_asset) &&
+                connected.contains(&candidate.market.quote_asset)
+            )
+            .collect()
+    }
+}